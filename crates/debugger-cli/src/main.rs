@@ -1,11 +1,27 @@
-use std::{convert::Infallible, fmt, path::PathBuf, process::ExitCode, str::FromStr};
+use std::{
+    convert::Infallible,
+    fmt,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
 
 use clap::{Parser, Subcommand};
 use clap_repl::{
     ClapEditor,
     reedline::{DefaultPrompt, DefaultPromptSegment},
 };
-use debugger_core::{ContinueExecutionOutcome, Debugger, watchpoint::*};
+use debugger_core::{
+    ContinueExecutionOutcome, Debugger, Error,
+    backtrace::{StackFrame, UnwindStopReason},
+    breakpoint::{BreakpointCondition, BreakpointGroupId, BreakpointId},
+    elf_summary::ElfSummary,
+    expression::{Expression, UnaryOperator},
+    line_table::SourceLocation,
+    variables::Inspector,
+    watchpoint::*,
+};
 use envconfig::Envconfig;
 use log::LevelFilter;
 
@@ -26,33 +42,109 @@ struct ProgramArgs {
 enum ReplCommand {
     #[clap(alias = "c")]
     Continue,
+    /// Step to the next source line, entering called functions
     #[clap(alias = "s")]
-    Step {
+    Step,
+    /// Step to the next source line, stepping over called functions
+    #[clap(alias = "n")]
+    Next,
+    /// Step machine instructions
+    #[clap(alias = "si")]
+    Stepi {
         #[clap(default_value_t = 1)]
         steps: u32,
     },
+    /// Run until the selected frame returns
+    Finish,
+    /// Show the stack frames that led to the current location
+    #[clap(alias = "bt")]
+    Backtrace,
+    /// Select a stack frame by its number, or show the selected frame
+    #[clap(alias = "f")]
+    Frame { index: Option<usize> },
+    /// Select the frame of a caller
+    Up {
+        #[clap(default_value_t = 1)]
+        count: usize,
+    },
+    /// Select the frame of a callee
+    Down {
+        #[clap(default_value_t = 1)]
+        count: usize,
+    },
     #[clap(alias = "b")]
     Break {
         #[clap(value_parser=clap::value_parser!(BreakpointLocation))]
-        /// An address where the breakpoint will be placed as a decimal (123) or hexadecimal number (0x123). The prefix "text:" can be used to specify an offset relative to the start of the text section. Also symbol names can be used.
+        /// An address where the breakpoint will be placed as a decimal (123) or hexadecimal number (0x123). The prefix "text:" can be used to specify an offset relative to the start of the .text section of the executable, e.g. text:0x2a, so the same offset works regardless of where the executable is loaded. Also symbol names, source lines (file.c:12) and lines relative to the start of a function (func:+3) can be used.
         location: BreakpointLocation,
-        #[clap(value_parser=clap::value_parser!(BreakpointType), default_value_t=BreakpointType::Software)]
-        breakpoint_type: BreakpointType,
+        /// The breakpoint type (software or hardware), followed by "if" and a condition like `if count > 3`
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+        arguments: Vec<String>,
+    },
+    /// Set a breakpoint on every function whose demangled name matches a regular expression
+    Rbreak { regex: String },
+    #[clap(alias = "d")]
+    Delete {
+        #[clap(value_parser=clap::value_parser!(BreakpointSelector))]
+        /// A breakpoint number or "group:" followed by the number of a breakpoint group
+        target: BreakpointSelector,
+    },
+    Disable {
+        #[clap(value_parser=clap::value_parser!(BreakpointSelector))]
+        /// A breakpoint number or "group:" followed by the number of a breakpoint group
+        target: BreakpointSelector,
+    },
+    Enable {
+        #[clap(value_parser=clap::value_parser!(BreakpointSelector))]
+        /// A breakpoint number or "group:" followed by the number of a breakpoint group
+        target: BreakpointSelector,
     },
     #[clap(alias = "w")]
     Watch {
         #[clap(value_parser=clap::value_parser!(BreakpointLocation))]
-        /// An address where the breakpoint will be placed as a decimal (123) or hexadecimal number (0x123). The prefix "text:" can be used to specify an offset relative to the start of the text section. Also symbol names can be used.
+        /// An address where the breakpoint will be placed as a decimal (123) or hexadecimal number (0x123). The prefix "text:" can be used to specify an offset relative to the start of the .text section of the executable, e.g. text:0x2a, so the same offset works regardless of where the executable is loaded. Also symbol names and expressions like `pt.x` can be used.
         location: BreakpointLocation,
         #[clap(value_parser=clap::value_parser!(WatchCondition))]
         condition: WatchCondition,
-        length: usize,
+        #[clap(value_parser=clap::value_parser!(WatchLength))]
+        /// The number of bytes to watch, 1, 2, 4 or 8. Defaults to the size of the variable or expression.
+        length: Option<WatchLength>,
+    },
+    /// List source lines around a location, or continue the previous listing
+    #[clap(alias = "l")]
+    List {
+        #[clap(value_parser=clap::value_parser!(BreakpointLocation))]
+        location: Option<BreakpointLocation>,
+    },
+    /// Examine memory as hexadecimal bytes
+    X {
+        #[clap(value_parser=clap::value_parser!(BreakpointLocation))]
+        /// An address, text offset, symbol name or expression like `$rsp+8` or `&pt`. Thread-local variables are resolved for the current thread.
+        location: BreakpointLocation,
+        /// The number of bytes to show. Defaults to the size of the variable or expression.
+        length: Option<usize>,
+    },
+    #[clap(alias = "p")]
+    Print {
+        /// An expression like `pt.x * 2`, `*(u64*)($rsp+8)` or `count = 3`. Variables without debug information are
+        /// shown as integers of their size.
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        expression: Vec<String>,
+    },
+    /// Show the type of an expression
+    Ptype {
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        expression: Vec<String>,
     },
     #[clap(alias = "i")]
     Info {
         #[command(subcommand)]
         command: InfoCommand,
     },
+    Set {
+        #[command(subcommand)]
+        command: SetCommand,
+    },
     #[clap(alias = "q")]
     Quit,
 }
@@ -72,6 +164,23 @@ impl FromStr for WatchCondition {
     }
 }
 
+#[derive(Debug, Clone)]
+struct WatchLength(WatchpointLength);
+
+impl FromStr for WatchLength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let length = s
+            .parse::<usize>()
+            .map_err(|err| format!("Invalid watchpoint length {s}: {err}"))?;
+
+        WatchpointLength::try_from(length)
+            .map(WatchLength)
+            .map_err(|err| err.to_string())
+    }
+}
+
 #[derive(Debug, Clone)]
 enum BreakpointType {
     Hardware,
@@ -88,13 +197,13 @@ impl fmt::Display for BreakpointType {
 }
 
 impl FromStr for BreakpointType {
-    type Err = &'static str;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "hardware" | "hard" | "h" => Ok(BreakpointType::Hardware),
             "software" | "soft" | "s" => Ok(BreakpointType::Software),
-            other => Err("Unknown breakpoint type {other}"),
+            other => Err(format!("Unknown breakpoint type {other}")),
         }
     }
 }
@@ -104,6 +213,9 @@ enum BreakpointLocation {
     Address(u64),
     TextOffset(u64),
     Symbol(String),
+    Line { file: PathBuf, line: u64 },
+    FunctionLine { function: String, line_offset: u64 },
+    Expression(Expression),
 }
 
 impl FromStr for BreakpointLocation {
@@ -117,8 +229,53 @@ impl FromStr for BreakpointLocation {
             Ok(BreakpointLocation::TextOffset(offset))
         } else if let Ok(offset) = clap_num::maybe_hex::<u64>(s) {
             Ok(BreakpointLocation::Address(offset))
+        } else if let Some((function, line_offset)) = s
+            .rsplit_once(":+")
+            .and_then(|(function, offset)| Some((function, offset.parse().ok()?)))
+        {
+            Ok(BreakpointLocation::FunctionLine {
+                function: function.to_owned(),
+                line_offset,
+            })
+        } else if let Some((file, line)) = s
+            .rsplit_once(':')
+            .filter(|(file, _)| !file.is_empty() && !file.ends_with(':'))
+            .and_then(|(file, line)| Some((file, line.parse().ok()?)))
+        {
+            Ok(BreakpointLocation::Line {
+                file: PathBuf::from(file),
+                line,
+            })
+        } else {
+            // Symbols that are not valid expressions, e.g. with templates, are still looked up by name
+            match Expression::parse(s) {
+                Ok(Expression::Identifier(name)) => Ok(BreakpointLocation::Symbol(name)),
+                Ok(expression) => Ok(BreakpointLocation::Expression(expression)),
+                Err(_) => Ok(BreakpointLocation::Symbol(s.to_owned())),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BreakpointSelector {
+    Breakpoint(BreakpointId),
+    Group(BreakpointGroupId),
+}
+
+impl FromStr for BreakpointSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(group) = s.strip_prefix("group:") {
+            group
+                .parse()
+                .map(BreakpointSelector::Group)
+                .map_err(|_| format!("Invalid breakpoint group {group}"))
         } else {
-            Ok(BreakpointLocation::Symbol(s.to_owned()))
+            s.parse()
+                .map(BreakpointSelector::Breakpoint)
+                .map_err(|_| format!("Invalid breakpoint number {s}"))
         }
     }
 }
@@ -126,6 +283,437 @@ impl FromStr for BreakpointLocation {
 #[derive(Debug, Subcommand)]
 enum InfoCommand {
     Functions,
+    Variables,
+    #[command(name = "sharedlibrary", alias = "shared")]
+    SharedLibrary,
+    #[clap(alias = "b")]
+    Breakpoints,
+    /// Registers of the selected frame
+    #[clap(alias = "r")]
+    Registers,
+    /// Local variables of the selected frame
+    Locals,
+    /// Arguments of the selected frame
+    Args,
+    /// Sections of the executable
+    Sections,
+    /// Program headers of the executable
+    Segments,
+    /// Entry point of the executable
+    Entry,
+    /// Dynamic section of the executable
+    Dynamic,
+    /// Shared libraries required by the executable
+    Needed,
+    /// GNU build ID of the executable
+    #[command(name = "build-id")]
+    BuildId,
+    /// Pretty printers in the order they are tried
+    #[command(name = "pretty-printer")]
+    PrettyPrinter,
+    Proc {
+        #[command(subcommand)]
+        command: ProcCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProcCommand {
+    /// Memory mappings of the tracee
+    Mappings,
+}
+
+#[derive(Debug, Subcommand)]
+enum SetCommand {
+    /// Replace the prefix FROM of source paths with TO
+    #[command(name = "substitute-path")]
+    SubstitutePath { from: PathBuf, to: PathBuf },
+    /// Directories to search for separate debug files, separated by ":"
+    #[command(name = "debug-file-directory")]
+    DebugFileDirectory { directories: String },
+    /// Show raw symbol names instead of demangled ones
+    MangledNames {
+        #[clap(value_parser=clap::value_parser!(OnOff))]
+        value: OnOff,
+    },
+    /// Show values of Rust standard library types and enums in a readable form
+    #[command(name = "pretty-printers")]
+    PrettyPrinters {
+        #[clap(value_parser=clap::value_parser!(OnOff))]
+        value: OnOff,
+    },
+    /// Assign a value to a variable or register, e.g. `set var pt.x = 3`
+    #[clap(alias = "variable")]
+    Var {
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        expression: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OnOff(bool);
+
+impl FromStr for OnOff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "on" | "true" | "1" => Ok(OnOff(true)),
+            "off" | "false" | "0" => Ok(OnOff(false)),
+            other => Err(format!("Expected on or off, got {other}")),
+        }
+    }
+}
+
+fn resolve_location(debugger: &Debugger, location: &BreakpointLocation) -> Result<u64, String> {
+    let address = match location {
+        BreakpointLocation::Address(address) => Some(*address),
+        BreakpointLocation::TextOffset(offset) => {
+            return debugger
+                .get_text_offset_address(*offset)
+                .map_err(|err| err.to_string());
+        }
+        BreakpointLocation::Symbol(symbol_name) => debugger
+            .resolve_function_addresses(symbol_name)
+            .first()
+            .copied(),
+        BreakpointLocation::Line { file, line } => debugger
+            .resolve_line_addresses(file, *line)
+            .first()
+            .copied(),
+        BreakpointLocation::FunctionLine {
+            function,
+            line_offset,
+        } => debugger
+            .resolve_symbol_runtime_address(function)
+            .and_then(|address| debugger.find_source_location(address))
+            .and_then(|source_location| {
+                debugger
+                    .resolve_line_addresses(
+                        source_location.path,
+                        source_location.line + line_offset,
+                    )
+                    .first()
+                    .copied()
+            }),
+        BreakpointLocation::Expression(expression) => {
+            let inspector = debugger.inspector();
+            return inspector
+                .evaluate(expression)
+                .and_then(|value| inspector.as_address(&value))
+                .map_err(|err| err.to_string());
+        }
+    };
+
+    address.ok_or_else(|| "No symbol found".to_owned())
+}
+
+// Resolves a location for reading memory. Variables and other values stored in memory, like `pt.x` or `*p`, resolve
+// to their address and size, other values like `$rsp+8` or `&pt` are used as an address.
+fn resolve_data_location(
+    debugger: &Debugger,
+    location: &BreakpointLocation,
+) -> Result<(u64, Option<u64>), String> {
+    let expression = match location {
+        BreakpointLocation::Symbol(symbol_name) => Expression::Identifier(symbol_name.clone()),
+        BreakpointLocation::Expression(expression) => expression.clone(),
+        location => return resolve_location(debugger, location).map(|address| (address, None)),
+    };
+
+    let inspector = debugger.inspector();
+    let value = inspector
+        .evaluate(&expression)
+        .map_err(|err| err.to_string())?;
+    if let Some(address) = value.address() {
+        let size = inspector
+            .type_size(value.ty)
+            .map_err(|err| err.to_string())?;
+        return Ok((address, size));
+    }
+
+    let address = inspector
+        .as_address(&value)
+        .map_err(|err| err.to_string())?;
+    let pointee_size = inspector
+        .evaluate(&Expression::Unary {
+            operator: UnaryOperator::Deref,
+            operand: Box::new(expression),
+        })
+        .and_then(|pointee| inspector.type_size(pointee.ty))
+        .ok()
+        .flatten();
+
+    Ok((address, pointee_size))
+}
+
+// The text left of the `=` of an assignment, which is the first one that is not part of a comparison
+fn assignment_target(text: &str) -> &str {
+    let bytes = text.as_bytes();
+    let position = (0..bytes.len()).find(|&index| {
+        bytes[index] == b'='
+            && !matches!(
+                index.checked_sub(1).map(|index| bytes[index]),
+                Some(b'=' | b'!' | b'<' | b'>')
+            )
+            && bytes.get(index + 1) != Some(&b'=')
+    });
+
+    position.map_or(text, |position| text[..position].trim_end())
+}
+
+// Splits the arguments of break into the breakpoint type and the condition following "if"
+fn parse_break_arguments(
+    arguments: &[String],
+) -> Result<(BreakpointType, Option<BreakpointCondition>), String> {
+    let (breakpoint_type, arguments) = match arguments.split_first() {
+        Some((first, rest)) if first != "if" => (first.parse()?, rest),
+        _ => (BreakpointType::Software, arguments),
+    };
+
+    match arguments.split_first() {
+        None => Ok((breakpoint_type, None)),
+        Some((first, condition)) if first == "if" && !condition.is_empty() => {
+            let condition =
+                BreakpointCondition::parse(&condition.join(" ")).map_err(|err| err.to_string())?;
+            Ok((breakpoint_type, Some(condition)))
+        }
+        Some(_) => Err("Expected \"if\" followed by a condition".to_owned()),
+    }
+}
+
+fn print_variables(inspector: &Inspector, arguments: bool) {
+    let variables = if arguments {
+        inspector.arguments()
+    } else {
+        inspector.locals()
+    };
+
+    match variables {
+        Ok(variables) if variables.is_empty() && arguments => println!("No arguments."),
+        Ok(variables) if variables.is_empty() => println!("No locals."),
+        Ok(variables) => {
+            for variable in variables {
+                println!(
+                    "{} = {}",
+                    variable.name,
+                    inspector.format_value(&variable.value)
+                );
+            }
+        }
+        Err(err) => println!("Failed to read variables: {err}"),
+    }
+}
+
+fn executable_elf_summary(debugger: &Debugger) -> Option<ElfSummary> {
+    match debugger.executable_elf_summary() {
+        Ok(summary) => Some(summary),
+        Err(err) => {
+            println!("Failed to read the ELF headers of the executable: {err}");
+            None
+        }
+    }
+}
+
+fn print_hex_dump(address: u64, data: &[u8]) {
+    for (index, line) in data.chunks(16).enumerate() {
+        let bytes: Vec<String> = line.iter().map(|byte| format!("{byte:02x}")).collect();
+        println!(
+            "0x{:012x}: {}",
+            address + index as u64 * 16,
+            bytes.join(" ")
+        );
+    }
+}
+
+// The number of lines shown before and after the current line when the tracee stops
+const STOP_CONTEXT_LINES: u64 = 2;
+// The number of lines shown by the list command
+const LIST_LINES: u64 = 10;
+
+// Returns whether any lines were printed, `None` if the file could not be read
+fn print_source_lines(
+    debugger: &Debugger,
+    path: &Path,
+    lines: RangeInclusive<u64>,
+    current_line: Option<u64>,
+) -> Option<bool> {
+    match debugger.read_source_lines(path, lines) {
+        Ok(lines) => {
+            for (number, line) in &lines {
+                let marker = if Some(*number) == current_line {
+                    "=>"
+                } else {
+                    ""
+                };
+                println!("{marker:<2} {number:<5} {line}");
+            }
+            Some(!lines.is_empty())
+        }
+        Err(err) => {
+            println!("{err}");
+            None
+        }
+    }
+}
+
+// Reports why the tracee stopped after it was resumed, quits once the tracee is gone
+fn report_outcome(debugger: &Debugger, outcome: Result<ContinueExecutionOutcome, Error>) {
+    match outcome {
+        Ok(ContinueExecutionOutcome::ProcessExited(code)) => {
+            println!("Process exited with code {code}. Quitting...");
+            std::process::exit(0);
+        }
+        Ok(ContinueExecutionOutcome::ProcessKilled(signal)) => {
+            println!("Process was killed by signal {signal}. Quitting...");
+            std::process::exit(0);
+        }
+        Ok(ContinueExecutionOutcome::BreakpointHit(address)) => {
+            match debugger.breakpoint_at(address) {
+                Some(breakpoint) => println!(
+                    "Hit breakpoint {} at address {}",
+                    breakpoint.id,
+                    format_address(debugger, address)
+                ),
+                None => println!(
+                    "Hit breakpoint at address {}",
+                    format_address(debugger, address)
+                ),
+            }
+        }
+        Ok(ContinueExecutionOutcome::WatchpointHit((address, watchpoint))) => {
+            println!(
+                "Hit watchpoint {watchpoint:?} at address {}",
+                format_address(debugger, address)
+            );
+            if let Ok(pc) = debugger.get_tracee_pc() {
+                println!("pc now at {}", format_address(debugger, pc));
+            }
+        }
+        Ok(ContinueExecutionOutcome::SignalReceived(signal)) => {
+            println!("Process received signal {signal}");
+        }
+        Ok(ContinueExecutionOutcome::Syscall(syscall)) => {
+            println!("Stopped at syscall {syscall:?}");
+        }
+        Ok(ContinueExecutionOutcome::Stepped(pc)) => {
+            println!(
+                "Stopped after step, pc now at {}",
+                format_address(debugger, pc)
+            );
+        }
+        // Library loads are only reported to observers asking for them, the CLI has none
+        Ok(ContinueExecutionOutcome::LibrariesLoaded(_) | ContinueExecutionOutcome::Other) => {}
+        Err(err) => {
+            println!("Got error while continuing execution: {err}");
+            std::process::exit(0);
+        }
+    }
+    print_stop_source_context(debugger);
+}
+
+// Shows the source around the current pc after the tracee stopped
+fn print_stop_source_context(debugger: &Debugger) {
+    if let Ok(pc) = debugger.get_tracee_pc() {
+        print_source_context(debugger, debugger.find_source_location(pc));
+    }
+}
+
+fn print_source_context(debugger: &Debugger, source_location: Option<SourceLocation>) {
+    let Some(source_location) = source_location else {
+        return;
+    };
+
+    let line = source_location.line;
+    println!("{}:{line}", source_location.path.display());
+    print_source_lines(
+        debugger,
+        source_location.path,
+        line.saturating_sub(STOP_CONTEXT_LINES).max(1)..=line + STOP_CONTEXT_LINES,
+        Some(line),
+    );
+}
+
+// Finds the source file and line a list command without a previous listing is centered on
+fn resolve_list_location(
+    debugger: &Debugger,
+    location: Option<&BreakpointLocation>,
+) -> Option<(PathBuf, u64)> {
+    if let Some(BreakpointLocation::Line { file, line }) = location {
+        return Some((debugger.find_source_file(file)?.to_owned(), *line));
+    }
+
+    let source_location = match location {
+        Some(location) => {
+            debugger.find_source_location(resolve_location(debugger, location).ok()?)?
+        }
+        None => debugger.frame_source_location(&debugger.selected_frame().ok()?)?,
+    };
+
+    Some((source_location.path.to_owned(), source_location.line))
+}
+
+fn print_frame(debugger: &Debugger, frame: &StackFrame) {
+    let mut line = format!("#{:<3} 0x{:012x}", frame.index, frame.pc);
+    match debugger.inlined_subroutine(frame) {
+        Some(subroutine) => {
+            let name = subroutine.name.as_deref().unwrap_or("??");
+            line += &format!(" in {name} [inlined]");
+        }
+        None => {
+            if let Some(symbol) = debugger.symbolize(frame.lookup_address()) {
+                line += &format!(" in {}", symbol.name);
+            }
+        }
+    }
+    if let Some(source_location) = debugger.frame_source_location(frame) {
+        line += &format!(
+            " at {}:{}",
+            source_location.path.display(),
+            source_location.line
+        );
+    }
+
+    println!("{line}");
+}
+
+fn select_frame(debugger: &mut Debugger, index: usize) {
+    match debugger.select_frame(index) {
+        Ok(frame) => {
+            print_frame(debugger, &frame);
+            print_source_context(debugger, debugger.frame_source_location(&frame));
+        }
+        Err(err) => println!("Failed to select frame: {err}"),
+    }
+}
+
+fn print_breakpoint(debugger: &Debugger, id: BreakpointId) {
+    let Some(breakpoint) = debugger
+        .breakpoints()
+        .iter()
+        .find(|breakpoint| breakpoint.id == id)
+    else {
+        return;
+    };
+
+    match breakpoint.address {
+        Some(address) => match debugger.find_source_location(address) {
+            Some(source_location) => println!(
+                "Breakpoint {id} at {}: file {}, line {}.",
+                format_address(debugger, address),
+                source_location.path.display(),
+                source_location.line
+            ),
+            None => println!("Breakpoint {id} at {}", format_address(debugger, address)),
+        },
+        None => println!("Breakpoint {id} ({}) pending", breakpoint.location),
+    }
+}
+
+fn format_address(debugger: &Debugger, address: u64) -> String {
+    match debugger.symbolize(address) {
+        Some(symbol) => format!("0x{address:012x} <{symbol}>"),
+        None => format!("0x{address:012x}"),
+    }
 }
 
 fn main() -> std::process::ExitCode {
@@ -159,72 +747,189 @@ fn main() -> std::process::ExitCode {
         .with_prompt(Box::new(prompt))
         .build();
 
+    // The file and the next line to show for a list command without a location
+    let mut list_position: Option<(PathBuf, u64)> = None;
+
     rl.repl(|command| match command {
-        ReplCommand::Continue => match debugger.continue_execution() {
-            Ok(ContinueExecutionOutcome::ProcessExited(code)) => {
-                println!("Process exited with code {code}. Quitting...");
-                std::process::exit(0);
+        ReplCommand::Continue => {
+            list_position = None;
+            let outcome = debugger.continue_execution();
+            report_outcome(&debugger, outcome);
+        }
+        ReplCommand::Step => {
+            list_position = None;
+            let outcome = debugger.step_line();
+            report_outcome(&debugger, outcome);
+        }
+        ReplCommand::Next => {
+            list_position = None;
+            let outcome = debugger.next_line();
+            report_outcome(&debugger, outcome);
+        }
+        ReplCommand::Stepi { steps } => {
+            list_position = None;
+            match debugger.step_instructions(steps) {
+                Ok(new_pc) => {
+                    println!(
+                        "Stepped {steps} instructions, pc now at {}",
+                        format_address(&debugger, new_pc)
+                    );
+                    print_stop_source_context(&debugger);
+                }
+                Err(err) => {
+                    println!("Encountered error while stepping instructions: {err}");
+                }
             }
-            Ok(ContinueExecutionOutcome::BreakpointHit(address)) => {
-                println!("Hit breakpoint at address {address}");
+        }
+        ReplCommand::Finish => {
+            list_position = None;
+            if let Ok(frame) = debugger.selected_frame() {
+                print!("Run till exit from ");
+                print_frame(&debugger, &frame);
             }
-            Ok(ContinueExecutionOutcome::WatchpointHit((address, watchpoint))) => {
-                println!("Hit watchpoint {watchpoint:?} at address 0x{address:012x}");
+            match debugger.finish() {
+                Err(Error::OutermostFrame) => {
+                    println!("\"finish\" not meaningful in the outermost frame.");
+                }
+                outcome => report_outcome(&debugger, outcome),
             }
-            Ok(ContinueExecutionOutcome::Other) => {}
-            Err(err) => {
-                println!("Got error while continuing execution: {err}");
-                std::process::exit(0);
+        }
+        ReplCommand::Backtrace => match debugger.backtrace() {
+            Ok(backtrace) => {
+                for frame in &backtrace.frames {
+                    print_frame(&debugger, frame);
+                }
+                if backtrace.stop_reason != UnwindStopReason::Outermost {
+                    println!("Backtrace stopped: {}", backtrace.stop_reason);
+                }
             }
+            Err(err) => println!("Failed to create backtrace: {err}"),
         },
-        ReplCommand::Step { steps } => match debugger.step_instructions(steps) {
-            Ok(new_pc) => {
-                println!("Stepped {steps} instructions, pc now at 0x{new_pc:012x}");
+        ReplCommand::Frame { index } => {
+            let index = index.unwrap_or(debugger.selected_frame_index());
+            select_frame(&mut debugger, index);
+        }
+        ReplCommand::Up { count } => {
+            let Ok(backtrace) = debugger.backtrace() else {
+                println!("No stack");
+                return;
+            };
+            let outermost = backtrace.frames.len() - 1;
+            let selected = debugger.selected_frame_index();
+            if selected == outermost {
+                println!("Initial frame selected; you cannot go up");
+                return;
             }
-            Err(err) => {
-                println!("Encountered error while stepping instructions: {err}");
+            select_frame(&mut debugger, (selected + count).min(outermost));
+        }
+        ReplCommand::Down { count } => {
+            let selected = debugger.selected_frame_index();
+            if selected == 0 {
+                println!("Bottom (innermost) frame selected; you cannot go down");
+                return;
             }
-        },
+            select_frame(&mut debugger, selected.saturating_sub(count));
+        }
         ReplCommand::Break {
             location,
-            breakpoint_type,
+            arguments,
         } => {
-            let watchpoint = Watchpoint::Execution;
-
-            let res = match location {
-                BreakpointLocation::Address(address) => match breakpoint_type {
-                    BreakpointType::Software => debugger.set_breakpoint_at(address),
-                    BreakpointType::Hardware => debugger.set_watchpoint_at(address, watchpoint),
-                },
-                BreakpointLocation::TextOffset(offset) => match breakpoint_type {
-                    BreakpointType::Software => debugger.set_breakpoint_at_text_offset(offset),
-                    BreakpointType::Hardware => {
-                        debugger.set_watchpoint_at_text_offset(offset, watchpoint)
-                    }
-                },
-                BreakpointLocation::Symbol(symbol_name) => {
-                    match debugger.find_symbol_address_by_name(&symbol_name) {
-                        Ok(Some(address)) => match breakpoint_type {
-                            BreakpointType::Software => {
-                                debugger.set_breakpoint_at_text_offset(address)
-                            }
-                            BreakpointType::Hardware => {
-                                debugger.set_watchpoint_at_text_offset(address, watchpoint)
-                            }
-                        },
-                        Ok(None) => {
-                            println!("No symbol found");
+            let (breakpoint_type, condition) = match parse_break_arguments(&arguments) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    println!("{err}");
+                    return;
+                }
+            };
+            if condition.is_some() && matches!(breakpoint_type, BreakpointType::Hardware) {
+                println!("Conditions are only supported for software breakpoints");
+                return;
+            }
+
+            let res = match (location, breakpoint_type) {
+                (BreakpointLocation::Symbol(symbol_name), BreakpointType::Software) => {
+                    debugger.set_breakpoint_at_symbol(&symbol_name)
+                }
+                (BreakpointLocation::Line { file, line }, BreakpointType::Software) => {
+                    debugger.set_breakpoint_at_line(&file, line)
+                }
+                (
+                    BreakpointLocation::FunctionLine {
+                        function,
+                        line_offset,
+                    },
+                    BreakpointType::Software,
+                ) => debugger.set_breakpoint_at_function_line(&function, line_offset),
+                (location, breakpoint_type) => {
+                    let address = match resolve_location(&debugger, &location) {
+                        Ok(address) => address,
+                        Err(err) => {
+                            println!("{err}");
                             return;
                         }
-                        Err(err) => {
-                            println!("Got error during symbol look up: {err}");
+                    };
+
+                    match breakpoint_type {
+                        BreakpointType::Software => {
+                            debugger.set_breakpoint_at(address).map(|id| vec![id])
+                        }
+                        BreakpointType::Hardware => {
+                            if let Err(err) =
+                                debugger.set_watchpoint_at(address, Watchpoint::Execution)
+                            {
+                                println!("Failed to set breakpoint: {err}");
+                            }
                             return;
                         }
                     }
                 }
             };
+            match res {
+                Ok(ids) => {
+                    for id in ids {
+                        if let Err(err) = debugger.set_breakpoint_condition(id, condition.clone()) {
+                            println!("Failed to set condition: {err}");
+                        }
+                        print_breakpoint(&debugger, id);
+                    }
+                }
+                Err(err) => println!("Failed to set breakpoint: {err}"),
+            }
+        }
+        ReplCommand::Rbreak { regex } => {
+            match debugger.set_breakpoints_at_matching_functions(&regex) {
+                Ok(group) => {
+                    let ids: Vec<BreakpointId> = debugger
+                        .breakpoints_in_group(group)
+                        .map(|breakpoint| breakpoint.id)
+                        .collect();
+                    for id in &ids {
+                        print_breakpoint(&debugger, *id);
+                    }
+                    println!("Breakpoint group {group}: {} breakpoints", ids.len());
+                }
+                Err(err) => println!("Failed to set breakpoints: {err}"),
+            }
+        }
+        ReplCommand::Delete { target } => {
+            let res = match target {
+                BreakpointSelector::Breakpoint(id) => debugger.delete_breakpoint(id),
+                BreakpointSelector::Group(group) => debugger.delete_breakpoint_group(group),
+            };
             if let Err(err) = res {
-                println!("Failed to set breakpoint: {err}");
+                println!("Failed to delete breakpoint: {err}");
+            }
+        }
+        ReplCommand::Disable { target } | ReplCommand::Enable { target } => {
+            let enabled = matches!(command, ReplCommand::Enable { .. });
+            let res = match target {
+                BreakpointSelector::Breakpoint(id) => debugger.set_breakpoint_enabled(id, enabled),
+                BreakpointSelector::Group(group) => {
+                    debugger.set_breakpoint_group_enabled(group, enabled)
+                }
+            };
+            if let Err(err) = res {
+                println!("Failed to change breakpoint: {err}");
             }
         }
         ReplCommand::Watch {
@@ -232,65 +937,377 @@ fn main() -> std::process::ExitCode {
             condition,
             length,
         } => {
-            // Not pretty, should probably have value parser for WatchpointLength
-            let watchpoint_length = match WatchpointLength::try_from(length) {
-                Ok(length) => length,
+            let res = match (location, length.map(|length| length.0)) {
+                (BreakpointLocation::Symbol(symbol_name), length)
+                    if !matches!(
+                        debugger.resolve_variable(&symbol_name),
+                        Err(Error::UnknownVariable(_))
+                    ) =>
+                {
+                    debugger.set_watchpoint_at_variable(&symbol_name, condition.0, length)
+                }
+                (location, length) => {
+                    let (address, size) = match resolve_data_location(&debugger, &location) {
+                        Ok(resolved) => resolved,
+                        Err(err) => {
+                            println!("{err}");
+                            return;
+                        }
+                    };
+                    let length = match (length, size) {
+                        (Some(length), _) => length,
+                        (None, Some(size)) => match WatchpointLength::try_from(size as usize) {
+                            Ok(length) => length,
+                            Err(err) => {
+                                println!("Cannot watch the whole value: {err}");
+                                return;
+                            }
+                        },
+                        (None, None) => {
+                            println!("A length is required unless a variable is watched");
+                            return;
+                        }
+                    };
+
+                    let watchpoint = Watchpoint::Data {
+                        condition: condition.0,
+                        length,
+                    };
+                    debugger.set_watchpoint_at(address, watchpoint)
+                }
+            };
+            if let Err(err) = res {
+                println!("Failed to set watchpoint: {err}");
+            }
+        }
+        ReplCommand::List { location } => {
+            let (path, first_line) = match (&location, list_position.take()) {
+                (None, Some(position)) => position,
+                (location, _) => {
+                    let Some((path, line)) = resolve_list_location(&debugger, location.as_ref())
+                    else {
+                        println!("No line information available");
+                        return;
+                    };
+                    (path, line.saturating_sub(LIST_LINES / 2).max(1))
+                }
+            };
+
+            let last_line = first_line + LIST_LINES - 1;
+            match print_source_lines(&debugger, &path, first_line..=last_line, None) {
+                Some(true) => list_position = Some((path, last_line + 1)),
+                Some(false) => {
+                    println!("No more lines to list");
+                    list_position = Some((path, first_line));
+                }
+                None => {}
+            }
+        }
+        ReplCommand::X { location, length } => {
+            let (address, size) = match resolve_data_location(&debugger, &location) {
+                Ok(resolved) => resolved,
                 Err(err) => {
-                    println!("Got error while parsing watchpoint length: {err}");
+                    println!("{err}");
                     return;
                 }
             };
+            let length = length.or(size.map(|size| size as usize)).unwrap_or(8);
 
-            let watchpoint = Watchpoint::Data {
-                condition: condition.0,
-                length: watchpoint_length,
+            match debugger.read_memory(address, length) {
+                Ok(data) => print_hex_dump(address, &data),
+                Err(err) => println!("Failed to read memory: {err}"),
+            }
+        }
+        ReplCommand::Print { expression } => {
+            let text = expression.join(" ");
+            let expression = match Expression::parse(&text) {
+                Ok(expression) => expression,
+                Err(err) => {
+                    println!("{err}");
+                    return;
+                }
             };
 
-            let res = match location {
-                BreakpointLocation::Address(address) => {
-                    debugger.set_watchpoint_at(address, watchpoint)
-                }
-                BreakpointLocation::TextOffset(offset) => {
-                    debugger.set_watchpoint_at_text_offset(offset, watchpoint)
-                }
-                BreakpointLocation::Symbol(symbol_name) => {
-                    match debugger.find_symbol_address_by_name(&symbol_name) {
-                        Ok(Some(address)) => {
-                            debugger.set_watchpoint_at_text_offset(address, watchpoint)
-                        }
-                        Ok(None) => {
-                            println!("No symbol found");
-                            return;
-                        }
-                        Err(err) => {
-                            println!("Got error during symbol look up: {err}");
-                            return;
-                        }
+            // Like GDB, assignments are evaluated and the new value of the target is printed
+            let (label, expression) = match expression {
+                Expression::Assign { ref target, .. } => {
+                    if let Err(err) = debugger.assign(&expression) {
+                        println!("{err}");
+                        return;
                     }
+                    (assignment_target(&text), target.as_ref().clone())
                 }
+                expression => (text.as_str(), expression),
             };
-            if let Err(err) = res {
-                println!("Failed to set breakpoint: {err}");
+
+            let inspector = debugger.inspector();
+            match inspector.evaluate(&expression) {
+                Ok(value) => println!("{label} = {}", inspector.format_value(&value)),
+                Err(err) => println!("{err}"),
             }
         }
+        ReplCommand::Ptype { expression } => {
+            let inspector = debugger.inspector();
+            match Expression::parse(&expression.join(" "))
+                .and_then(|expression| inspector.evaluate(&expression))
+                .and_then(|value| inspector.describe_type(value.ty))
+            {
+                Ok(description) => println!("type = {description}"),
+                Err(err) => println!("{err}"),
+            }
+        }
+        ReplCommand::Set { command } => match command {
+            SetCommand::SubstitutePath { from, to } => {
+                debugger.add_source_path_substitution(from, to);
+            }
+            SetCommand::DebugFileDirectory { directories } => {
+                let directories = std::env::split_paths(&directories).collect();
+                if let Err(err) = debugger.set_debug_file_directories(directories) {
+                    println!("Failed to load debug files: {err}");
+                }
+                for module in debugger.modules() {
+                    if let Some(debug_file) = &module.debug_file {
+                        println!(
+                            "Symbols of {} from {}",
+                            module.path.display(),
+                            debug_file.path.display()
+                        );
+                    }
+                }
+            }
+            SetCommand::MangledNames {
+                value: OnOff(enabled),
+            } => {
+                debugger.set_show_mangled_names(enabled);
+            }
+            SetCommand::PrettyPrinters {
+                value: OnOff(enabled),
+            } => {
+                debugger.set_pretty_printing(enabled);
+            }
+            SetCommand::Var { expression } => {
+                if let Err(err) = Expression::parse(&expression.join(" "))
+                    .and_then(|expression| debugger.assign(&expression))
+                {
+                    println!("{err}");
+                }
+            }
+        },
         ReplCommand::Quit => {
             // TODO kill children of debugger
             std::process::exit(0);
         }
         ReplCommand::Info { command } => match command {
-            InfoCommand::Functions => match debugger.list_function_symbols() {
-                Ok(functions) => {
-                    println!("List of all functions:");
-                    for function in functions {
-                        println!(
-                            "- {} ({:#x}) ",
-                            function.name.unwrap_or("---"),
-                            function.offset,
-                        );
+            InfoCommand::Functions => {
+                println!("List of all functions:");
+                for function in debugger.list_function_symbols() {
+                    println!(
+                        "- {} ({:#x}) [{}]",
+                        debugger.symbol_name(function),
+                        function.address,
+                        function.source
+                    );
+                }
+            }
+            InfoCommand::Variables => {
+                println!("List of all variables:");
+                for variable in debugger.list_variable_symbols() {
+                    println!(
+                        "- {} ({:#x}, {} bytes) [{}, {}]",
+                        debugger.symbol_name(variable),
+                        variable.address,
+                        variable.size,
+                        variable.kind,
+                        variable.source
+                    );
+                }
+            }
+            InfoCommand::Breakpoints => {
+                println!(
+                    "{:<4} {:<4} {:<6} {:<20} What",
+                    "Num", "Enb", "Group", "Address"
+                );
+                for breakpoint in debugger.breakpoints() {
+                    let address = match breakpoint.address {
+                        Some(address) => format!("0x{address:016x}"),
+                        None => "<PENDING>".to_owned(),
+                    };
+                    let what = match breakpoint
+                        .address
+                        .and_then(|address| debugger.symbolize(address))
+                    {
+                        Some(symbol) => symbol.to_string(),
+                        None => breakpoint.location.to_string(),
+                    };
+                    let what = match breakpoint
+                        .address
+                        .and_then(|address| debugger.find_source_location(address))
+                    {
+                        Some(source_location) => format!(
+                            "{what} at {}:{}",
+                            source_location.path.display(),
+                            source_location.line
+                        ),
+                        None => what,
+                    };
+                    println!(
+                        "{:<4} {:<4} {:<6} {:<20} {}",
+                        breakpoint.id,
+                        if breakpoint.enabled { "y" } else { "n" },
+                        breakpoint
+                            .group
+                            .map(|group| group.to_string())
+                            .unwrap_or_default(),
+                        address,
+                        what
+                    );
+                    if let Some(condition) = &breakpoint.condition {
+                        println!("\tstop only if {condition}");
                     }
                 }
-                Err(err) => println!("Failed to list all functions: {err}"),
+            }
+            InfoCommand::Registers => match debugger.selected_frame() {
+                Ok(frame) => {
+                    for (name, value) in frame.registers.iter() {
+                        match value {
+                            Some(value) => println!("{name:<8} 0x{value:016x} {value}"),
+                            None => println!("{name:<8} <not saved>"),
+                        }
+                    }
+                }
+                Err(err) => println!("Failed to read registers: {err}"),
             },
+            InfoCommand::Locals => print_variables(&debugger.inspector(), false),
+            InfoCommand::Args => print_variables(&debugger.inspector(), true),
+            InfoCommand::SharedLibrary => {
+                println!("{:<18} {:<18} Shared Object Library", "From", "To");
+                for library in debugger.shared_libraries() {
+                    let range = library.address_range();
+                    println!(
+                        "0x{:016x} 0x{:016x} {}",
+                        range.start,
+                        range.end,
+                        library.path.display()
+                    );
+                }
+            }
+            InfoCommand::Proc {
+                command: ProcCommand::Mappings,
+            } => {
+                if let Err(err) = debugger.refresh_memory_maps() {
+                    println!("Failed to read the memory mappings: {err}");
+                    return;
+                }
+                println!(
+                    "{:<18} {:<18} {:<10} {:<10} {:<5} Objfile",
+                    "Start Addr", "End Addr", "Size", "Offset", "Perms"
+                );
+                for map in debugger.memory_maps() {
+                    println!(
+                        "0x{:016x} 0x{:016x} 0x{:08x} 0x{:08x} {:<5} {}{}",
+                        map.range_from,
+                        map.range_to,
+                        map.size(),
+                        map.offset,
+                        map.permissions,
+                        map.pathname.as_deref().unwrap_or_default(),
+                        if map.deleted { " (deleted)" } else { "" }
+                    );
+                }
+            }
+            InfoCommand::Sections => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                println!(
+                    "{:<4} {:<24} {:<16} {:<18} {:<10} {:<10} Flags",
+                    "Nr", "Name", "Type", "Address", "Offset", "Size"
+                );
+                for (index, section) in summary.sections.iter().enumerate() {
+                    println!(
+                        "{:<4} {:<24} {:<16} 0x{:016x} 0x{:08x} 0x{:08x} {}",
+                        index,
+                        section.name,
+                        section.type_name(),
+                        section.address,
+                        section.offset,
+                        section.size,
+                        section.flags_string()
+                    );
+                }
+            }
+            InfoCommand::Segments => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                println!(
+                    "{:<16} {:<10} {:<18} {:<10} {:<10} {:<5} Align",
+                    "Type", "Offset", "VirtAddr", "FileSiz", "MemSiz", "Flags"
+                );
+                for segment in &summary.segments {
+                    println!(
+                        "{:<16} 0x{:08x} 0x{:016x} 0x{:08x} 0x{:08x} {:<5} {:#x}",
+                        segment.type_name(),
+                        segment.offset,
+                        segment.virtual_address,
+                        segment.file_size,
+                        segment.memory_size,
+                        segment.flags_string(),
+                        segment.alignment
+                    );
+                }
+            }
+            InfoCommand::Entry => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                let runtime_entry = summary.entry.wrapping_add(debugger.load_bias());
+                println!(
+                    "Entry point: {} (link-time address 0x{:x}, type {})",
+                    format_address(&debugger, runtime_entry),
+                    summary.entry,
+                    summary.type_name()
+                );
+            }
+            InfoCommand::Dynamic => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                if summary.dynamic.is_empty() {
+                    println!("The executable has no dynamic section");
+                }
+                for entry in &summary.dynamic {
+                    println!("{:<24} {}", entry.tag_name(), entry);
+                }
+            }
+            InfoCommand::Needed => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                for library in summary.needed() {
+                    println!("{library}");
+                }
+            }
+            InfoCommand::BuildId => {
+                let Some(summary) = executable_elf_summary(&debugger) else {
+                    return;
+                };
+                match summary.build_id_string() {
+                    Some(build_id) => println!("Build ID: {build_id}"),
+                    None => println!("The executable has no build ID"),
+                }
+            }
+            InfoCommand::PrettyPrinter => {
+                let state = if debugger.pretty_printing() {
+                    "enabled"
+                } else {
+                    "disabled"
+                };
+                println!("Pretty printers are {state}:");
+                for name in debugger.pretty_printer_names() {
+                    println!("  {name}");
+                }
+            }
         },
     });
 
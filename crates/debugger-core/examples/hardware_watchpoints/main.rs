@@ -17,21 +17,27 @@ fn main() {
         length: WatchpointLength::FourBytes,
     };
     // Break at before_write
-    debugger.set_watchpoint_at(0x401136, watchpoint_exec);
+    debugger
+        .set_watchpoint_at(0x401136, watchpoint_exec)
+        .unwrap();
     // Break at after_write
-    debugger.set_watchpoint_at(0x40115d, watchpoint_exec);
+    debugger
+        .set_watchpoint_at(0x40115d, watchpoint_exec)
+        .unwrap();
     // Break at write to a
-    debugger.set_watchpoint_at(0x404030, watchpoint_write);
+    debugger
+        .set_watchpoint_at(0x404030, watchpoint_write)
+        .unwrap();
     // Hit breakpoint of before_write()
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Hit watchpoint (write to a)
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Hit watchpoint again
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Hit watchpoint yet again?
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Hit breakpoint of after_write()
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Continues and exits with code 0
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
 }
@@ -11,11 +11,11 @@ fn main() {
     let mut debugger = Debugger::new_with_forked_child(path).unwrap();
 
     // Break at fn_c
-    debugger.set_breakpoint_at_text_offset(0x118f);
+    debugger.set_breakpoint_at_text_offset(0x12f).unwrap();
     // Hits breakpoint the first time, just before printing "C"
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // "C" printed for the first time, hits breakpoint before printing "C" again
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
     // Continues and exists with code 0
-    debugger.continue_execution();
+    debugger.continue_execution().unwrap();
 }
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use nix::unistd::Pid;
+
+pub const AT_PHDR: u64 = 3;
+pub const AT_BASE: u64 = 7;
+pub const AT_ENTRY: u64 = 9;
+
+/// The auxiliary vector passed by the kernel to the tracee, see getauxval(3).
+#[derive(Debug, Default)]
+pub struct AuxiliaryVector {
+    entries: HashMap<u64, u64>,
+}
+
+impl AuxiliaryVector {
+    pub fn from_pid(pid: Pid) -> Result<Self, std::io::Error> {
+        let data = std::fs::read(format!("/proc/{pid}/auxv"))?;
+
+        Ok(Self::from_data(&data))
+    }
+
+    // The vector is a list of key and value pairs of native words, terminated by an `AT_NULL` key
+    fn from_data(data: &[u8]) -> Self {
+        let entries = data
+            .chunks_exact(16)
+            .map(|entry| {
+                let key = u64::from_ne_bytes(entry[..8].try_into().expect("chunk to be 16 bytes"));
+                let value =
+                    u64::from_ne_bytes(entry[8..].try_into().expect("chunk to be 16 bytes"));
+                (key, value)
+            })
+            .take_while(|(key, _value)| *key != 0)
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn get(&self, key: u64) -> Option<u64> {
+        self.entries.get(&key).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, value: u64) -> Vec<u8> {
+        [key.to_ne_bytes(), value.to_ne_bytes()].concat()
+    }
+
+    #[test]
+    fn stops_at_null_entry() {
+        let data = [
+            entry(AT_PHDR, 0x5000_0040),
+            entry(AT_ENTRY, 0x5000_1060),
+            entry(0, 0),
+            entry(AT_BASE, 0x7f00_0000),
+        ]
+        .concat();
+        let auxiliary_vector = AuxiliaryVector::from_data(&data);
+
+        assert_eq!(auxiliary_vector.get(AT_PHDR), Some(0x5000_0040));
+        assert_eq!(auxiliary_vector.get(AT_ENTRY), Some(0x5000_1060));
+        assert_eq!(auxiliary_vector.get(AT_BASE), None);
+    }
+
+    #[test]
+    fn ignores_incomplete_entry() {
+        let mut data = entry(AT_BASE, 0x7f00_0000);
+        data.extend(&AT_ENTRY.to_ne_bytes());
+        let auxiliary_vector = AuxiliaryVector::from_data(&data);
+
+        assert_eq!(auxiliary_vector.get(AT_BASE), Some(0x7f00_0000));
+        assert_eq!(auxiliary_vector.get(AT_ENTRY), None);
+    }
+
+    #[test]
+    fn reads_own_auxiliary_vector() {
+        let auxiliary_vector = AuxiliaryVector::from_pid(nix::unistd::getpid()).unwrap();
+
+        assert!(auxiliary_vector.get(AT_ENTRY).is_some());
+        assert!(auxiliary_vector.get(AT_PHDR).is_some());
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt;
+
+use nix::libc::user_regs_struct;
+
+use crate::{
+    Debugger, Error, Result,
+    line_table::{InlinedSubroutine, SourceLocation},
+};
+
+// Guards against walking corrupted stacks for too long
+const MAX_FRAMES: usize = 1024;
+
+const PUSH_RBP: &[u8] = &[0x55];
+const MOV_RBP_RSP: &[u8] = &[0x48, 0x89, 0xe5];
+const ENDBR64: &[u8] = &[0xf3, 0x0f, 0x1e, 0xfa];
+const RET: u8 = 0xc3;
+
+// DWARF register numbers of x86-64, `rip` is represented by the return address column
+const REGISTER_COUNT: usize = 17;
+const REGISTER_NAMES: [&str; REGISTER_COUNT] = [
+    "rax", "rdx", "rcx", "rbx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15", "rip",
+];
+const RBP: u16 = 6;
+const RSP: u16 = 7;
+const RETURN_ADDRESS: u16 = 16;
+
+/// The registers of a stack frame, indexed by their DWARF register number. Registers that could not be recovered
+/// while unwinding, e.g. caller-saved registers of callers, are `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameRegisters([Option<u64>; REGISTER_COUNT]);
+
+impl FrameRegisters {
+    pub fn from_user_regs(regs: &user_regs_struct) -> Self {
+        Self(
+            [
+                regs.rax, regs.rdx, regs.rcx, regs.rbx, regs.rsi, regs.rdi, regs.rbp, regs.rsp,
+                regs.r8, regs.r9, regs.r10, regs.r11, regs.r12, regs.r13, regs.r14, regs.r15,
+                regs.rip,
+            ]
+            .map(Some),
+        )
+    }
+
+    pub fn get(&self, register: u16) -> Option<u64> {
+        self.0.get(register as usize).copied().flatten()
+    }
+
+    // Registers without a slot, e.g. vector registers, are ignored
+    pub(crate) fn set(&mut self, register: u16, value: Option<u64>) {
+        if let Some(slot) = self.0.get_mut(register as usize) {
+            *slot = value;
+        }
+    }
+
+    /// Looks up a register by its name, `pc`, `sp` and `fp` are accepted as aliases of `rip`, `rsp` and `rbp`.
+    pub fn get_by_name(&self, name: &str) -> Option<u64> {
+        self.get(register_number(name)?)
+    }
+
+    /// The registers with their names, in the order of their DWARF register numbers.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<u64>)> {
+        REGISTER_NAMES.into_iter().zip(self.0)
+    }
+
+    pub fn pc(&self) -> Option<u64> {
+        self.get(RETURN_ADDRESS)
+    }
+
+    pub fn sp(&self) -> Option<u64> {
+        self.get(RSP)
+    }
+
+    pub fn frame_pointer(&self) -> Option<u64> {
+        self.get(RBP)
+    }
+}
+
+/// The DWARF register number of a register name, see [`FrameRegisters::get_by_name`].
+pub fn register_number(name: &str) -> Option<u16> {
+    let name = match name {
+        "pc" => "rip",
+        "sp" => "rsp",
+        "fp" => "rbp",
+        name => name,
+    };
+
+    REGISTER_NAMES
+        .iter()
+        .position(|register| *register == name)
+        .map(|register| register as u16)
+}
+
+// The field of the registers read by ptrace holding a register, by its DWARF register number
+pub(crate) fn user_regs_field(regs: &mut user_regs_struct, register: u16) -> Option<&mut u64> {
+    let field = match register {
+        0 => &mut regs.rax,
+        1 => &mut regs.rdx,
+        2 => &mut regs.rcx,
+        3 => &mut regs.rbx,
+        4 => &mut regs.rsi,
+        5 => &mut regs.rdi,
+        6 => &mut regs.rbp,
+        7 => &mut regs.rsp,
+        8 => &mut regs.r8,
+        9 => &mut regs.r9,
+        10 => &mut regs.r10,
+        11 => &mut regs.r11,
+        12 => &mut regs.r12,
+        13 => &mut regs.r13,
+        14 => &mut regs.r14,
+        15 => &mut regs.r15,
+        16 => &mut regs.rip,
+        _ => return None,
+    };
+
+    Some(field)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StackFrame {
+    /// The position in the backtrace, 0 is the innermost frame.
+    pub index: usize,
+    /// The current instruction of the frame. For callers this is the return address.
+    pub pc: u64,
+    /// The canonical frame address, i.e. the stack pointer in the caller before the call. It is only known once the
+    /// frame was unwound.
+    pub cfa: Option<u64>,
+    pub registers: FrameRegisters,
+    /// Functions inlined at `pc` get virtual frames, which share the pc and registers of the frame of the function
+    /// they were inlined into. This is the number of those functions whose frames come before this one, 0 for the
+    /// innermost function at `pc`.
+    pub inline_depth: usize,
+}
+
+impl StackFrame {
+    /// The address used to look up the function and source line of the frame. The return address of a caller may
+    /// already belong to the next line or even the next function, so an address inside the call instruction is used
+    /// instead.
+    pub fn lookup_address(&self) -> u64 {
+        // Only the frames of the innermost call, which come first, are at the current instruction
+        if self.index == self.inline_depth {
+            self.pc
+        } else {
+            self.pc.wrapping_sub(1)
+        }
+    }
+}
+
+/// Why unwinding stopped after the last frame of a backtrace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwindStopReason {
+    /// The outermost frame was reached, marked by a zero frame pointer or return address.
+    Outermost,
+    /// The caller's frame does not lie further up the stack, which would lead into a loop.
+    Loop(u64),
+    /// Neither call frame information nor a frame pointer is available to find the caller.
+    NoUnwindInformation,
+    /// The frame record at this address could not be read.
+    UnreadableFrame(u64),
+    /// The return address does not point into executable memory.
+    InvalidReturnAddress(u64),
+    TooManyFrames,
+}
+
+impl fmt::Display for UnwindStopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnwindStopReason::Outermost => write!(f, "outermost frame reached"),
+            UnwindStopReason::Loop(address) => write!(
+                f,
+                "previous frame at 0x{address:x} is not above the current one (corrupt stack?)"
+            ),
+            UnwindStopReason::NoUnwindInformation => write!(f, "no unwind information"),
+            UnwindStopReason::UnreadableFrame(address) => {
+                write!(f, "cannot read frame at 0x{address:x}")
+            }
+            UnwindStopReason::InvalidReturnAddress(address) => {
+                write!(
+                    f,
+                    "return address 0x{address:x} is not in executable memory"
+                )
+            }
+            UnwindStopReason::TooManyFrames => {
+                write!(f, "more than {MAX_FRAMES} frames")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Backtrace {
+    pub frames: Vec<StackFrame>,
+    pub stop_reason: UnwindStopReason,
+}
+
+impl Debugger {
+    /// Unwinds the stack of the tracee, starting at its current pc. Frames are unwound with the call frame information
+    /// from `.eh_frame` or `.debug_frame`, falling back to the chain of saved frame pointers for code without it.
+    pub fn backtrace(&self) -> Result<Backtrace> {
+        let regs = self.get_tracee_registers()?;
+
+        let mut frame = StackFrame {
+            index: 0,
+            pc: regs.rip,
+            cfa: None,
+            registers: FrameRegisters::from_user_regs(&regs),
+            inline_depth: 0,
+        };
+        let mut frames = Vec::new();
+
+        let stop_reason = loop {
+            if frames.len() + 1 >= MAX_FRAMES {
+                frames.push(frame);
+                break UnwindStopReason::TooManyFrames;
+            }
+
+            match self.unwind_frame(&mut frame) {
+                Ok(caller) => {
+                    frames.push(frame);
+                    frame = caller;
+                }
+                Err(stop_reason) => {
+                    frames.push(frame);
+                    break stop_reason;
+                }
+            }
+        };
+
+        // Inlined functions are listed before the function they were inlined into, starting with the innermost one
+        let mut expanded = Vec::with_capacity(frames.len());
+        for frame in frames {
+            let inlined = self.inlined_subroutines_at(frame.lookup_address()).len();
+            for inline_depth in 0..=inlined {
+                expanded.push(StackFrame {
+                    index: expanded.len(),
+                    inline_depth,
+                    ..frame
+                });
+            }
+        }
+
+        Ok(Backtrace {
+            frames: expanded,
+            stop_reason,
+        })
+    }
+
+    /// The inlined call a frame belongs to, or `None` if it is the frame of a function that was actually called.
+    pub fn inlined_subroutine(&self, frame: &StackFrame) -> Option<&InlinedSubroutine> {
+        self.inlined_subroutines_at(frame.lookup_address())
+            .get(frame.inline_depth)
+            .map(|(_, subroutine)| *subroutine)
+    }
+
+    /// The source location a frame is at. Frames whose function was inlined into the next one are at the
+    /// instruction of the innermost frame, so the location of the next frame is where the call was inlined.
+    pub fn frame_source_location(&self, frame: &StackFrame) -> Option<SourceLocation<'_>> {
+        let address = frame.lookup_address();
+        let Some(inline_depth) = frame.inline_depth.checked_sub(1) else {
+            return self.find_source_location(address);
+        };
+
+        let inlined = self.inlined_subroutines_at(address);
+        let (_, callee) = inlined.get(inline_depth)?;
+        callee.call_location()
+    }
+
+    pub fn selected_frame_index(&self) -> usize {
+        self.selected_frame
+    }
+
+    /// Selects a frame of the backtrace by its index. Register values and source locations are then looked up in the
+    /// context of this frame, until the tracee executes again.
+    pub fn select_frame(&mut self, index: usize) -> Result<StackFrame> {
+        let frame = self.frame_at(index)?;
+        self.selected_frame = index;
+
+        Ok(frame)
+    }
+
+    pub fn selected_frame(&self) -> Result<StackFrame> {
+        self.frame_at(self.selected_frame)
+    }
+
+    fn frame_at(&self, index: usize) -> Result<StackFrame> {
+        self.backtrace()?
+            .frames
+            .get(index)
+            .copied()
+            .ok_or(Error::UnknownFrame(index))
+    }
+
+    // Recovers the caller of a frame and stores the canonical frame address in the frame
+    fn unwind_frame(
+        &self,
+        frame: &mut StackFrame,
+    ) -> std::result::Result<StackFrame, UnwindStopReason> {
+        let (registers, cfa) = match self.unwind_with_cfi(frame.lookup_address(), &frame.registers)
+        {
+            Some(unwound) => unwound,
+            None => self.unwind_with_frame_pointer(frame)?,
+        };
+        frame.cfa = Some(cfa);
+
+        let pc = match registers.pc() {
+            None | Some(0) => return Err(UnwindStopReason::Outermost),
+            Some(pc) => pc,
+        };
+        if !self.is_executable_address(pc) {
+            return Err(UnwindStopReason::InvalidReturnAddress(pc));
+        }
+        // The stack grows down, so callers have their frames at higher addresses
+        if frame.registers.sp().is_some_and(|sp| cfa <= sp) {
+            return Err(UnwindStopReason::Loop(cfa));
+        }
+
+        Ok(StackFrame {
+            index: frame.index + 1,
+            pc,
+            cfa: None,
+            registers,
+            inline_depth: 0,
+        })
+    }
+
+    // Reads the return address and the saved frame pointer from the frame record `rbp` points to. Only the stack and
+    // frame pointer of the caller can be recovered.
+    fn unwind_with_frame_pointer(
+        &self,
+        frame: &StackFrame,
+    ) -> std::result::Result<(FrameRegisters, u64), UnwindStopReason> {
+        let mut registers = FrameRegisters::default();
+
+        // Inside the prologue or at the return, rbp still or already holds the frame pointer of the caller and the
+        // return address is found relative to the stack pointer
+        let return_address_slot = match (frame.index, frame.registers.sp()) {
+            (0, Some(sp)) => self.return_address_slot(frame.pc, sp),
+            _ => None,
+        };
+        let return_address_slot = match return_address_slot {
+            Some(return_address_slot) => {
+                registers.set(RBP, frame.registers.frame_pointer());
+                return_address_slot
+            }
+            None => {
+                let frame_pointer = match frame.registers.frame_pointer() {
+                    None => return Err(UnwindStopReason::NoUnwindInformation),
+                    Some(0) => return Err(UnwindStopReason::Outermost),
+                    Some(frame_pointer) => frame_pointer,
+                };
+                let saved_frame_pointer = self
+                    .read_u64(frame_pointer)
+                    .map_err(|_| UnwindStopReason::UnreadableFrame(frame_pointer))?;
+                registers.set(RBP, Some(saved_frame_pointer));
+                frame_pointer.wrapping_add(8)
+            }
+        };
+
+        let return_address = self
+            .read_u64(return_address_slot)
+            .map_err(|_| UnwindStopReason::UnreadableFrame(return_address_slot))?;
+        let cfa = return_address_slot.wrapping_add(8);
+        registers.set(RETURN_ADDRESS, Some(return_address));
+        registers.set(RSP, Some(cfa));
+
+        Ok((registers, cfa))
+    }
+
+    // Finds where the return address is stored if the innermost frame did not set up its frame pointer yet, or
+    // already restored the one of the caller. Recognizes the usual `push rbp; mov rbp, rsp` prologue.
+    fn return_address_slot(&self, pc: u64, sp: u64) -> Option<u64> {
+        if self.read_memory(pc, 1).ok()?.first() == Some(&RET) {
+            return Some(sp);
+        }
+
+        let symbol = self.symbolize(pc)?;
+        let function_start = pc - symbol.offset;
+        let code = self
+            .read_memory(
+                function_start,
+                ENDBR64.len() + PUSH_RBP.len() + MOV_RBP_RSP.len(),
+            )
+            .ok()?;
+
+        let push_offset = if code.starts_with(ENDBR64) {
+            ENDBR64.len() as u64
+        } else {
+            0
+        };
+        let mov_offset = push_offset + PUSH_RBP.len() as u64;
+        let prologue = &code[push_offset as usize..];
+        if !prologue.starts_with(PUSH_RBP) || !prologue[PUSH_RBP.len()..].starts_with(MOV_RBP_RSP) {
+            return None;
+        }
+
+        match symbol.offset {
+            offset if offset <= push_offset => Some(sp),
+            offset if offset == mov_offset => Some(sp + 8),
+            _ => None,
+        }
+    }
+
+    fn is_executable_address(&self, address: u64) -> bool {
+        self.memory_maps
+            .iter()
+            .any(|map| map.permissions.execute && map.contains(address))
+    }
+}
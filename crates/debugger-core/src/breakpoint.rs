@@ -0,0 +1,521 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use log::*;
+use regex::Regex;
+
+use crate::{Debugger, Error, Result, expression::Expression};
+
+pub type BreakpointId = usize;
+/// Identifies breakpoints that were set together, e.g. by [`Debugger::set_breakpoints_at_matching_functions`].
+pub type BreakpointGroupId = usize;
+
+/// Where the user asked for a breakpoint to be placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakpointLocation {
+    Address(u64),
+    Symbol(String),
+    Line { file: PathBuf, line: u64 },
+}
+
+impl fmt::Display for BreakpointLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakpointLocation::Address(address) => write!(f, "0x{address:012x}"),
+            BreakpointLocation::Symbol(name) => write!(f, "{name}"),
+            BreakpointLocation::Line { file, line } => write!(f, "{}:{line}", file.display()),
+        }
+    }
+}
+
+/// A software breakpoint set by the user.
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: BreakpointId,
+    pub location: BreakpointLocation,
+    /// The runtime address of the breakpoint, `None` while it is pending on code that is not loaded.
+    pub address: Option<u64>,
+    /// Disabled breakpoints are kept, but their instruction is removed from the tracee.
+    pub enabled: bool,
+    pub group: Option<BreakpointGroupId>,
+    /// The tracee only stops at the breakpoint if this expression is true.
+    pub condition: Option<BreakpointCondition>,
+}
+
+/// The condition of a breakpoint, which is parsed once when it is set instead of on every hit.
+#[derive(Debug, Clone)]
+pub struct BreakpointCondition {
+    pub text: String,
+    pub expression: Expression,
+}
+
+impl BreakpointCondition {
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(Self {
+            text: text.to_owned(),
+            expression: Expression::parse(text)?,
+        })
+    }
+}
+
+impl fmt::Display for BreakpointCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Breakpoint {
+    pub fn is_pending(&self) -> bool {
+        self.address.is_none()
+    }
+}
+
+impl Debugger {
+    // Writes an int3 instruction to an address, unless there already is one placed by the debugger
+    pub(crate) fn insert_breakpoint_instruction(&mut self, address: u64) -> Result<()> {
+        if self.breakpoints.contains_key(&address) {
+            return Ok(());
+        }
+
+        let original_byte = self.read_memory_raw(address, 1)?[0];
+        self.write_memory_raw(address, &[0xCC])?;
+
+        self.breakpoints.insert(address, original_byte);
+
+        info!("Set breakpoint at 0x{address:012x}");
+
+        Ok(())
+    }
+
+    // Restores the original instruction, if the address is not used by another breakpoint anymore
+    pub(crate) fn remove_breakpoint_instruction(&mut self, address: u64) -> Result<()> {
+        let still_used =
+            self.is_dynamic_linker_breakpoint(address) || self.is_breakpoint_enabled_at(address);
+
+        if !still_used && let Some(original_byte) = self.breakpoints.remove(&address) {
+            self.write_memory_raw(address, &[original_byte])?;
+            info!("Removed breakpoint at 0x{address:012x}");
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_breakpoint_enabled_at(&self, address: u64) -> bool {
+        self.breakpoint_at(address)
+            .is_some_and(|breakpoint| breakpoint.enabled)
+    }
+
+    fn add_breakpoint(
+        &mut self,
+        location: BreakpointLocation,
+        address: Option<u64>,
+        group: Option<BreakpointGroupId>,
+    ) -> Result<BreakpointId> {
+        if let Some(address) = address {
+            if self.breakpoint_at(address).is_some() {
+                return Err(Error::BreakpointExists(address));
+            }
+
+            self.insert_breakpoint_instruction(address)?;
+        }
+
+        self.next_breakpoint_id += 1;
+        let id = self.next_breakpoint_id;
+
+        self.user_breakpoints.push(Breakpoint {
+            id,
+            location,
+            address,
+            enabled: true,
+            group,
+            condition: None,
+        });
+
+        Ok(id)
+    }
+
+    // Adds a breakpoint at every address. If one of them cannot be added, the ones added before are deleted again, so
+    // either all or none of the breakpoints exist.
+    fn add_breakpoints(
+        &mut self,
+        locations: Vec<(BreakpointLocation, u64)>,
+        group: Option<BreakpointGroupId>,
+    ) -> Result<Vec<BreakpointId>> {
+        let mut ids = Vec::new();
+
+        for (location, address) in locations {
+            match self.add_breakpoint(location, Some(address), group) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    for id in ids {
+                        if let Err(delete_err) = self.delete_breakpoint(id) {
+                            warn!("Failed to delete breakpoint {id} again: {delete_err}");
+                        }
+                    }
+
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    pub fn set_breakpoint_at(&mut self, breakpoint_address: u64) -> Result<BreakpointId> {
+        self.add_breakpoint(
+            BreakpointLocation::Address(breakpoint_address),
+            Some(breakpoint_address),
+            None,
+        )
+    }
+
+    pub fn set_breakpoint_at_text_offset(&mut self, text_offset: u64) -> Result<BreakpointId> {
+        let breakpoint_address = self.get_text_offset_address(text_offset)?;
+
+        self.set_breakpoint_at(breakpoint_address)
+    }
+
+    /// Sets breakpoints on a symbol. A function gets a breakpoint on its symbol and on every call it was inlined into.
+    /// If no loaded module defines the symbol, a single breakpoint stays pending until a shared library defining it is
+    /// loaded.
+    pub fn set_breakpoint_at_symbol(&mut self, symbol_name: &str) -> Result<Vec<BreakpointId>> {
+        let location = BreakpointLocation::Symbol(symbol_name.to_owned());
+        let addresses = self.resolve_function_addresses(symbol_name);
+
+        if addresses.is_empty() {
+            info!("Symbol {symbol_name} is not loaded yet, breakpoint is pending");
+            return Ok(vec![self.add_breakpoint(location, None, None)?]);
+        }
+
+        let locations = addresses
+            .into_iter()
+            .map(|address| (location.clone(), address))
+            .collect();
+        self.add_breakpoints(locations, None)
+    }
+
+    /// Sets breakpoints on the code of a source line in all files whose path ends with `file`. There is a breakpoint for
+    /// every function containing the line. If no loaded module has code for the line, a single pending breakpoint is
+    /// set.
+    pub fn set_breakpoint_at_line(&mut self, file: &Path, line: u64) -> Result<Vec<BreakpointId>> {
+        let location = BreakpointLocation::Line {
+            file: file.to_owned(),
+            line,
+        };
+        let addresses = self.resolve_line_addresses(file, line);
+
+        if addresses.is_empty() {
+            info!("No code for {location} is loaded yet, breakpoint is pending");
+            return Ok(vec![self.add_breakpoint(location, None, None)?]);
+        }
+
+        let locations = addresses
+            .into_iter()
+            .map(|address| (location.clone(), address))
+            .collect();
+        self.add_breakpoints(locations, None)
+    }
+
+    /// Sets breakpoints on the line `line_offset` lines after the start of a function.
+    pub fn set_breakpoint_at_function_line(
+        &mut self,
+        function_name: &str,
+        line_offset: u64,
+    ) -> Result<Vec<BreakpointId>> {
+        let address = self
+            .resolve_function_addresses(function_name)
+            .first()
+            .copied()
+            .ok_or_else(|| Error::UnknownSymbol(function_name.to_owned()))?;
+        let source_location = self
+            .find_source_location(address)
+            .ok_or_else(|| Error::NoLineInformation(function_name.to_owned()))?;
+
+        let file = source_location.path.to_owned();
+        let line = source_location.line + line_offset;
+
+        self.set_breakpoint_at_line(&file, line)
+    }
+
+    /// Sets a breakpoint on every function of all loaded modules whose demangled name matches a regular expression.
+    /// All of them are placed into a new group, which can be enabled, disabled or deleted at once. No group is created
+    /// if no function matches.
+    pub fn set_breakpoints_at_matching_functions(
+        &mut self,
+        pattern: &str,
+    ) -> Result<BreakpointGroupId> {
+        let regex = Regex::new(pattern)?;
+
+        let mut matching: Vec<(String, u64)> = self
+            .modules()
+            .flat_map(|module| {
+                module
+                    .symbols
+                    .functions()
+                    .filter(|symbol| symbol.address != 0 && regex.is_match(symbol.display_name()))
+                    .map(|symbol| {
+                        (
+                            symbol.name.clone(),
+                            symbol.address.wrapping_add(module.load_bias),
+                        )
+                    })
+            })
+            .collect();
+        if matching.is_empty() {
+            return Err(Error::NoMatchingFunctions(pattern.to_owned()));
+        }
+
+        // Aliases share an address, but only one breakpoint can be placed there
+        matching.sort_by_key(|(_, address)| *address);
+        matching.dedup_by_key(|(_, address)| *address);
+
+        let first_address = matching[0].1;
+        matching.retain(|(symbol_name, address)| {
+            let exists = self.breakpoint_at(*address).is_some();
+            if exists {
+                info!("Skipping {symbol_name}, there already is a breakpoint at 0x{address:012x}");
+            }
+            !exists
+        });
+        if matching.is_empty() {
+            return Err(Error::BreakpointExists(first_address));
+        }
+
+        self.next_breakpoint_group_id += 1;
+        let group = self.next_breakpoint_group_id;
+
+        let locations = matching
+            .into_iter()
+            .map(|(symbol_name, address)| (BreakpointLocation::Symbol(symbol_name), address))
+            .collect();
+        self.add_breakpoints(locations, Some(group))?;
+
+        Ok(group)
+    }
+
+    pub fn delete_breakpoint(&mut self, id: BreakpointId) -> Result<()> {
+        let index = self
+            .user_breakpoints
+            .iter()
+            .position(|breakpoint| breakpoint.id == id)
+            .ok_or(Error::UnknownBreakpoint(id))?;
+
+        let breakpoint = self.user_breakpoints.remove(index);
+        if let Some(address) = breakpoint.address {
+            self.remove_breakpoint_instruction(address)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_breakpoint_enabled(&mut self, id: BreakpointId, enabled: bool) -> Result<()> {
+        let breakpoint = self
+            .user_breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.id == id)
+            .ok_or(Error::UnknownBreakpoint(id))?;
+
+        if breakpoint.enabled == enabled {
+            return Ok(());
+        }
+        breakpoint.enabled = enabled;
+
+        let Some(address) = breakpoint.address else {
+            return Ok(());
+        };
+
+        if enabled {
+            self.insert_breakpoint_instruction(address)
+        } else {
+            self.remove_breakpoint_instruction(address)
+        }
+    }
+
+    /// Makes the breakpoint only stop the tracee if the condition is true, or always if it is `None`.
+    pub fn set_breakpoint_condition(
+        &mut self,
+        id: BreakpointId,
+        condition: Option<BreakpointCondition>,
+    ) -> Result<()> {
+        let breakpoint = self
+            .user_breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.id == id)
+            .ok_or(Error::UnknownBreakpoint(id))?;
+        breakpoint.condition = condition;
+
+        Ok(())
+    }
+
+    // Evaluates the condition of the breakpoint at an address in the frame that hit it. If the condition cannot be
+    // evaluated, the tracee stops like GDB does, so the user can fix it.
+    pub(crate) fn breakpoint_condition_met(&self, address: u64) -> bool {
+        let Some(condition) = self
+            .breakpoint_at(address)
+            .and_then(|breakpoint| breakpoint.condition.as_ref())
+        else {
+            return true;
+        };
+
+        let inspector = self.inspector();
+        let result = inspector
+            .evaluate(&condition.expression)
+            .and_then(|value| inspector.is_true(&value));
+
+        result.unwrap_or_else(|err| {
+            warn!("Could not evaluate the breakpoint condition `{condition}`: {err}");
+            true
+        })
+    }
+
+    pub fn delete_breakpoint_group(&mut self, group: BreakpointGroupId) -> Result<()> {
+        for id in self.breakpoint_ids_in_group(group)? {
+            self.delete_breakpoint(id)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_breakpoint_group_enabled(
+        &mut self,
+        group: BreakpointGroupId,
+        enabled: bool,
+    ) -> Result<()> {
+        for id in self.breakpoint_ids_in_group(group)? {
+            self.set_breakpoint_enabled(id, enabled)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn breakpoints_in_group(
+        &self,
+        group: BreakpointGroupId,
+    ) -> impl Iterator<Item = &Breakpoint> {
+        self.user_breakpoints
+            .iter()
+            .filter(move |breakpoint| breakpoint.group == Some(group))
+    }
+
+    fn breakpoint_ids_in_group(&self, group: BreakpointGroupId) -> Result<Vec<BreakpointId>> {
+        if group == 0 || group > self.next_breakpoint_group_id {
+            return Err(Error::UnknownBreakpointGroup(group));
+        }
+
+        Ok(self
+            .breakpoints_in_group(group)
+            .map(|breakpoint| breakpoint.id)
+            .collect())
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.user_breakpoints
+    }
+
+    pub fn breakpoint_at(&self, address: u64) -> Option<&Breakpoint> {
+        self.user_breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.address == Some(address))
+    }
+
+    // Tries to resolve pending breakpoints after new shared libraries were loaded
+    pub(crate) fn resolve_pending_breakpoints(&mut self) -> Result<()> {
+        let mut locations: Vec<BreakpointLocation> = Vec::new();
+        for breakpoint in &self.user_breakpoints {
+            if breakpoint.is_pending() && !locations.contains(&breakpoint.location) {
+                locations.push(breakpoint.location.clone());
+            }
+        }
+
+        for location in locations {
+            let addresses = match &location {
+                BreakpointLocation::Address(address) => {
+                    if !self.modules().any(|module| module.contains(*address)) {
+                        continue;
+                    }
+                    vec![*address]
+                }
+                BreakpointLocation::Symbol(symbol_name) => {
+                    self.resolve_function_addresses(symbol_name)
+                }
+                BreakpointLocation::Line { file, line } => self.resolve_line_addresses(file, *line),
+            };
+
+            self.resolve_pending_location(&location, addresses)?;
+        }
+
+        Ok(())
+    }
+
+    // Places the pending breakpoints on a location at its addresses. A location can resolve to more addresses than
+    // there are pending breakpoints on it, e.g. a function that was inlined into several callers, so the remaining
+    // addresses get new breakpoints with the settings of the first pending one. The addresses of a location come in a
+    // stable order, so after a library was unloaded and loaded again every breakpoint returns to its own instance.
+    fn resolve_pending_location(
+        &mut self,
+        location: &BreakpointLocation,
+        addresses: Vec<u64>,
+    ) -> Result<()> {
+        let pending: Vec<usize> = self
+            .user_breakpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, breakpoint)| breakpoint.is_pending() && breakpoint.location == *location)
+            .map(|(index, _)| index)
+            .collect();
+        let Some(&template) = pending.first() else {
+            return Ok(());
+        };
+        let mut pending = pending.into_iter();
+
+        for address in addresses {
+            if self.breakpoint_at(address).is_some() {
+                continue;
+            }
+
+            let index = pending.next().unwrap_or_else(|| {
+                self.next_breakpoint_id += 1;
+                self.user_breakpoints.push(Breakpoint {
+                    id: self.next_breakpoint_id,
+                    ..self.user_breakpoints[template].clone()
+                });
+                self.user_breakpoints.len() - 1
+            });
+
+            info!(
+                "Resolved pending breakpoint {} on {location} to 0x{address:012x}",
+                self.user_breakpoints[index].id
+            );
+
+            if self.user_breakpoints[index].enabled {
+                self.insert_breakpoint_instruction(address)?;
+            }
+            self.user_breakpoints[index].address = Some(address);
+        }
+
+        Ok(())
+    }
+
+    // Breakpoints in an unloaded library become pending again. The memory they were written to is gone, so the original
+    // instruction is not restored.
+    pub(crate) fn unresolve_breakpoints_in(&mut self, range: std::ops::Range<u64>) {
+        for breakpoint in &mut self.user_breakpoints {
+            let Some(address) = breakpoint.address.filter(|address| range.contains(address)) else {
+                continue;
+            };
+
+            if let BreakpointLocation::Address(_) = breakpoint.location {
+                warn!(
+                    "Breakpoint {} at 0x{address:012x} was in an unloaded library and is pending until code is loaded \
+                     there again",
+                    breakpoint.id
+                );
+            }
+
+            self.breakpoints.remove(&address);
+            breakpoint.address = None;
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use elf::{ElfBytes, endian::AnyEndian};
+use log::*;
+
+use crate::{
+    Debugger, Result,
+    elf_summary::parse_build_id,
+    module::{Module, load_line_table},
+    symbols::SymbolIndex,
+};
+
+pub const DEFAULT_DEBUG_FILE_DIRECTORY: &str = "/usr/lib/debug";
+
+/// A file containing the debug information of a stripped module.
+#[derive(Debug)]
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub(crate) data: Arc<[u8]>,
+}
+
+/// Searches the separate debug file of an ELF file, first by its build ID and then by its `.gnu_debuglink` section.
+pub fn find_debug_file(
+    path: &Path,
+    data: &[u8],
+    debug_file_directories: &[PathBuf],
+) -> Result<Option<DebugFile>> {
+    let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+
+    if let Some(build_id) = parse_build_id(&elf_bytes)?
+        && let Some(debug_file) = find_by_build_id(&build_id, debug_file_directories)
+    {
+        return Ok(Some(debug_file));
+    }
+
+    if let Some((name, crc)) = parse_debuglink(&elf_bytes)? {
+        return Ok(find_by_debuglink(path, &name, crc, debug_file_directories));
+    }
+
+    Ok(None)
+}
+
+// Debug files are stored as <debug directory>/.build-id/<first byte>/<remaining bytes>.debug
+fn find_by_build_id(build_id: &[u8], debug_file_directories: &[PathBuf]) -> Option<DebugFile> {
+    let (first, rest) = build_id.split_first()?;
+    let rest: String = rest.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    debug_file_directories.iter().find_map(|directory| {
+        let path = directory
+            .join(".build-id")
+            .join(format!("{first:02x}"))
+            .join(format!("{rest}.debug"));
+        let data = std::fs::read(&path).ok()?;
+
+        // Guard against stale links left behind by a previous version of the package
+        let matches = ElfBytes::<AnyEndian>::minimal_parse(&data)
+            .ok()
+            .and_then(|elf_bytes| parse_build_id(&elf_bytes).ok().flatten())
+            .is_some_and(|debug_build_id| debug_build_id == build_id);
+        if !matches {
+            warn!("Ignoring {}, its build ID does not match", path.display());
+            return None;
+        }
+
+        Some(DebugFile {
+            path,
+            data: data.into(),
+        })
+    })
+}
+
+fn parse_debuglink(elf_bytes: &ElfBytes<'_, AnyEndian>) -> Result<Option<(String, u32)>> {
+    let Some(section_header) = elf_bytes.section_header_by_name(".gnu_debuglink")? else {
+        return Ok(None);
+    };
+    let (data, _) = elf_bytes.section_data(&section_header)?;
+
+    Ok(parse_debuglink_data(data))
+}
+
+// The section contains the NUL-terminated file name, padded to 4 bytes, followed by the CRC-32 of the debug file
+fn parse_debuglink_data(data: &[u8]) -> Option<(String, u32)> {
+    let name_length = data.iter().position(|byte| *byte == 0)?;
+    let crc_offset = (name_length + 1).next_multiple_of(4);
+    let crc = data.get(crc_offset..crc_offset + 4)?;
+
+    let name = String::from_utf8_lossy(&data[..name_length]).into_owned();
+    let crc = u32::from_le_bytes(crc.try_into().expect("slice to be 4 bytes long"));
+
+    Some((name, crc))
+}
+
+// Searched like gdb does: next to the file, in a .debug subdirectory and below the debug directories, mirroring the
+// directory of the file
+fn find_by_debuglink(
+    path: &Path,
+    name: &str,
+    crc: u32,
+    debug_file_directories: &[PathBuf],
+) -> Option<DebugFile> {
+    let path = path.canonicalize().ok()?;
+    let directory = path.parent().map(Path::to_owned).unwrap_or_default();
+
+    let mut candidates = vec![directory.join(name), directory.join(".debug").join(name)];
+    candidates.extend(debug_file_directories.iter().map(|debug_directory| {
+        debug_directory
+            .join(directory.strip_prefix("/").unwrap_or(&directory))
+            .join(name)
+    }));
+
+    candidates.into_iter().find_map(|candidate| {
+        // The file itself may carry a debuglink to a file with the same name
+        if candidate == path {
+            return None;
+        }
+
+        let data = std::fs::read(&candidate).ok()?;
+        if crc32fast::hash(&data) != crc {
+            warn!("Ignoring {}, its CRC does not match", candidate.display());
+            return None;
+        }
+
+        Some(DebugFile {
+            path: candidate,
+            data: data.into(),
+        })
+    })
+}
+
+impl Module {
+    /// Looks for the separate debug file of this module and merges its symbols into the symbol index. The debug file
+    /// only adds information, so failures are logged and the module is used without it.
+    pub fn load_debug_file(&mut self, debug_file_directories: &[PathBuf]) {
+        if self.debug_file.is_some() {
+            return;
+        }
+
+        let debug_file = match find_debug_file(&self.path, &self.data, debug_file_directories) {
+            Ok(Some(debug_file)) => debug_file,
+            Ok(None) => return,
+            Err(err) => {
+                warn!(
+                    "Failed to look up the debug file of {}: {err}",
+                    self.path.display()
+                );
+                return;
+            }
+        };
+
+        info!(
+            "Reading symbols of {} from {}",
+            self.path.display(),
+            debug_file.path.display()
+        );
+
+        match SymbolIndex::from_elf_files(&self.data, Some(&debug_file.data)) {
+            Ok(symbols) => self.symbols = symbols,
+            Err(err) => {
+                warn!(
+                    "Failed to read debug file {}: {err}",
+                    debug_file.path.display()
+                );
+                return;
+            }
+        }
+        // Stripped files usually have no line table of their own
+        if self.lines.is_empty() {
+            self.lines = load_line_table(&debug_file.path, &debug_file.data);
+        }
+        self.debug_file = Some(debug_file);
+        // Debug information read before came from the stripped module itself
+        self.debug_info = OnceCell::new();
+    }
+}
+
+impl Debugger {
+    pub fn debug_file_directories(&self) -> &[PathBuf] {
+        &self.debug_file_directories
+    }
+
+    /// Sets the directories searched for separate debug files and looks up the debug files of all loaded modules that
+    /// do not have one yet.
+    pub fn set_debug_file_directories(&mut self, directories: Vec<PathBuf>) -> Result<()> {
+        self.debug_file_directories = directories;
+
+        self.executable
+            .load_debug_file(&self.debug_file_directories);
+        for library in &mut self.shared_libraries {
+            library.load_debug_file(&self.debug_file_directories);
+        }
+
+        // Symbols from the debug files may resolve pending breakpoints
+        self.resolve_pending_breakpoints()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_debuglink_with_padding() {
+        // "libfoo.so.debug" is 15 bytes, the terminating NUL already ends on a multiple of 4
+        let mut data = b"libfoo.so.debug\0".to_vec();
+        data.extend(0xdeadbeef_u32.to_le_bytes());
+        assert_eq!(
+            parse_debuglink_data(&data),
+            Some(("libfoo.so.debug".to_owned(), 0xdeadbeef))
+        );
+
+        // "a.so.debug" plus its NUL is 11 bytes, padded to 12
+        let mut data = b"a.so.debug\0\0".to_vec();
+        data.extend(0x01020304_u32.to_le_bytes());
+        assert_eq!(
+            parse_debuglink_data(&data),
+            Some(("a.so.debug".to_owned(), 0x01020304))
+        );
+
+        let mut data = b"ab\0\0".to_vec();
+        data.extend(0x0000_0001_u32.to_le_bytes());
+        assert_eq!(parse_debuglink_data(&data), Some(("ab".to_owned(), 1)));
+    }
+
+    #[test]
+    fn rejects_truncated_debuglink() {
+        assert_eq!(parse_debuglink_data(b""), None);
+        assert_eq!(parse_debuglink_data(b"no terminator"), None);
+        assert_eq!(parse_debuglink_data(b"ab\0\0\x01\x02\x03"), None);
+    }
+
+    #[test]
+    fn find_by_debuglink_checks_crc() {
+        let directory = std::env::temp_dir().join(format!("debuglink-test-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join(".debug")).unwrap();
+        let directory = directory.canonicalize().unwrap();
+        let path = directory.join("libfoo.so");
+        std::fs::write(&path, b"stripped").unwrap();
+        std::fs::write(directory.join(".debug/libfoo.so.debug"), b"debug info").unwrap();
+
+        let crc = crc32fast::hash(b"debug info");
+        let debug_file = find_by_debuglink(&path, "libfoo.so.debug", crc, &[]).unwrap();
+        assert_eq!(debug_file.path, directory.join(".debug/libfoo.so.debug"));
+        assert_eq!(*debug_file.data, *b"debug info");
+
+        assert!(find_by_debuglink(&path, "libfoo.so.debug", crc ^ 1, &[]).is_none());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
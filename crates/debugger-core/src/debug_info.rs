@@ -0,0 +1,480 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    ops::Range,
+    sync::Arc,
+};
+
+use gimli::{
+    AttributeValue, DebugInfoOffset, DebuggingInformationEntry, DwAt, DwLang, Dwarf, Reader, Unit,
+    UnitOffset,
+};
+
+use log::*;
+
+use crate::{
+    Debugger, Result,
+    dwarf::{SharedDwarfReader, load_shared_dwarf},
+    line_table::{InlinedSubroutine, file_path},
+    module::Module,
+    symbols::demangle,
+    types::{Type, TypeId, TypeSource},
+};
+
+pub(crate) type DwarfUnit = Unit<SharedDwarfReader>;
+pub(crate) type DwarfEntry = DebuggingInformationEntry<SharedDwarfReader>;
+pub(crate) type DwarfAttributeValue = AttributeValue<SharedDwarfReader>;
+
+// Abstract origins and specifications may form chains, malformed ones could even form cycles
+const MAX_ORIGIN_DEPTH: usize = 8;
+
+// The parsed `.debug_info` of a module. It is only read on demand, as it is a lot larger than the line table, and
+// then kept alongside the module.
+#[derive(Debug)]
+pub(crate) struct LoadedDebugInfo {
+    dwarf: Dwarf<SharedDwarfReader>,
+    // Sorted by their offset, as they are read in order
+    units: Vec<DwarfUnit>,
+    // Collected on first use, as this walks all entries
+    inlined_subroutines: OnceCell<InlinedSubroutines>,
+}
+
+impl LoadedDebugInfo {
+    pub(crate) fn load(data: &Arc<[u8]>) -> Result<Self> {
+        let dwarf = load_shared_dwarf(data)?;
+
+        let mut units = Vec::new();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            units.push(dwarf.unit(header)?);
+        }
+
+        Ok(Self {
+            dwarf,
+            units,
+            inlined_subroutines: OnceCell::new(),
+        })
+    }
+
+    // Views the debug information of the module at an index of `Debugger::modules`
+    pub(crate) fn for_module(&self, module: usize) -> DebugInfo<'_> {
+        DebugInfo {
+            module,
+            dwarf: &self.dwarf,
+            units: &self.units,
+            inlined_subroutines: &self.inlined_subroutines,
+        }
+    }
+}
+
+// The debug information of a module, as seen from an inspector that knows the index of the module
+pub(crate) struct DebugInfo<'a> {
+    pub(crate) module: usize,
+    pub(crate) dwarf: &'a Dwarf<SharedDwarfReader>,
+    pub(crate) units: &'a [DwarfUnit],
+    inlined_subroutines: &'a OnceCell<InlinedSubroutines>,
+}
+
+impl<'a> DebugInfo<'a> {
+    pub(crate) fn unit_at(&self, offset: DebugInfoOffset) -> Option<(&DwarfUnit, UnitOffset)> {
+        let index = self
+            .units
+            .partition_point(|unit| unit.header.offset().0 <= offset.0)
+            .checked_sub(1)?;
+        let unit = &self.units[index];
+
+        Some((unit, offset.to_unit_offset(&unit.header)?))
+    }
+
+    // Finds the compilation unit covering a link-time address
+    pub(crate) fn unit_for_address(&self, address: u64) -> Result<Option<&DwarfUnit>> {
+        for unit in self.units {
+            let mut ranges = self.dwarf.unit_ranges(unit)?;
+            while let Some(range) = ranges.next()? {
+                if (range.begin..range.end).contains(&address) {
+                    return Ok(Some(unit));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub(crate) fn language(&self, unit: &DwarfUnit) -> Option<DwLang> {
+        let mut entries = unit.entries();
+        match entries
+            .next_dfs()
+            .ok()??
+            .attr_value(gimli::DW_AT_language)?
+        {
+            AttributeValue::Language(language) => Some(language),
+            _ => None,
+        }
+    }
+
+    // Resolves a reference to another entry, which may be located in another unit
+    pub(crate) fn reference<'s>(
+        &'s self,
+        unit: &'s DwarfUnit,
+        value: DwarfAttributeValue,
+    ) -> Option<(&'s DwarfUnit, UnitOffset)> {
+        match value {
+            AttributeValue::UnitRef(offset) => Some((unit, offset)),
+            AttributeValue::DebugInfoRef(offset) => self.unit_at(offset),
+            _ => None,
+        }
+    }
+
+    // Looks up an attribute of an entry. Concrete instances of inlined functions and out-of-line definitions only
+    // carry the attributes that differ from their abstract origin or specification, the rest is looked up there.
+    pub(crate) fn attr<'s>(
+        &'s self,
+        unit: &'s DwarfUnit,
+        entry: &DwarfEntry,
+        name: DwAt,
+    ) -> Result<Option<(&'s DwarfUnit, DwarfAttributeValue)>> {
+        if let Some(value) = entry.attr_value(name) {
+            return Ok(Some((unit, value)));
+        }
+
+        let mut unit = unit;
+        let mut origin = origin_of(entry);
+        for _ in 0..MAX_ORIGIN_DEPTH {
+            let Some((origin_unit, offset)) =
+                origin.and_then(|origin| self.reference(unit, origin))
+            else {
+                break;
+            };
+            let origin_entry = origin_unit.entry(offset)?;
+            if let Some(value) = origin_entry.attr_value(name) {
+                return Ok(Some((origin_unit, value)));
+            }
+
+            unit = origin_unit;
+            origin = origin_of(&origin_entry);
+        }
+
+        Ok(None)
+    }
+
+    pub(crate) fn name(&self, unit: &DwarfUnit, entry: &DwarfEntry) -> Result<Option<String>> {
+        let Some((unit, value)) = self.attr(unit, entry, gimli::DW_AT_name)? else {
+            return Ok(None);
+        };
+
+        let name = self.dwarf.attr_string(unit, value)?;
+        Ok(Some(name.to_string_lossy()?.into_owned()))
+    }
+
+    pub(crate) fn type_of(&self, unit: &DwarfUnit, entry: &DwarfEntry) -> Result<Option<TypeId>> {
+        let Some((unit, value)) = self.attr(unit, entry, gimli::DW_AT_type)? else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .reference(unit, value)
+            .and_then(|(unit, offset)| offset.to_debug_info_offset(&unit.header))
+            .map(|offset| {
+                TypeId(TypeSource::Dwarf {
+                    module: self.module,
+                    offset,
+                })
+            }))
+    }
+
+    // Checks whether the address ranges of an entry contain a link-time address. Entries without ranges, e.g. some
+    // lexical blocks, cover the whole range of their parent.
+    pub(crate) fn entry_contains(
+        &self,
+        unit: &DwarfUnit,
+        entry: &DwarfEntry,
+        address: u64,
+    ) -> Result<bool> {
+        if !entry.has_attr(gimli::DW_AT_low_pc) && !entry.has_attr(gimli::DW_AT_ranges) {
+            return Ok(entry.tag() == gimli::DW_TAG_lexical_block);
+        }
+
+        let mut ranges = self.dwarf.die_ranges(unit, entry)?;
+        while let Some(range) = ranges.next()? {
+            if (range.begin..range.end).contains(&address) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub(crate) fn inlined_subroutines(&self) -> &'a InlinedSubroutines {
+        self.inlined_subroutines.get_or_init(|| {
+            let mut subroutines = Vec::new();
+            for unit in self.units {
+                // Only limits showing and stepping through inlined frames, so malformed units are skipped
+                if let Err(err) = self.collect_inlined_subroutines(unit, &mut subroutines) {
+                    warn!("Failed to read the inlined subroutines of a unit: {err}");
+                }
+            }
+
+            InlinedSubroutines::new(subroutines)
+        })
+    }
+
+    fn collect_inlined_subroutines(
+        &self,
+        unit: &DwarfUnit,
+        subroutines: &mut Vec<InlinedSubroutine>,
+    ) -> Result<()> {
+        // Tree depths of the inlined subroutines enclosing the current entry
+        let mut enclosing: Vec<isize> = Vec::new();
+
+        let mut entries = unit.entries();
+        while let Some(entry) = entries.next_dfs()? {
+            while enclosing
+                .last()
+                .is_some_and(|depth| *depth >= entry.depth())
+            {
+                enclosing.pop();
+            }
+            if entry.tag() != gimli::DW_TAG_inlined_subroutine {
+                continue;
+            }
+
+            let mut ranges = Vec::new();
+            let mut die_ranges = self.dwarf.die_ranges(unit, entry)?;
+            while let Some(range) = die_ranges.next()? {
+                ranges.push(range.begin..range.end);
+            }
+            let start = ranges.iter().map(|range| range.start).min().unwrap_or(0);
+            // Since DWARF 5 the entry can also be given as an offset from the start of the code
+            let entry_pc = match entry.attr_value(gimli::DW_AT_entry_pc) {
+                Some(value) => match self.dwarf.attr_address(unit, value.clone())? {
+                    Some(address) => Some(address),
+                    None => value.udata_value().map(|offset| start.wrapping_add(offset)),
+                },
+                None => None,
+            };
+
+            let call_file = match (entry.attr_value(gimli::DW_AT_call_file), &unit.line_program) {
+                (Some(AttributeValue::FileIndex(index)), Some(program)) => {
+                    let path = file_path(self.dwarf, unit, program.header(), index)?;
+                    (!path.as_os_str().is_empty()).then_some(path)
+                }
+                _ => None,
+            };
+            let attr_number = |name| {
+                entry
+                    .attr_value(name)
+                    .and_then(|value| value.udata_value())
+                    .unwrap_or(0)
+            };
+
+            subroutines.push(InlinedSubroutine {
+                name: self.function_name(unit, entry)?,
+                entry_pc: entry_pc.unwrap_or(start),
+                ranges,
+                call_file,
+                call_line: attr_number(gimli::DW_AT_call_line),
+                call_column: attr_number(gimli::DW_AT_call_column),
+                depth: enclosing.len(),
+            });
+            enclosing.push(entry.depth());
+        }
+
+        Ok(())
+    }
+
+    // The demangled linkage name is preferred over the plain name, as it includes the path of Rust functions
+    fn function_name(&self, unit: &DwarfUnit, entry: &DwarfEntry) -> Result<Option<String>> {
+        let linkage_name = match self.attr(unit, entry, gimli::DW_AT_linkage_name)? {
+            Some(linkage_name) => Some(linkage_name),
+            None => self.attr(unit, entry, gimli::DW_AT_MIPS_linkage_name)?,
+        };
+        let Some((unit, value)) = linkage_name else {
+            return self.name(unit, entry);
+        };
+
+        let name = self
+            .dwarf
+            .attr_string(unit, value)?
+            .to_string_lossy()?
+            .into_owned();
+        Ok(Some(demangle(&name).unwrap_or(name)))
+    }
+}
+
+// The inlined subroutines of a module and an index to look them up by address
+#[derive(Debug, Default)]
+pub(crate) struct InlinedSubroutines {
+    // In the order of their entries
+    pub(crate) subroutines: Vec<InlinedSubroutine>,
+    // Disjoint, sorted address ranges and the inlined subroutines covering them, the innermost one first
+    index: Vec<(Range<u64>, Vec<usize>)>,
+}
+
+impl InlinedSubroutines {
+    // Splits the code of the inlined subroutines into disjoint ranges, each covered by the same inlined subroutines.
+    // Their ranges nest, so only few of them cover any address.
+    fn new(subroutines: Vec<InlinedSubroutine>) -> Self {
+        // Ends sort before starts at the same address
+        let mut boundaries: Vec<(u64, bool, usize)> = subroutines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, subroutine)| {
+                subroutine
+                    .ranges
+                    .iter()
+                    .filter(|range| !range.is_empty())
+                    .flat_map(move |range| [(range.start, true, index), (range.end, false, index)])
+            })
+            .collect();
+        boundaries.sort_unstable();
+
+        let mut index = Vec::new();
+        let mut covering: Vec<usize> = Vec::new();
+        let mut start = 0;
+        for (address, is_start, subroutine) in boundaries {
+            if address > start && !covering.is_empty() {
+                let mut innermost_first = covering.clone();
+                innermost_first
+                    .sort_by_key(|index| (std::cmp::Reverse(subroutines[*index].depth), *index));
+                innermost_first.dedup();
+                index.push((start..address, innermost_first));
+            }
+            start = address;
+
+            if is_start {
+                covering.push(subroutine);
+            } else if let Some(position) = covering.iter().position(|index| *index == subroutine) {
+                covering.swap_remove(position);
+            }
+        }
+
+        Self { subroutines, index }
+    }
+
+    // Finds the inlined subroutines whose code contains a link-time address, as indices into `subroutines`. The
+    // innermost one comes first.
+    pub(crate) fn at(&self, address: u64) -> &[usize] {
+        let index = self
+            .index
+            .partition_point(|(range, _)| range.end <= address);
+
+        self.index
+            .get(index)
+            .filter(|(range, _)| range.contains(&address))
+            .map_or(&[], |(_, subroutines)| subroutines)
+    }
+}
+
+impl Module {
+    // The inlined subroutines of the module at an index of `Debugger::modules`, `None` without debug information
+    pub(crate) fn inlined_subroutines(&self, index: usize) -> Option<&InlinedSubroutines> {
+        Some(self.debug_info()?.for_module(index).inlined_subroutines())
+    }
+}
+
+fn origin_of(entry: &DwarfEntry) -> Option<DwarfAttributeValue> {
+    entry
+        .attr_value(gimli::DW_AT_abstract_origin)
+        .or_else(|| entry.attr_value(gimli::DW_AT_specification))
+}
+
+// Reads a constant attribute, e.g. an enumerator value or an array bound. Data forms do not tell whether the value is
+// signed, they are read as unsigned.
+pub(crate) fn constant_value(value: &DwarfAttributeValue) -> Option<i128> {
+    match *value {
+        AttributeValue::Data1(value) => Some(value.into()),
+        AttributeValue::Data2(value) => Some(value.into()),
+        AttributeValue::Data4(value) => Some(value.into()),
+        AttributeValue::Data8(value) => Some(value.into()),
+        AttributeValue::Udata(value) => Some(value.into()),
+        AttributeValue::Sdata(value) => Some(value.into()),
+        _ => None,
+    }
+}
+
+// The debug information of all modules, each one is loaded on its first use
+pub(crate) struct DebugInfoCache<'a> {
+    modules: Vec<&'a Module>,
+    debug_info: Vec<OnceCell<Option<DebugInfo<'a>>>>,
+    pub(crate) synthetic_types: RefCell<Vec<Type>>,
+}
+
+impl<'a> DebugInfoCache<'a> {
+    pub(crate) fn new(debugger: &'a Debugger) -> Self {
+        let modules: Vec<_> = debugger.modules().collect();
+        let debug_info = modules.iter().map(|_| OnceCell::new()).collect();
+
+        Self {
+            modules,
+            debug_info,
+            synthetic_types: RefCell::default(),
+        }
+    }
+
+    pub(crate) fn module(&self, index: usize) -> &'a Module {
+        self.modules[index]
+    }
+
+    pub(crate) fn module_containing(&self, address: u64) -> Option<usize> {
+        self.modules
+            .iter()
+            .position(|module| module.contains(address))
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&DebugInfo<'a>> {
+        self.debug_info
+            .get(index)?
+            .get_or_init(|| {
+                self.modules[index]
+                    .debug_info()
+                    .map(|loaded| loaded.for_module(index))
+            })
+            .as_ref()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.modules.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subroutine(ranges: Vec<Range<u64>>, depth: usize) -> InlinedSubroutine {
+        InlinedSubroutine {
+            name: None,
+            entry_pc: ranges[0].start,
+            ranges,
+            call_file: None,
+            call_line: 0,
+            call_column: 0,
+            depth,
+        }
+    }
+
+    #[test]
+    fn inlined_subroutines_at_orders_innermost_first() {
+        let inlined_subroutines = vec![
+            subroutine(vec![0x1000..0x1010, 0x1020..0x1030], 0),
+            subroutine(vec![0x1004..0x1008, 0x1024..0x1028], 1),
+            subroutine(vec![0x1006..0x1007, 0x1026..0x1027], 2),
+            // Adjacent to the first range of the outermost subroutine
+            subroutine(vec![0x1010..0x1018, 0x1040..0x1040], 0),
+        ];
+        let inlined = InlinedSubroutines::new(inlined_subroutines);
+
+        assert_eq!(inlined.at(0xfff), &[] as &[usize]);
+        assert_eq!(inlined.at(0x1000), &[0]);
+        assert_eq!(inlined.at(0x1004), &[1, 0]);
+        assert_eq!(inlined.at(0x1006), &[2, 1, 0]);
+        assert_eq!(inlined.at(0x1007), &[1, 0]);
+        assert_eq!(inlined.at(0x100f), &[0]);
+        assert_eq!(inlined.at(0x1010), &[3]);
+        assert_eq!(inlined.at(0x1018), &[] as &[usize]);
+        assert_eq!(inlined.at(0x1026), &[2, 1, 0]);
+        assert_eq!(inlined.at(0x1027), &[1, 0]);
+        assert_eq!(inlined.at(0x1028), &[0]);
+        assert_eq!(inlined.at(0x1030), &[] as &[usize]);
+        assert_eq!(inlined.at(0x1040), &[] as &[usize]);
+    }
+}
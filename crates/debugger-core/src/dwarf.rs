@@ -0,0 +1,57 @@
+use std::{ops::Range, sync::Arc};
+
+use elf::{ElfBytes, endian::AnyEndian};
+use gimli::{Dwarf, EndianArcSlice, EndianReader, EndianSlice, RunTimeEndian, SectionId};
+use log::*;
+
+use crate::Result;
+
+pub(crate) type DwarfReader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+// Shares ownership of the file data, so the parsed debug information can be kept alongside its module
+pub(crate) type SharedDwarfReader = EndianArcSlice<RunTimeEndian>;
+
+/// Borrows the DWARF sections of an ELF file. Missing and compressed sections are treated as empty.
+pub(crate) fn load_dwarf(data: &[u8]) -> Result<Dwarf<DwarfReader<'_>>> {
+    load_sections(data, |range, endian| EndianSlice::new(&data[range], endian))
+}
+
+/// Like [`load_dwarf`], but the sections share ownership of the file data instead of borrowing it.
+pub(crate) fn load_shared_dwarf(data: &Arc<[u8]>) -> Result<Dwarf<SharedDwarfReader>> {
+    load_sections(data, |range, endian| {
+        EndianReader::new(data.clone(), endian).range(range)
+    })
+}
+
+// Creates the reader of each DWARF section from its byte range in the file
+fn load_sections<R>(
+    data: &[u8],
+    reader: impl Fn(Range<usize>, RunTimeEndian) -> R,
+) -> Result<Dwarf<R>> {
+    let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let endian = match elf_bytes.ehdr.endianness {
+        AnyEndian::Little => RunTimeEndian::Little,
+        AnyEndian::Big => RunTimeEndian::Big,
+    };
+
+    let dwarf = Dwarf::load(|section: SectionId| -> Result<R> {
+        let range = match elf_bytes.section_header_by_name(section.name())? {
+            Some(section_header) => match elf_bytes.section_data(&section_header)? {
+                (section_data, None) if !section_data.is_empty() => {
+                    let start = section_header.sh_offset as usize;
+                    start..start + section_data.len()
+                }
+                (_, None) => 0..0,
+                (_, Some(_)) => {
+                    warn!("Compressed section {} is not supported", section.name());
+                    0..0
+                }
+            },
+            None => 0..0,
+        };
+
+        Ok(reader(range, endian))
+    })?;
+
+    Ok(dwarf)
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+
+use elf::{
+    ElfBytes,
+    abi::{
+        DT_NEEDED, DT_NULL, DT_RPATH, DT_RUNPATH, DT_SONAME, PF_R, PF_W, PF_X, PT_NOTE, SHF_ALLOC,
+        SHF_EXECINSTR, SHF_WRITE, SHT_NOTE,
+    },
+    endian::AnyEndian,
+    note::Note,
+    to_str::{d_tag_to_str, e_type_to_string, p_type_to_string, sh_type_to_string},
+};
+
+use crate::{Debugger, Result, module::Module};
+
+#[derive(Debug, Clone)]
+pub struct SectionSummary {
+    pub name: String,
+    pub section_type: u32,
+    pub flags: u64,
+    /// The link-time address, 0 for sections that are not loaded.
+    pub address: u64,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl SectionSummary {
+    pub fn type_name(&self) -> String {
+        sh_type_to_string(self.section_type)
+    }
+
+    /// The flags in the notation of readelf, e.g. `WA` for writable and allocated sections.
+    pub fn flags_string(&self) -> String {
+        [(SHF_WRITE, 'W'), (SHF_ALLOC, 'A'), (SHF_EXECINSTR, 'X')]
+            .into_iter()
+            .filter(|(flag, _)| self.flags & *flag as u64 != 0)
+            .map(|(_, letter)| letter)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentSummary {
+    pub segment_type: u32,
+    pub flags: u32,
+    pub offset: u64,
+    /// The link-time address of the segment.
+    pub virtual_address: u64,
+    pub file_size: u64,
+    pub memory_size: u64,
+    pub alignment: u64,
+}
+
+impl SegmentSummary {
+    pub fn type_name(&self) -> String {
+        p_type_to_string(self.segment_type)
+    }
+
+    /// The permissions of the segment, e.g. `R-X` for code.
+    pub fn flags_string(&self) -> String {
+        [(PF_R, 'R'), (PF_W, 'W'), (PF_X, 'X')]
+            .into_iter()
+            .map(|(flag, letter)| if self.flags & flag != 0 { letter } else { '-' })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicEntry {
+    pub tag: i64,
+    pub value: u64,
+    /// The referenced string for entries pointing into the dynamic string table, e.g. `DT_NEEDED`.
+    pub string: Option<String>,
+}
+
+impl DynamicEntry {
+    pub fn tag_name(&self) -> String {
+        match d_tag_to_str(self.tag) {
+            Some(name) => name.to_owned(),
+            None => format!("{:#x}", self.tag),
+        }
+    }
+}
+
+impl fmt::Display for DynamicEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.string {
+            Some(string) => write!(f, "{string}"),
+            None => write!(f, "{:#x}", self.value),
+        }
+    }
+}
+
+/// An overview of the headers of an ELF file, similar to what readelf shows.
+#[derive(Debug, Clone)]
+pub struct ElfSummary {
+    pub elf_type: u16,
+    /// The link-time entry point.
+    pub entry: u64,
+    pub sections: Vec<SectionSummary>,
+    pub segments: Vec<SegmentSummary>,
+    pub dynamic: Vec<DynamicEntry>,
+    pub build_id: Option<Vec<u8>>,
+}
+
+impl ElfSummary {
+    pub fn from_elf_data(data: &[u8]) -> Result<Self> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+
+        let sections = match elf_bytes.section_headers_with_strtab()? {
+            (Some(section_headers), Some(string_table)) => section_headers
+                .iter()
+                .map(|section_header| {
+                    Ok(SectionSummary {
+                        name: string_table
+                            .get(section_header.sh_name as usize)?
+                            .to_owned(),
+                        section_type: section_header.sh_type,
+                        flags: section_header.sh_flags,
+                        address: section_header.sh_addr,
+                        offset: section_header.sh_offset,
+                        size: section_header.sh_size,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            _ => Vec::new(),
+        };
+
+        let segments = elf_bytes
+            .segments()
+            .map(|segments| {
+                segments
+                    .iter()
+                    .map(|segment| SegmentSummary {
+                        segment_type: segment.p_type,
+                        flags: segment.p_flags,
+                        offset: segment.p_offset,
+                        virtual_address: segment.p_vaddr,
+                        file_size: segment.p_filesz,
+                        memory_size: segment.p_memsz,
+                        alignment: segment.p_align,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            elf_type: elf_bytes.ehdr.e_type,
+            entry: elf_bytes.ehdr.e_entry,
+            sections,
+            segments,
+            dynamic: parse_dynamic_entries(&elf_bytes)?,
+            build_id: parse_build_id(&elf_bytes)?,
+        })
+    }
+
+    pub fn type_name(&self) -> String {
+        e_type_to_string(self.elf_type)
+    }
+
+    /// The shared libraries this file depends on, in the order of its `DT_NEEDED` entries.
+    pub fn needed(&self) -> impl Iterator<Item = &str> {
+        self.dynamic
+            .iter()
+            .filter(|entry| entry.tag == DT_NEEDED)
+            .filter_map(|entry| entry.string.as_deref())
+    }
+
+    /// The build ID as a lowercase hex string.
+    pub fn build_id_string(&self) -> Option<String> {
+        self.build_id
+            .as_ref()
+            .map(|build_id| build_id.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+fn parse_dynamic_entries(elf_bytes: &ElfBytes<'_, AnyEndian>) -> Result<Vec<DynamicEntry>> {
+    let Some(dynamic_table) = elf_bytes.dynamic()? else {
+        return Ok(Vec::new());
+    };
+
+    let string_table = match elf_bytes.section_header_by_name(".dynstr")? {
+        Some(dynstr) => Some(elf_bytes.section_data_as_strtab(&dynstr)?),
+        None => None,
+    };
+
+    dynamic_table
+        .iter()
+        .take_while(|entry| entry.d_tag != DT_NULL)
+        .map(|entry| {
+            let tag = entry.d_tag;
+            let value = entry.d_val();
+
+            let string = match (tag, &string_table) {
+                (DT_NEEDED | DT_SONAME | DT_RPATH | DT_RUNPATH, Some(string_table)) => {
+                    Some(string_table.get(value as usize)?.to_owned())
+                }
+                _ => None,
+            };
+
+            Ok(DynamicEntry { tag, value, string })
+        })
+        .collect()
+}
+
+/// Reads the GNU build ID note from the note sections, or from the note segments if there are no section headers.
+pub(crate) fn parse_build_id(elf_bytes: &ElfBytes<'_, AnyEndian>) -> Result<Option<Vec<u8>>> {
+    if let Some(section_headers) = elf_bytes.section_headers() {
+        for section_header in section_headers
+            .iter()
+            .filter(|section_header| section_header.sh_type == SHT_NOTE)
+        {
+            for note in elf_bytes.section_data_as_notes(&section_header)? {
+                if let Note::GnuBuildId(build_id) = note {
+                    return Ok(Some(build_id.0.to_vec()));
+                }
+            }
+        }
+    }
+
+    if let Some(segments) = elf_bytes.segments() {
+        for segment in segments.iter().filter(|segment| segment.p_type == PT_NOTE) {
+            for note in elf_bytes.segment_data_as_notes(&segment)? {
+                if let Note::GnuBuildId(build_id) = note {
+                    return Ok(Some(build_id.0.to_vec()));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+impl Module {
+    pub fn elf_summary(&self) -> Result<ElfSummary> {
+        ElfSummary::from_elf_data(&self.data)
+    }
+}
+
+impl Debugger {
+    pub fn executable_elf_summary(&self) -> Result<ElfSummary> {
+        self.executable.elf_summary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use elf::abi::{ET_DYN, PT_LOAD, SHT_PROGBITS};
+
+    use super::*;
+
+    #[test]
+    fn flags_use_readelf_notation() {
+        let section = SectionSummary {
+            name: ".data".to_owned(),
+            section_type: SHT_PROGBITS,
+            flags: (SHF_WRITE | SHF_ALLOC) as u64,
+            address: 0x4000,
+            offset: 0x3000,
+            size: 0x10,
+        };
+        assert_eq!(section.flags_string(), "WA");
+        assert_eq!(section.type_name(), "SHT_PROGBITS");
+
+        let segment = SegmentSummary {
+            segment_type: PT_LOAD,
+            flags: PF_R | PF_X,
+            offset: 0x1000,
+            virtual_address: 0x1000,
+            file_size: 0x100,
+            memory_size: 0x100,
+            alignment: 0x1000,
+        };
+        assert_eq!(segment.flags_string(), "R-X");
+        assert_eq!(segment.type_name(), "PT_LOAD");
+    }
+
+    #[test]
+    fn dynamic_entry_names_unknown_tags() {
+        let entry = DynamicEntry {
+            tag: 0x7abc_0000,
+            value: 0x20,
+            string: None,
+        };
+        assert_eq!(entry.tag_name(), "0x7abc0000");
+        assert_eq!(entry.to_string(), "0x20");
+    }
+
+    #[test]
+    fn summarizes_test_executable() {
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let summary = ElfSummary::from_elf_data(&data).unwrap();
+
+        assert_eq!(summary.elf_type, ET_DYN);
+        let text = summary
+            .sections
+            .iter()
+            .find(|section| section.name == ".text")
+            .unwrap();
+        assert_eq!(text.flags_string(), "AX");
+        assert!((text.address..text.address + text.size).contains(&summary.entry));
+        assert!(summary.needed().any(|needed| needed.starts_with("libc.so")));
+    }
+}
@@ -0,0 +1,1103 @@
+use std::cmp::Ordering;
+
+use crate::{
+    Debugger, Error, Result,
+    backtrace::register_number,
+    expression::{BinaryOperator, Expression, TypeName, UnaryOperator},
+    types::{BaseEncoding, Member, PointerKind, Type, TypeId},
+    value::{Value, ValueLocation, integer, x87_extended_to_f64},
+    variables::Inspector,
+};
+
+// A value reduced to a number, which is what arithmetic and comparisons operate on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    Integer {
+        value: i128,
+        signed: bool,
+    },
+    Float(f64),
+    Pointer {
+        address: u64,
+        pointee: Option<TypeId>,
+    },
+}
+
+impl Scalar {
+    fn is_true(&self) -> bool {
+        match *self {
+            Scalar::Integer { value, .. } => value != 0,
+            Scalar::Float(value) => value != 0.0,
+            Scalar::Pointer { address, .. } => address != 0,
+        }
+    }
+
+    fn is_signed(&self) -> bool {
+        match *self {
+            Scalar::Integer { signed, .. } => signed,
+            Scalar::Float(_) => true,
+            Scalar::Pointer { .. } => false,
+        }
+    }
+
+    fn as_integer(&self) -> Option<i128> {
+        match *self {
+            Scalar::Integer { value, .. } => Some(value),
+            Scalar::Float(_) => None,
+            Scalar::Pointer { address, .. } => Some(address.into()),
+        }
+    }
+
+    fn as_float(&self) -> f64 {
+        match *self {
+            Scalar::Integer { value, .. } => value as f64,
+            Scalar::Float(value) => value,
+            Scalar::Pointer { address, .. } => address as f64,
+        }
+    }
+}
+
+fn evaluation_error(message: impl Into<String>) -> Error {
+    Error::EvaluateExpression(message.into())
+}
+
+impl Inspector<'_> {
+    /// Evaluates an expression in the context of the selected frame. Assignments are only evaluated by
+    /// [`Debugger::assign`], as they modify the tracee.
+    pub fn evaluate(&self, expression: &Expression) -> Result<Value> {
+        match expression {
+            Expression::Integer(value) => Ok(match i64::try_from(*value) {
+                Ok(value) => self.integer_value(value.into(), true),
+                Err(_) => self.integer_value((*value).into(), false),
+            }),
+            Expression::Float(value) => Ok(self.scalar_value(Scalar::Float(*value))),
+            Expression::Character(value) => Ok(Value {
+                ty: Some(self.base_type("char", BaseEncoding::SignedChar, 1)),
+                location: ValueLocation::Data(vec![*value]),
+            }),
+            Expression::Register(name) => self.register(name),
+            Expression::Identifier(name) => self.identifier(name),
+            Expression::Unary { operator, operand } => self.unary(*operator, operand),
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => self.binary(*operator, left, right),
+            Expression::Assign { .. } => Err(evaluation_error(
+                "assignments are not allowed here, use set var",
+            )),
+            Expression::Cast { ty, operand } => {
+                let ty = self.resolve_type_name(ty)?;
+                let value = self.evaluate(operand)?;
+                self.cast(&value, ty)
+            }
+            Expression::Member { operand, member } => {
+                let value = self.evaluate(operand)?;
+                self.member(&value, member)
+            }
+            Expression::Index { operand, index } => {
+                let value = self.evaluate(operand)?;
+                let index = self.evaluate(index)?;
+                self.index(&value, &index)
+            }
+        }
+    }
+
+    /// Whether a value is non-zero, e.g. the result of the condition of a breakpoint.
+    pub fn is_true(&self, value: &Value) -> Result<bool> {
+        Ok(self.scalar(value)?.is_true())
+    }
+
+    /// Interprets a value as an address: pointers point to it, while integers, arrays and functions are converted.
+    pub fn as_address(&self, value: &Value) -> Result<u64> {
+        self.scalar(value)?
+            .as_integer()
+            .map(|address| address as u64)
+            .ok_or_else(|| evaluation_error("a floating point number cannot be used as an address"))
+    }
+
+    fn register(&self, name: &str) -> Result<Value> {
+        let register =
+            register_number(name).ok_or_else(|| Error::UnknownRegister(name.to_owned()))?;
+        let frame = self
+            .frame()
+            .ok_or_else(|| evaluation_error("no frame is selected"))?;
+
+        // Like GDB, the pc points to code and the stack and frame pointers to data
+        let ty = match name {
+            "rip" | "pc" => {
+                let function = self.debug_info.add_type(Type::Function {
+                    return_type: None,
+                    parameters: Vec::new(),
+                    variadic: false,
+                });
+                self.pointer_type(Some(function))
+            }
+            "rsp" | "sp" | "rbp" | "fp" => self.pointer_type(None),
+            _ => self.base_type("long", BaseEncoding::Signed, 8),
+        };
+        let location = match frame.registers.get(register) {
+            Some(value) => ValueLocation::Data(value.to_le_bytes().to_vec()),
+            None => ValueLocation::OptimizedOut,
+        };
+
+        Ok(Value {
+            ty: Some(ty),
+            location,
+        })
+    }
+
+    // Variables are looked up in the debug information first, then in the symbol tables
+    fn identifier(&self, name: &str) -> Result<Value> {
+        match self.find_variable(name) {
+            Ok(variable) => return Ok(variable.value),
+            Err(Error::UnknownVariable(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        // Without type information, variables of integer size are shown as integers and others as bytes
+        match self.debugger.resolve_variable(name) {
+            Ok((address, symbol)) => {
+                let ty = match symbol.size {
+                    1 => self.base_type("uint8_t", BaseEncoding::Unsigned, 1),
+                    2 => self.base_type("uint16_t", BaseEncoding::Unsigned, 2),
+                    4 => self.base_type("uint32_t", BaseEncoding::Unsigned, 4),
+                    8 => self.base_type("uint64_t", BaseEncoding::Unsigned, 8),
+                    size => {
+                        let byte = self.base_type("uint8_t", BaseEncoding::Unsigned, 1);
+                        self.debug_info.add_type(Type::Array {
+                            element: Some(byte),
+                            dimensions: vec![Some(size)],
+                        })
+                    }
+                };
+                return Ok(Value {
+                    ty: Some(ty),
+                    location: ValueLocation::Address(address),
+                });
+            }
+            Err(Error::UnknownVariable(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        let address = self
+            .debugger
+            .resolve_symbol_runtime_address(name)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_owned()))?;
+        let function = self.debug_info.add_type(Type::Function {
+            return_type: None,
+            parameters: Vec::new(),
+            variadic: false,
+        });
+
+        Ok(Value {
+            ty: Some(function),
+            location: ValueLocation::Address(address),
+        })
+    }
+
+    fn unary(&self, operator: UnaryOperator, operand: &Expression) -> Result<Value> {
+        let value = self.evaluate(operand)?;
+
+        match operator {
+            UnaryOperator::Deref => self.deref(&value),
+            UnaryOperator::AddressOf => {
+                let address = value.address().ok_or_else(|| {
+                    evaluation_error("cannot take the address of a value that is not in memory")
+                })?;
+                Ok(self.scalar_value(Scalar::Pointer {
+                    address,
+                    pointee: value.ty,
+                }))
+            }
+            UnaryOperator::Not => Ok(self.bool_value(!self.scalar(&value)?.is_true())),
+            UnaryOperator::Negate => match self.scalar(&value)? {
+                Scalar::Integer { value, signed } => {
+                    Ok(self.integer_value(value.wrapping_neg(), signed))
+                }
+                Scalar::Float(value) => Ok(self.scalar_value(Scalar::Float(-value))),
+                Scalar::Pointer { .. } => Err(evaluation_error("cannot negate a pointer")),
+            },
+            UnaryOperator::BitNot => match self.scalar(&value)? {
+                Scalar::Integer { value, signed } => Ok(self.integer_value(!value, signed)),
+                _ => Err(evaluation_error("the operand of ~ has to be an integer")),
+            },
+        }
+    }
+
+    fn deref(&self, value: &Value) -> Result<Value> {
+        let (address, pointee) = match self.debug_info.strip_typedefs(value.ty)?.1 {
+            Type::Pointer { pointee, size, .. } => {
+                let data = self
+                    .read_value(value, size)?
+                    .ok_or_else(|| evaluation_error("value has been optimized out"))?;
+                (integer(&data, false) as u64, pointee)
+            }
+            _ => match self.scalar(value)? {
+                Scalar::Pointer { address, pointee } => (address, pointee),
+                _ => {
+                    return Err(evaluation_error(
+                        "attempt to take contents of a non-pointer value",
+                    ));
+                }
+            },
+        };
+
+        if pointee.is_none() {
+            return Err(evaluation_error(
+                "attempt to take contents of a void pointer",
+            ));
+        }
+
+        Ok(Value {
+            ty: pointee,
+            location: ValueLocation::Address(address),
+        })
+    }
+
+    fn binary(
+        &self,
+        operator: BinaryOperator,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<Value> {
+        // The right operand of logical operators is only evaluated if it decides the result
+        if let BinaryOperator::And | BinaryOperator::Or = operator {
+            let left = self.scalar(&self.evaluate(left)?)?.is_true();
+            if left == (operator == BinaryOperator::Or) {
+                return Ok(self.bool_value(left));
+            }
+            let right = self.scalar(&self.evaluate(right)?)?.is_true();
+            return Ok(self.bool_value(right));
+        }
+
+        let left = self.scalar(&self.evaluate(left)?)?;
+        let right = self.scalar(&self.evaluate(right)?)?;
+
+        if let Some(result) = compare(operator, left, right) {
+            return Ok(self.bool_value(result));
+        }
+
+        let result = arithmetic(operator, left, right, |pointee| self.pointee_size(pointee))?;
+
+        Ok(self.scalar_value(result))
+    }
+
+    fn cast(&self, value: &Value, ty: Option<TypeId>) -> Result<Value> {
+        match self.debug_info.strip_typedefs(ty)?.1 {
+            Type::Void => Ok(Value {
+                ty: None,
+                location: ValueLocation::Data(Vec::new()),
+            }),
+            Type::Base { .. } | Type::Enum { .. } | Type::Pointer { .. } => {
+                let data = self.convert(self.scalar(value)?, ty)?;
+                Ok(Value {
+                    ty,
+                    location: ValueLocation::Data(data),
+                })
+            }
+            // Other values are reinterpreted, e.g. `*(struct point *)address`
+            _ => Ok(Value {
+                ty,
+                location: value.location.clone(),
+            }),
+        }
+    }
+
+    /// Looks up a member of a struct or union by its name, also in anonymous structs and unions and in base classes.
+    /// Like GDB, pointers and references are followed, so `.` can be used instead of `->`.
+    pub fn member(&self, value: &Value, name: &str) -> Result<Value> {
+        let value = match self.debug_info.strip_typedefs(value.ty)?.1 {
+            Type::Pointer { .. } => self.deref(value)?,
+            _ => value.clone(),
+        };
+        let Type::Composite { members, .. } = self.debug_info.strip_typedefs(value.ty)?.1 else {
+            return Err(evaluation_error(format!(
+                "attempt to extract a member of a value of type {}, which is not a struct or union",
+                self.type_name(value.ty)
+            )));
+        };
+
+        self.find_member(&value, &members, name)?
+            .ok_or_else(|| evaluation_error(format!("there is no member named {name}")))
+    }
+
+    fn find_member(&self, value: &Value, members: &[Member], name: &str) -> Result<Option<Value>> {
+        if let Some(member) = members
+            .iter()
+            .find(|member| member.name.as_deref() == Some(name))
+        {
+            return self.member_value(value, member).map(Some);
+        }
+
+        for member in members
+            .iter()
+            .filter(|member| member.name.is_none() || member.is_base)
+        {
+            let Type::Composite { members, .. } = self.debug_info.strip_typedefs(member.ty)?.1
+            else {
+                continue;
+            };
+            let inner = value.at_offset(member.offset, member.ty);
+            if let Some(found) = self.find_member(&inner, &members, name)? {
+                return Ok(Some(found));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Bit fields are not addressable, their value is extracted
+    fn member_value(&self, value: &Value, member: &Member) -> Result<Value> {
+        let Some(bit_field) = member.bit_field else {
+            return Ok(value.at_offset(member.offset, member.ty));
+        };
+
+        let bits = self.read_bit_field(value, member)?;
+        let size = self.type_size(member.ty)?.unwrap_or(8) as usize;
+        let mut data = bits.to_le_bytes()[..size.min(16)].to_vec();
+        data.resize(size.max(bit_field.bit_size.div_ceil(8) as usize), 0);
+
+        Ok(Value {
+            ty: member.ty,
+            location: ValueLocation::Data(data),
+        })
+    }
+
+    fn index(&self, value: &Value, index: &Value) -> Result<Value> {
+        let index = self
+            .scalar(index)?
+            .as_integer()
+            .ok_or_else(|| evaluation_error("array subscript is not an integer"))?;
+
+        match self.debug_info.strip_typedefs(value.ty)?.1 {
+            // Indexing an array does not require it to be in memory
+            Type::Array {
+                element,
+                dimensions,
+            } => {
+                let inner_dimensions = &dimensions[1.min(dimensions.len())..];
+                let element_size = self.type_size(element)?.unwrap_or(0);
+                let stride = inner_dimensions
+                    .iter()
+                    .map(|dimension| dimension.unwrap_or(0))
+                    .product::<u64>()
+                    * element_size;
+                let element = self.sub_array(element, inner_dimensions);
+                Ok(value.at_offset((index as u64).wrapping_mul(stride), element))
+            }
+            Type::Pointer {
+                kind: PointerKind::Reference | PointerKind::RvalueReference,
+                ..
+            } => self.index(&self.deref(value)?, &self.integer_value(index, true)),
+            _ => match self.scalar(value)? {
+                Scalar::Pointer { address, pointee } => {
+                    let offset = (index as u64).wrapping_mul(self.pointee_size(pointee)?);
+                    self.deref(&self.scalar_value(Scalar::Pointer {
+                        address: address.wrapping_add(offset),
+                        pointee,
+                    }))
+                }
+                _ => Err(evaluation_error(
+                    "cannot subscript a value that is neither an array nor a pointer",
+                )),
+            },
+        }
+    }
+
+    // Reduces a value to a number. References are followed, arrays and functions decay to pointers like in C.
+    fn scalar(&self, value: &Value) -> Result<Scalar> {
+        if value.location == ValueLocation::OptimizedOut {
+            return Err(evaluation_error("value has been optimized out"));
+        }
+
+        let (ty, parsed) = self.debug_info.strip_typedefs(value.ty)?;
+        let read = |size| {
+            self.read_value(value, size)?
+                .ok_or_else(|| evaluation_error("value has been optimized out"))
+        };
+
+        let scalar = match parsed {
+            Type::Base {
+                encoding: BaseEncoding::Float,
+                size,
+                ..
+            } => {
+                let data = read(size)?;
+                let value = match data.len() {
+                    4 => f32::from_le_bytes(data.try_into().expect("length to be 4")).into(),
+                    8 => f64::from_le_bytes(data.try_into().expect("length to be 8")),
+                    10 | 16 => x87_extended_to_f64(&data),
+                    size => {
+                        return Err(evaluation_error(format!(
+                            "floating point numbers of {size} bytes are not supported"
+                        )));
+                    }
+                };
+                Scalar::Float(value)
+            }
+            Type::Base { encoding, size, .. } => Scalar::Integer {
+                value: integer(&read(size)?, encoding.is_signed()),
+                signed: encoding.is_signed(),
+            },
+            Type::Enum {
+                size, underlying, ..
+            } => {
+                let signed = matches!(
+                    self.debug_info.strip_typedefs(underlying)?.1,
+                    Type::Base { encoding, .. } if encoding.is_signed()
+                );
+                Scalar::Integer {
+                    value: integer(&read(size)?, signed),
+                    signed,
+                }
+            }
+            Type::Pointer {
+                kind: PointerKind::Pointer,
+                pointee,
+                size,
+                ..
+            } => Scalar::Pointer {
+                address: integer(&read(size)?, false) as u64,
+                pointee,
+            },
+            Type::Pointer { .. } => return self.scalar(&self.deref(value)?),
+            Type::Array {
+                element,
+                dimensions,
+            } => Scalar::Pointer {
+                address: value.address().ok_or_else(|| {
+                    evaluation_error("an array that is not in memory cannot be used as a pointer")
+                })?,
+                pointee: self.sub_array(element, &dimensions[1.min(dimensions.len())..]),
+            },
+            Type::Function { .. } => Scalar::Pointer {
+                address: value.address().unwrap_or_default(),
+                pointee: ty,
+            },
+            _ => {
+                return Err(evaluation_error(format!(
+                    "a value of type {} cannot be used as a number",
+                    self.type_name(value.ty)
+                )));
+            }
+        };
+
+        Ok(scalar)
+    }
+
+    fn convert(&self, scalar: Scalar, ty: Option<TypeId>) -> Result<Vec<u8>> {
+        convert_scalar(scalar, &self.debug_info.strip_typedefs(ty)?.1)?.ok_or_else(|| {
+            evaluation_error(format!("cannot convert a number to {}", self.type_name(ty)))
+        })
+    }
+
+    fn resolve_type_name(&self, type_name: &TypeName) -> Result<Option<TypeId>> {
+        let name = type_name.name.as_str();
+        let mut ty = if name == "void" {
+            None
+        } else if let Some((encoding, size)) = builtin_type(name) {
+            Some(self.base_type(name, encoding, size))
+        } else {
+            Some(
+                self.debug_info
+                    .find_type(name)?
+                    .ok_or_else(|| Error::UnknownType(name.to_owned()))?,
+            )
+        };
+
+        for _ in 0..type_name.pointers {
+            ty = Some(self.pointer_type(ty));
+        }
+
+        Ok(ty)
+    }
+
+    // The size of the elements a pointer points to, `void *` is treated like `char *` like GCC does
+    fn pointee_size(&self, pointee: Option<TypeId>) -> Result<u64> {
+        match self.debug_info.strip_typedefs(pointee)?.1 {
+            Type::Void | Type::Function { .. } => Ok(1),
+            _ => self.type_size(pointee)?.ok_or_else(|| {
+                evaluation_error(format!(
+                    "cannot do arithmetic on pointers to {}, its size is unknown",
+                    self.type_name(pointee)
+                ))
+            }),
+        }
+    }
+
+    // The type of an element of a multi-dimensional array, which is an array itself
+    fn sub_array(&self, element: Option<TypeId>, dimensions: &[Option<u64>]) -> Option<TypeId> {
+        if dimensions.is_empty() {
+            return element;
+        }
+
+        Some(self.debug_info.add_type(Type::Array {
+            element,
+            dimensions: dimensions.to_vec(),
+        }))
+    }
+
+    fn base_type(&self, name: &str, encoding: BaseEncoding, size: u64) -> TypeId {
+        self.debug_info.add_type(Type::Base {
+            name: name.to_owned(),
+            encoding,
+            size,
+        })
+    }
+
+    fn pointer_type(&self, pointee: Option<TypeId>) -> TypeId {
+        self.debug_info.add_type(Type::Pointer {
+            kind: PointerKind::Pointer,
+            name: None,
+            pointee,
+            size: 8,
+        })
+    }
+
+    // Results of arithmetic are 64 bit wide, like `long` of C on x86-64
+    fn integer_value(&self, value: i128, signed: bool) -> Value {
+        let (name, encoding, value) = if signed {
+            ("long", BaseEncoding::Signed, value as i64 as i128)
+        } else {
+            (
+                "unsigned long",
+                BaseEncoding::Unsigned,
+                value as u64 as i128,
+            )
+        };
+
+        Value {
+            ty: Some(self.base_type(name, encoding, 8)),
+            location: ValueLocation::Data(value.to_le_bytes()[..8].to_vec()),
+        }
+    }
+
+    fn bool_value(&self, value: bool) -> Value {
+        Value {
+            ty: Some(self.base_type("bool", BaseEncoding::Boolean, 1)),
+            location: ValueLocation::Data(vec![value.into()]),
+        }
+    }
+
+    fn scalar_value(&self, scalar: Scalar) -> Value {
+        match scalar {
+            Scalar::Integer { value, signed } => self.integer_value(value, signed),
+            Scalar::Float(value) => Value {
+                ty: Some(self.base_type("double", BaseEncoding::Float, 8)),
+                location: ValueLocation::Data(value.to_le_bytes().to_vec()),
+            },
+            Scalar::Pointer { address, pointee } => Value {
+                ty: Some(self.pointer_type(pointee)),
+                location: ValueLocation::Data(address.to_le_bytes().to_vec()),
+            },
+        }
+    }
+}
+
+// Applies an arithmetic or bitwise operator. Integers are added to pointers in units of the size of their pointee.
+fn arithmetic(
+    operator: BinaryOperator,
+    left: Scalar,
+    right: Scalar,
+    pointee_size: impl Fn(Option<TypeId>) -> Result<u64>,
+) -> Result<Scalar> {
+    match (operator, left, right) {
+        (
+            BinaryOperator::Add | BinaryOperator::Subtract,
+            Scalar::Pointer { address, pointee },
+            Scalar::Integer { value, .. },
+        )
+        | (
+            BinaryOperator::Add,
+            Scalar::Integer { value, .. },
+            Scalar::Pointer { address, pointee },
+        ) => {
+            let offset = (value as i64 as u64).wrapping_mul(pointee_size(pointee)?);
+            let address = if operator == BinaryOperator::Add {
+                address.wrapping_add(offset)
+            } else {
+                address.wrapping_sub(offset)
+            };
+            Ok(Scalar::Pointer { address, pointee })
+        }
+        (
+            BinaryOperator::Subtract,
+            Scalar::Pointer { address, pointee },
+            Scalar::Pointer { address: other, .. },
+        ) => {
+            let difference = address.wrapping_sub(other) as i64;
+            let size = pointee_size(pointee)?.max(1) as i64;
+            Ok(Scalar::Integer {
+                value: (difference / size).into(),
+                signed: true,
+            })
+        }
+        (_, Scalar::Float(_), _) | (_, _, Scalar::Float(_)) => {
+            let (left, right) = (left.as_float(), right.as_float());
+            let result = match operator {
+                BinaryOperator::Add => left + right,
+                BinaryOperator::Subtract => left - right,
+                BinaryOperator::Multiply => left * right,
+                BinaryOperator::Divide => left / right,
+                _ => {
+                    return Err(evaluation_error(
+                        "invalid floating point operands for the operator",
+                    ));
+                }
+            };
+            Ok(Scalar::Float(result))
+        }
+        _ => {
+            let signed = left.is_signed() && right.is_signed();
+            let (left, right) = (
+                left.as_integer().unwrap_or_default(),
+                right.as_integer().unwrap_or_default(),
+            );
+            let result = match operator {
+                BinaryOperator::Add => left.wrapping_add(right),
+                BinaryOperator::Subtract => left.wrapping_sub(right),
+                BinaryOperator::Multiply => left.wrapping_mul(right),
+                BinaryOperator::Divide | BinaryOperator::Remainder if right == 0 => {
+                    return Err(evaluation_error("division by zero"));
+                }
+                BinaryOperator::Divide => left.wrapping_div(right),
+                BinaryOperator::Remainder => left.wrapping_rem(right),
+                BinaryOperator::BitAnd => left & right,
+                BinaryOperator::BitOr => left | right,
+                BinaryOperator::BitXor => left ^ right,
+                BinaryOperator::ShiftLeft => left.checked_shl(right as u32).unwrap_or(0),
+                BinaryOperator::ShiftRight => left.checked_shr(right as u32).unwrap_or(0),
+                _ => unreachable!("comparisons and logical operators are handled by the caller"),
+            };
+            Ok(Scalar::Integer {
+                value: result,
+                signed,
+            })
+        }
+    }
+}
+
+// Converts a number into the bytes of a value of a number or pointer type, `None` for other types
+fn convert_scalar(scalar: Scalar, ty: &Type) -> Result<Option<Vec<u8>>> {
+    let data = match *ty {
+        Type::Base {
+            encoding: BaseEncoding::Float,
+            size,
+            ..
+        } => match size {
+            4 => (scalar.as_float() as f32).to_le_bytes().to_vec(),
+            8 => scalar.as_float().to_le_bytes().to_vec(),
+            size => {
+                return Err(evaluation_error(format!(
+                    "floating point numbers of {size} bytes are not supported"
+                )));
+            }
+        },
+        Type::Base {
+            encoding: BaseEncoding::Boolean,
+            size,
+            ..
+        } => {
+            let mut data = vec![0; size as usize];
+            if let Some(first) = data.first_mut() {
+                *first = scalar.is_true().into();
+            }
+            data
+        }
+        Type::Base { size, .. } | Type::Enum { size, .. } | Type::Pointer { size, .. } => {
+            let value = match scalar {
+                Scalar::Float(value) => value as i128,
+                scalar => scalar.as_integer().unwrap_or_default(),
+            };
+            let mut data = value.to_le_bytes().to_vec();
+            data.resize(size as usize, 0);
+            data
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(data))
+}
+
+// Returns `None` if the operator is not a comparison
+fn compare(operator: BinaryOperator, left: Scalar, right: Scalar) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Scalar::Float(_), _) | (_, Scalar::Float(_)) => {
+            left.as_float().partial_cmp(&right.as_float())
+        }
+        _ => left.as_integer()?.partial_cmp(&right.as_integer()?),
+    };
+
+    let result = match operator {
+        BinaryOperator::Equal => ordering == Some(Ordering::Equal),
+        BinaryOperator::NotEqual => ordering != Some(Ordering::Equal),
+        BinaryOperator::Less => ordering == Some(Ordering::Less),
+        BinaryOperator::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        BinaryOperator::Greater => ordering == Some(Ordering::Greater),
+        BinaryOperator::GreaterEqual => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+// Types that can be named in casts without debug information, in Rust and C spelling
+fn builtin_type(name: &str) -> Option<(BaseEncoding, u64)> {
+    let builtin = match name {
+        "u8" => (BaseEncoding::Unsigned, 1),
+        "u16" => (BaseEncoding::Unsigned, 2),
+        "u32" => (BaseEncoding::Unsigned, 4),
+        "u64" | "usize" => (BaseEncoding::Unsigned, 8),
+        "u128" => (BaseEncoding::Unsigned, 16),
+        "i8" => (BaseEncoding::Signed, 1),
+        "i16" => (BaseEncoding::Signed, 2),
+        "i32" => (BaseEncoding::Signed, 4),
+        "i64" | "isize" => (BaseEncoding::Signed, 8),
+        "i128" => (BaseEncoding::Signed, 16),
+        "f32" | "float" => (BaseEncoding::Float, 4),
+        "f64" | "double" => (BaseEncoding::Float, 8),
+        "bool" | "_Bool" => (BaseEncoding::Boolean, 1),
+        name => return c_integer_type(name),
+    };
+
+    Some(builtin)
+}
+
+// C integer types are spelled with several words in any order, e.g. `unsigned long int` or `long unsigned`
+fn c_integer_type(name: &str) -> Option<(BaseEncoding, u64)> {
+    let mut unsigned = false;
+    let mut longs = 0;
+    let mut base = None;
+
+    for word in name.split(' ') {
+        match word {
+            "unsigned" => unsigned = true,
+            "signed" => {}
+            "long" => longs += 1,
+            "char" | "short" | "int" if base.is_none() => base = Some(word),
+            _ => return None,
+        }
+    }
+
+    let size = match (base, longs) {
+        (Some("char"), 0) => 1,
+        (Some("short"), 0) => 2,
+        (Some("int") | None, 0) => 4,
+        (Some("int") | None, 1 | 2) => 8,
+        _ => return None,
+    };
+    let encoding = match (base, unsigned) {
+        (Some("char"), true) => BaseEncoding::UnsignedChar,
+        (Some("char"), false) => BaseEncoding::SignedChar,
+        (_, true) => BaseEncoding::Unsigned,
+        (_, false) => BaseEncoding::Signed,
+    };
+
+    Some((encoding, size))
+}
+
+impl Debugger {
+    /// Evaluates an assignment like `count = count + 1` or `$rax = 0` and writes the new value into the tracee.
+    /// Registers can only be assigned in the innermost frame.
+    pub fn assign(&mut self, expression: &Expression) -> Result<()> {
+        let Expression::Assign { target, value } = expression else {
+            return Err(evaluation_error("expected an assignment like `x = 1`"));
+        };
+
+        if let Expression::Register(name) = target.as_ref() {
+            if self.selected_frame != 0 {
+                return Err(evaluation_error(
+                    "registers can only be assigned in the innermost frame",
+                ));
+            }
+
+            let new_value = {
+                let inspector = self.inspector();
+                inspector.as_address(&inspector.evaluate(value)?)?
+            };
+            return self.set_tracee_register(name, new_value);
+        }
+
+        let (address, data) = {
+            let inspector = self.inspector();
+            let target = inspector.evaluate(target)?;
+            let address = target.address().ok_or_else(|| {
+                evaluation_error("the left side of the assignment is not stored in memory")
+            })?;
+            let new_value = inspector.evaluate(value)?;
+
+            let data = match inspector.debug_info.strip_typedefs(target.ty)?.1 {
+                Type::Base { .. }
+                | Type::Enum { .. }
+                | Type::Pointer {
+                    kind: PointerKind::Pointer,
+                    ..
+                } => inspector.convert(inspector.scalar(&new_value)?, target.ty)?,
+                // Other values are copied as they are, if they have the same size
+                _ => {
+                    let size = inspector.type_size(target.ty)?;
+                    if size.is_none() || inspector.type_size(new_value.ty)? != size {
+                        return Err(evaluation_error(format!(
+                            "cannot assign a value of type {} to {}",
+                            inspector.type_name(new_value.ty),
+                            inspector.type_name(target.ty)
+                        )));
+                    }
+                    inspector
+                        .read_value(&new_value, size.unwrap_or_default())?
+                        .ok_or_else(|| evaluation_error("value has been optimized out"))?
+                }
+            };
+
+            (address, data)
+        };
+
+        self.write_memory(address, &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(value: i128) -> Scalar {
+        Scalar::Integer {
+            value,
+            signed: true,
+        }
+    }
+
+    fn unsigned(value: i128) -> Scalar {
+        Scalar::Integer {
+            value,
+            signed: false,
+        }
+    }
+
+    fn pointer(address: u64) -> Scalar {
+        Scalar::Pointer {
+            address,
+            pointee: None,
+        }
+    }
+
+    // Pointers in these tests point to 4 byte integers
+    fn evaluate(operator: BinaryOperator, left: Scalar, right: Scalar) -> Result<Scalar> {
+        arithmetic(operator, left, right, |_| Ok(4))
+    }
+
+    fn base(encoding: BaseEncoding, size: u64) -> Type {
+        Type::Base {
+            name: String::new(),
+            encoding,
+            size,
+        }
+    }
+
+    #[test]
+    fn integer_arithmetic() {
+        use BinaryOperator::*;
+
+        assert_eq!(evaluate(Add, signed(2), signed(3)).unwrap(), signed(5));
+        assert_eq!(
+            evaluate(Subtract, signed(2), signed(3)).unwrap(),
+            signed(-1)
+        );
+        assert_eq!(
+            evaluate(Multiply, signed(-4), signed(3)).unwrap(),
+            signed(-12)
+        );
+        // Division truncates towards zero like in C
+        assert_eq!(evaluate(Divide, signed(-7), signed(2)).unwrap(), signed(-3));
+        assert_eq!(
+            evaluate(Remainder, signed(-7), signed(2)).unwrap(),
+            signed(-1)
+        );
+        assert_eq!(
+            evaluate(BitAnd, signed(0b1100), signed(0b1010)).unwrap(),
+            signed(0b1000)
+        );
+        assert_eq!(
+            evaluate(BitOr, signed(0b1100), signed(0b1010)).unwrap(),
+            signed(0b1110)
+        );
+        assert_eq!(
+            evaluate(BitXor, signed(0b1100), signed(0b1010)).unwrap(),
+            signed(0b0110)
+        );
+        assert_eq!(
+            evaluate(ShiftLeft, signed(1), signed(4)).unwrap(),
+            signed(16)
+        );
+        assert_eq!(
+            evaluate(ShiftRight, signed(-16), signed(2)).unwrap(),
+            signed(-4)
+        );
+        assert_eq!(
+            evaluate(ShiftLeft, signed(1), signed(200)).unwrap(),
+            signed(0)
+        );
+        // The result is only signed if both operands are
+        assert_eq!(evaluate(Add, signed(1), unsigned(2)).unwrap(), unsigned(3));
+
+        assert!(evaluate(Divide, signed(1), signed(0)).is_err());
+        assert!(evaluate(Remainder, signed(1), unsigned(0)).is_err());
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        use BinaryOperator::*;
+
+        assert_eq!(
+            evaluate(Add, Scalar::Float(0.5), signed(1)).unwrap(),
+            Scalar::Float(1.5)
+        );
+        assert_eq!(
+            evaluate(Divide, signed(1), Scalar::Float(4.0)).unwrap(),
+            Scalar::Float(0.25)
+        );
+        assert_eq!(
+            evaluate(Divide, Scalar::Float(1.0), signed(0)).unwrap(),
+            Scalar::Float(f64::INFINITY)
+        );
+        assert!(evaluate(Remainder, Scalar::Float(1.0), signed(2)).is_err());
+        assert!(evaluate(BitAnd, signed(1), Scalar::Float(1.0)).is_err());
+    }
+
+    #[test]
+    fn pointer_arithmetic_scales_by_pointee_size() {
+        use BinaryOperator::*;
+
+        assert_eq!(
+            evaluate(Add, pointer(0x1000), signed(2)).unwrap(),
+            pointer(0x1008)
+        );
+        assert_eq!(
+            evaluate(Add, signed(2), pointer(0x1000)).unwrap(),
+            pointer(0x1008)
+        );
+        assert_eq!(
+            evaluate(Subtract, pointer(0x1000), signed(1)).unwrap(),
+            pointer(0xffc)
+        );
+        assert_eq!(
+            evaluate(Add, pointer(0x1000), signed(-1)).unwrap(),
+            pointer(0xffc)
+        );
+        assert_eq!(
+            evaluate(Subtract, pointer(0x1010), pointer(0x1000)).unwrap(),
+            signed(4)
+        );
+        assert_eq!(
+            evaluate(Subtract, pointer(0x1000), pointer(0x1010)).unwrap(),
+            signed(-4)
+        );
+        // The size of the pointee is unknown for pointers to incomplete structs
+        assert!(
+            arithmetic(Add, pointer(0x1000), signed(1), |_| Err(evaluation_error(
+                "unknown size"
+            )))
+            .is_err()
+        );
+        assert_eq!(
+            evaluate(BitAnd, pointer(0x1234), signed(0xff)).unwrap(),
+            unsigned(0x34)
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        use BinaryOperator::*;
+
+        assert_eq!(compare(Less, signed(-1), signed(0)), Some(true));
+        assert_eq!(
+            compare(GreaterEqual, signed(2), Scalar::Float(2.0)),
+            Some(true)
+        );
+        assert_eq!(compare(Equal, pointer(0x10), unsigned(0x10)), Some(true));
+        assert_eq!(
+            compare(NotEqual, Scalar::Float(f64::NAN), Scalar::Float(f64::NAN)),
+            Some(true)
+        );
+        assert_eq!(
+            compare(Equal, Scalar::Float(f64::NAN), Scalar::Float(f64::NAN)),
+            Some(false)
+        );
+        assert_eq!(compare(Add, signed(1), signed(1)), None);
+    }
+
+    #[test]
+    fn casts_convert_to_the_target_type() {
+        let u64_type = base(BaseEncoding::Unsigned, 8);
+        let i8_type = base(BaseEncoding::Signed, 1);
+
+        assert_eq!(
+            convert_scalar(signed(-1), &u64_type).unwrap(),
+            Some(vec![0xff; 8])
+        );
+        assert_eq!(
+            convert_scalar(signed(0x1ff), &i8_type).unwrap(),
+            Some(vec![0xff])
+        );
+        // Floats are truncated towards zero
+        assert_eq!(
+            convert_scalar(Scalar::Float(-2.75), &i8_type).unwrap(),
+            Some(vec![0xfe])
+        );
+        assert_eq!(
+            convert_scalar(signed(3), &base(BaseEncoding::Float, 8)).unwrap(),
+            Some(3.0f64.to_le_bytes().to_vec())
+        );
+        assert_eq!(
+            convert_scalar(Scalar::Float(0.5), &base(BaseEncoding::Float, 4)).unwrap(),
+            Some(0.5f32.to_le_bytes().to_vec())
+        );
+        assert_eq!(
+            convert_scalar(signed(42), &base(BaseEncoding::Boolean, 1)).unwrap(),
+            Some(vec![1])
+        );
+        assert_eq!(
+            convert_scalar(
+                pointer(0x1234),
+                &Type::Pointer {
+                    kind: PointerKind::Pointer,
+                    name: None,
+                    pointee: None,
+                    size: 8,
+                }
+            )
+            .unwrap(),
+            Some(0x1234u64.to_le_bytes().to_vec())
+        );
+        assert!(convert_scalar(signed(1), &base(BaseEncoding::Float, 16)).is_err());
+        assert_eq!(convert_scalar(signed(1), &Type::Void).unwrap(), None);
+    }
+
+    #[test]
+    fn builtin_types_in_c_and_rust_spelling() {
+        assert_eq!(builtin_type("u32"), Some((BaseEncoding::Unsigned, 4)));
+        assert_eq!(builtin_type("isize"), Some((BaseEncoding::Signed, 8)));
+        assert_eq!(builtin_type("double"), Some((BaseEncoding::Float, 8)));
+        assert_eq!(builtin_type("int"), Some((BaseEncoding::Signed, 4)));
+        assert_eq!(builtin_type("unsigned"), Some((BaseEncoding::Unsigned, 4)));
+        assert_eq!(
+            builtin_type("long unsigned int"),
+            Some((BaseEncoding::Unsigned, 8))
+        );
+        assert_eq!(builtin_type("long long"), Some((BaseEncoding::Signed, 8)));
+        assert_eq!(
+            builtin_type("unsigned char"),
+            Some((BaseEncoding::UnsignedChar, 1))
+        );
+        assert_eq!(
+            builtin_type("signed short"),
+            Some((BaseEncoding::Signed, 2))
+        );
+        assert_eq!(builtin_type("long char"), None);
+        assert_eq!(builtin_type("int int"), None);
+        assert_eq!(builtin_type("point"), None);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+    time::{Duration, Instant},
+};
+
+use log::*;
+use nix::{
+    poll::{PollFd, PollFlags, PollTimeout},
+    sys::{
+        ptrace,
+        wait::{WaitPidFlag, WaitStatus},
+    },
+};
+
+use crate::{ContinueExecutionOutcome, Debugger, Error, Result};
+
+#[derive(Debug, Clone, Copy)]
+pub enum SyscallEvent {
+    Entry { number: u64, arguments: [u64; 6] },
+    Exit { number: u64, return_value: i64 },
+}
+
+impl Debugger {
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Stops the tracee at every syscall entry and exit, which are reported as [`ContinueExecutionOutcome::Syscall`].
+    pub fn set_syscall_tracing(&mut self, enabled: bool) {
+        self.syscall_tracing = enabled;
+    }
+
+    /// Resumes the tracee without waiting for it to stop again.
+    ///
+    /// Use [`Debugger::poll_event`] or [`Debugger::wait_event`] to receive the next event.
+    pub fn resume(&mut self) -> Result<()> {
+        if self.running {
+            return Err(Error::TraceeRunning);
+        }
+        self.selected_frame = 0;
+
+        // Execute the original instruction first, if we are currently stopped at a software breakpoint
+        let pc = self.get_tracee_pc()?;
+        if self.breakpoints.contains_key(&pc)
+            && let Some(wait_status) = self.step_instruction()?
+        {
+            // Something else happened during the step, e.g. the instruction triggered a watchpoint. Report it with the
+            // next event instead.
+            self.deferred_wait_status = Some(wait_status);
+            self.running = true;
+            return Ok(());
+        }
+
+        let signal = self.pending_signal.take();
+        let result = if self.syscall_tracing {
+            ptrace::syscall(self.tracee_pid, signal)
+        } else {
+            // The matching syscall exit stop will not be reported
+            self.in_syscall = false;
+            ptrace::cont(self.tracee_pid, signal)
+        };
+
+        result.map_err(|errno| {
+            error!("failed ptrace cont call: {errno}");
+
+            Error::ContinueExecution
+        })?;
+
+        self.running = true;
+
+        Ok(())
+    }
+
+    /// Checks for a new tracee event without blocking.
+    pub fn poll_event(&mut self) -> Result<Option<ContinueExecutionOutcome>> {
+        if !self.running {
+            return Err(Error::TraceeNotRunning);
+        }
+
+        if let Some(wait_status) = self.deferred_wait_status.take() {
+            return self.process_wait_status(wait_status);
+        }
+
+        self.drain_event_fd();
+
+        let wait_status = nix::sys::wait::waitpid(self.tracee_pid, Some(WaitPidFlag::WNOHANG))
+            .map_err(|errno| {
+                error!("failed non-blocking waitpid: {errno}");
+
+                Error::ContinueExecution
+            })?;
+
+        match wait_status {
+            WaitStatus::StillAlive => Ok(None),
+            wait_status => self.process_wait_status(wait_status),
+        }
+    }
+
+    /// Waits for the next tracee event. Returns `None` if the timeout elapsed before an event occurred, a timeout of
+    /// `None` blocks until the next event.
+    pub fn wait_event(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<ContinueExecutionOutcome>> {
+        let Some(timeout) = timeout else {
+            // Observers may resume the tracee without reporting an event, so keep waiting until one is reported
+            loop {
+                if !self.running {
+                    return Err(Error::TraceeNotRunning);
+                }
+
+                let wait_status = match self.deferred_wait_status.take() {
+                    Some(wait_status) => wait_status,
+                    None => {
+                        let wait_status = self.wait_for_tracee()?;
+                        self.drain_event_fd();
+                        wait_status
+                    }
+                };
+
+                if let Some(outcome) = self.process_wait_status(wait_status)? {
+                    return Ok(Some(outcome));
+                }
+            }
+        };
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(outcome) = self.poll_event()? {
+                return Ok(Some(outcome));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+
+            let poll_timeout = PollTimeout::try_from(remaining).unwrap_or(PollTimeout::MAX);
+            let mut poll_fds = [PollFd::new(self.event_fd.as_fd(), PollFlags::POLLIN)];
+
+            match nix::poll::poll(&mut poll_fds, poll_timeout) {
+                Ok(_) | Err(nix::errno::Errno::EINTR) => {}
+                Err(errno) => {
+                    error!("failed to poll the tracee event fd: {errno}");
+
+                    return Err(Error::EventFd);
+                }
+            }
+        }
+    }
+
+    fn process_wait_status(
+        &mut self,
+        wait_status: WaitStatus,
+    ) -> Result<Option<ContinueExecutionOutcome>> {
+        let outcome = match self.handle_wait_status(wait_status)? {
+            ContinueExecutionOutcome::BreakpointHit(address)
+                if self.is_dynamic_linker_breakpoint(address) =>
+            {
+                let loaded = self.refresh_shared_libraries()?;
+                if self
+                    .breakpoint_at(address)
+                    .is_some_and(|breakpoint| breakpoint.enabled)
+                {
+                    ContinueExecutionOutcome::BreakpointHit(address)
+                } else if loaded.is_empty() {
+                    self.resume()?;
+                    return Ok(None);
+                } else {
+                    ContinueExecutionOutcome::LibrariesLoaded(loaded)
+                }
+            }
+            outcome => outcome,
+        };
+
+        // The stepping code decides itself how to handle a user breakpoint at the return address it waits for
+        if let ContinueExecutionOutcome::BreakpointHit(address) = outcome
+            && !self.is_awaited_return(address)?
+            && !self.breakpoint_condition_met(address)
+        {
+            self.resume()?;
+            return Ok(None);
+        }
+
+        self.apply_observers(outcome)
+    }
+
+    pub(crate) fn read_syscall_event(&mut self) -> Result<SyscallEvent> {
+        let regs = ptrace::getregs(self.tracee_pid).map_err(|errno| {
+            error!("Could not read registers of tracee: {errno}");
+
+            Error::ReadRegisters
+        })?;
+
+        self.in_syscall = !self.in_syscall;
+
+        let event = if self.in_syscall {
+            SyscallEvent::Entry {
+                number: regs.orig_rax,
+                arguments: [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9],
+            }
+        } else {
+            SyscallEvent::Exit {
+                number: regs.orig_rax,
+                return_value: regs.rax as i64,
+            }
+        };
+
+        Ok(event)
+    }
+
+    // Multiple SIGCHLD signals may be coalesced into a single one, so the pending ones are only used as a wake up
+    // notification and discarded before querying waitpid.
+    fn drain_event_fd(&self) {
+        while let Ok(Some(_siginfo)) = self.event_fd.read_signal() {}
+    }
+}
+
+impl AsFd for Debugger {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.event_fd.as_fd()
+    }
+}
+
+impl AsRawFd for Debugger {
+    fn as_raw_fd(&self) -> RawFd {
+        self.event_fd.as_raw_fd()
+    }
+}
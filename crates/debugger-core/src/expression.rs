@@ -0,0 +1,782 @@
+use std::fmt;
+
+use crate::{Error, Result};
+
+// Keywords that can only start a type name, so a parenthesized name starting with them is always a cast
+const TYPE_KEYWORDS: &[&str] = &[
+    "struct", "union", "enum", "class", "const", "volatile", "unsigned", "signed", "char", "short",
+    "int", "long", "float", "double", "void", "_Bool", "bool", "u8", "u16", "u32", "u64", "u128",
+    "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64",
+];
+
+// Multi-character punctuators have to be listed before their prefixes
+const PUNCTUATORS: &[&str] = &[
+    "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^",
+    "~", "!", "<", ">", "=", "(", ")", "[", "]", ".",
+];
+
+/// An expression of the small C-like language accepted by commands taking a value, e.g.
+/// `*(u64*)($rsp + 8)`, `point.x * 2` or `count > 3 && flags & 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Integer(u64),
+    Float(f64),
+    Character(u8),
+    /// A register of the selected frame, written as `$rsp`.
+    Register(String),
+    /// A variable or symbol, possibly with a path, e.g. `module::VARIABLE`.
+    Identifier(String),
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// Only evaluated by [`Debugger::assign`](crate::Debugger::assign).
+    Assign {
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
+    Cast {
+        ty: TypeName,
+        operand: Box<Expression>,
+    },
+    /// `a.b`, `a->b` is parsed as `(*a).b`.
+    Member {
+        operand: Box<Expression>,
+        member: String,
+    },
+    Index {
+        operand: Box<Expression>,
+        index: Box<Expression>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+    BitNot,
+    Deref,
+    AddressOf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+impl BinaryOperator {
+    // Binding strength of the operator, higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::BitOr => 3,
+            BinaryOperator::BitXor => 4,
+            BinaryOperator::BitAnd => 5,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 6,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => 10,
+        }
+    }
+
+    fn from_punctuator(punctuator: &str) -> Option<Self> {
+        let operator = match punctuator {
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Subtract,
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Remainder,
+            "&" => BinaryOperator::BitAnd,
+            "|" => BinaryOperator::BitOr,
+            "^" => BinaryOperator::BitXor,
+            "<<" => BinaryOperator::ShiftLeft,
+            ">>" => BinaryOperator::ShiftRight,
+            "==" => BinaryOperator::Equal,
+            "!=" => BinaryOperator::NotEqual,
+            "<" => BinaryOperator::Less,
+            "<=" => BinaryOperator::LessEqual,
+            ">" => BinaryOperator::Greater,
+            ">=" => BinaryOperator::GreaterEqual,
+            "&&" => BinaryOperator::And,
+            "||" => BinaryOperator::Or,
+            _ => return None,
+        };
+
+        Some(operator)
+    }
+}
+
+/// The type of a cast, e.g. `unsigned long` or `struct point` followed by a number of `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeName {
+    /// The words of the name without qualifiers, joined by a space.
+    pub name: String,
+    pub pointers: usize,
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.pointers > 0 {
+            write!(f, " {}", "*".repeat(self.pointers))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Integer(u64),
+    Float(f64),
+    Character(u8),
+    Register(String),
+    Identifier(String),
+    Punctuator(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Integer(value) => write!(f, "{value}"),
+            Token::Float(value) => write!(f, "{value}"),
+            Token::Character(value) => write!(f, "'{}'", *value as char),
+            Token::Register(name) => write!(f, "${name}"),
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Punctuator(punctuator) => write!(f, "{punctuator}"),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+
+        let expression = parser.assignment()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(Error::InvalidExpression(format!(
+                "unexpected `{token}` after the end of the expression"
+            ))),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+
+    while let Some(next) = rest.chars().next() {
+        let (token, length) = if next.is_ascii_digit() {
+            number(rest)?
+        } else if next == '\'' {
+            character(rest)?
+        } else if next == '$' {
+            let length = identifier_length(&rest[1..], false);
+            if length == 0 {
+                return Err(Error::InvalidExpression(
+                    "expected a register name after `$`".to_owned(),
+                ));
+            }
+            (Token::Register(rest[1..=length].to_owned()), length + 1)
+        } else if next.is_ascii_alphabetic() || next == '_' {
+            let length = identifier_length(rest, true);
+            (Token::Identifier(rest[..length].to_owned()), length)
+        } else if let Some(punctuator) = PUNCTUATORS
+            .iter()
+            .find(|punctuator| rest.starts_with(**punctuator))
+        {
+            (Token::Punctuator(punctuator), punctuator.len())
+        } else {
+            return Err(Error::InvalidExpression(format!(
+                "unexpected character `{next}`"
+            )));
+        };
+
+        tokens.push(token);
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+// Identifiers may contain `::` separated paths, e.g. `std::env::ARGV`
+fn identifier_length(input: &str, allow_paths: bool) -> usize {
+    let bytes = input.as_bytes();
+    let mut length = 0;
+
+    while length < bytes.len() {
+        let byte = bytes[length];
+        if byte.is_ascii_alphanumeric() || byte == b'_' {
+            length += 1;
+        } else if allow_paths
+            && input[length..].starts_with("::")
+            && bytes
+                .get(length + 2)
+                .is_some_and(|next| next.is_ascii_alphabetic() || *next == b'_')
+        {
+            length += 2;
+        } else {
+            break;
+        }
+    }
+
+    length
+}
+
+fn number(input: &str) -> Result<(Token, usize)> {
+    let length = input
+        .find(|character: char| !character.is_ascii_alphanumeric() && character != '.')
+        .unwrap_or(input.len());
+    let literal = &input[..length];
+
+    let token = if let Some(hex) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok().map(Token::Integer)
+    } else if literal.contains(['.', 'e', 'E']) {
+        literal.parse().ok().map(Token::Float)
+    } else {
+        literal.parse().ok().map(Token::Integer)
+    };
+
+    token
+        .map(|token| (token, length))
+        .ok_or_else(|| Error::InvalidExpression(format!("invalid number `{literal}`")))
+}
+
+fn character(input: &str) -> Result<(Token, usize)> {
+    let bytes = input.as_bytes();
+    let (value, length) = match bytes.get(1..3) {
+        Some([b'\\', escaped]) => {
+            let value = match escaped {
+                b'n' => b'\n',
+                b't' => b'\t',
+                b'r' => b'\r',
+                b'0' => 0,
+                other => *other,
+            };
+            (value, 3)
+        }
+        Some([value, _]) => (*value, 2),
+        _ => (0, 0),
+    };
+
+    if length == 0 || bytes.get(length) != Some(&b'\'') {
+        return Err(Error::InvalidExpression(
+            "invalid character literal".to_owned(),
+        ));
+    }
+
+    Ok((Token::Character(value), length + 1))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_punctuator(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Punctuator(punctuator)) => Some(punctuator),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| {
+            Error::InvalidExpression("unexpected end of the expression".to_owned())
+        })?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn expect(&mut self, punctuator: &str) -> Result<()> {
+        match self.next()? {
+            Token::Punctuator(next) if next == punctuator => Ok(()),
+            other => Err(Error::InvalidExpression(format!(
+                "expected `{punctuator}`, found `{other}`"
+            ))),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Identifier(name) => Ok(name),
+            other => Err(Error::InvalidExpression(format!(
+                "expected a name, found `{other}`"
+            ))),
+        }
+    }
+
+    // Assignments bind weakest and group from the right
+    fn assignment(&mut self) -> Result<Expression> {
+        let target = self.binary(0)?;
+        if self.peek_punctuator() != Some("=") {
+            return Ok(target);
+        }
+        self.position += 1;
+
+        Ok(Expression::Assign {
+            target: Box::new(target),
+            value: Box::new(self.assignment()?),
+        })
+    }
+
+    // Parses binary operators binding tighter than `min_precedence` by precedence climbing
+    fn binary(&mut self, min_precedence: u8) -> Result<Expression> {
+        let mut left = self.unary()?;
+
+        while let Some(operator) = self
+            .peek_punctuator()
+            .and_then(BinaryOperator::from_punctuator)
+            .filter(|operator| operator.precedence() > min_precedence)
+        {
+            self.position += 1;
+            let right = self.binary(operator.precedence())?;
+            left = Expression::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression> {
+        let operator = match self.peek_punctuator() {
+            Some("-") => UnaryOperator::Negate,
+            Some("!") => UnaryOperator::Not,
+            Some("~") => UnaryOperator::BitNot,
+            Some("*") => UnaryOperator::Deref,
+            Some("&") => UnaryOperator::AddressOf,
+            Some("(") => {
+                if let Some(ty) = self.cast_type() {
+                    return Ok(Expression::Cast {
+                        ty,
+                        operand: Box::new(self.unary()?),
+                    });
+                }
+                return self.postfix();
+            }
+            _ => return self.postfix(),
+        };
+        self.position += 1;
+
+        Ok(Expression::Unary {
+            operator,
+            operand: Box::new(self.unary()?),
+        })
+    }
+
+    // Tries to read a parenthesized type name. Like in C, `(name)` is ambiguous, it is only taken as a cast if it is
+    // followed by an operand, e.g. `(uint)x`, but `(x) - 1` is a subtraction.
+    fn cast_type(&mut self) -> Option<TypeName> {
+        let start = self.position;
+        let mut position = start + 1;
+
+        let mut words = Vec::new();
+        while let Some(Token::Identifier(word)) = self.tokens.get(position) {
+            words.push(word.as_str());
+            position += 1;
+        }
+        let mut pointers = 0;
+        while self.tokens.get(position) == Some(&Token::Punctuator("*")) {
+            pointers += 1;
+            position += 1;
+        }
+        if words.is_empty() || self.tokens.get(position) != Some(&Token::Punctuator(")")) {
+            return None;
+        }
+        position += 1;
+
+        let is_type = pointers > 0
+            || words.len() > 1
+            || TYPE_KEYWORDS.contains(&words[0])
+            || match self.tokens.get(position) {
+                Some(Token::Punctuator(punctuator)) => matches!(*punctuator, "(" | "~" | "!"),
+                Some(_) => true,
+                None => false,
+            };
+        if !is_type {
+            return None;
+        }
+
+        self.position = position;
+        let name = words
+            .into_iter()
+            .filter(|word| !matches!(*word, "const" | "volatile"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Some(TypeName { name, pointers })
+    }
+
+    fn postfix(&mut self) -> Result<Expression> {
+        let mut expression = self.primary()?;
+
+        loop {
+            expression = match self.peek_punctuator() {
+                Some(".") => {
+                    self.position += 1;
+                    Expression::Member {
+                        operand: Box::new(expression),
+                        member: self.identifier()?,
+                    }
+                }
+                Some("->") => {
+                    self.position += 1;
+                    Expression::Member {
+                        operand: Box::new(Expression::Unary {
+                            operator: UnaryOperator::Deref,
+                            operand: Box::new(expression),
+                        }),
+                        member: self.identifier()?,
+                    }
+                }
+                Some("[") => {
+                    self.position += 1;
+                    let index = self.assignment()?;
+                    self.expect("]")?;
+                    Expression::Index {
+                        operand: Box::new(expression),
+                        index: Box::new(index),
+                    }
+                }
+                _ => return Ok(expression),
+            };
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression> {
+        match self.next()? {
+            Token::Integer(value) => Ok(Expression::Integer(value)),
+            Token::Float(value) => Ok(Expression::Float(value)),
+            Token::Character(value) => Ok(Expression::Character(value)),
+            Token::Register(name) => Ok(Expression::Register(name)),
+            Token::Identifier(name) => Ok(Expression::Identifier(name)),
+            Token::Punctuator("(") => {
+                let expression = self.assignment()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            other => Err(Error::InvalidExpression(format!("unexpected `{other}`"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Expression {
+        Expression::parse(input).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        match Expression::parse(input) {
+            Err(Error::InvalidExpression(message)) => message,
+            other => panic!("expected {input:?} to be invalid, got {other:?}"),
+        }
+    }
+
+    fn identifier(name: &str) -> Expression {
+        Expression::Identifier(name.to_owned())
+    }
+
+    fn unary(operator: UnaryOperator, operand: Expression) -> Expression {
+        Expression::Unary {
+            operator,
+            operand: Box::new(operand),
+        }
+    }
+
+    fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Expression {
+        Expression::Binary {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn cast(name: &str, pointers: usize, operand: Expression) -> Expression {
+        Expression::Cast {
+            ty: TypeName {
+                name: name.to_owned(),
+                pointers,
+            },
+            operand: Box::new(operand),
+        }
+    }
+
+    fn member(operand: Expression, member: &str) -> Expression {
+        Expression::Member {
+            operand: Box::new(operand),
+            member: member.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parses_literals() {
+        assert_eq!(parse("42"), Expression::Integer(42));
+        assert_eq!(parse("0x2A"), Expression::Integer(42));
+        assert_eq!(parse("0XfF"), Expression::Integer(255));
+        assert_eq!(parse("1.5"), Expression::Float(1.5));
+        assert_eq!(parse("1e3"), Expression::Float(1000.0));
+        assert_eq!(parse("$rsp"), Expression::Register("rsp".to_owned()));
+        assert_eq!(parse("std::env::ARGV"), identifier("std::env::ARGV"));
+    }
+
+    #[test]
+    fn parses_character_escapes() {
+        assert_eq!(parse("'a'"), Expression::Character(b'a'));
+        assert_eq!(parse("'\\n'"), Expression::Character(b'\n'));
+        assert_eq!(parse("'\\t'"), Expression::Character(b'\t'));
+        assert_eq!(parse("'\\r'"), Expression::Character(b'\r'));
+        assert_eq!(parse("'\\0'"), Expression::Character(0));
+        assert_eq!(parse("'\\''"), Expression::Character(b'\''));
+        assert_eq!(parse("'\\\\'"), Expression::Character(b'\\'));
+        assert_eq!(parse("' '"), Expression::Character(b' '));
+    }
+
+    #[test]
+    fn binary_operators_follow_c_precedence() {
+        use BinaryOperator::*;
+
+        assert_eq!(
+            parse("1 + 2 * 3"),
+            binary(
+                Add,
+                Expression::Integer(1),
+                binary(Multiply, Expression::Integer(2), Expression::Integer(3))
+            )
+        );
+        // Operators of the same precedence group from the left
+        assert_eq!(
+            parse("8 - 4 - 2"),
+            binary(
+                Subtract,
+                binary(Subtract, Expression::Integer(8), Expression::Integer(4)),
+                Expression::Integer(2)
+            )
+        );
+        assert_eq!(
+            parse("a == 1 || b & 4 && c < 2"),
+            binary(
+                Or,
+                binary(Equal, identifier("a"), Expression::Integer(1)),
+                binary(
+                    And,
+                    binary(BitAnd, identifier("b"), Expression::Integer(4)),
+                    binary(Less, identifier("c"), Expression::Integer(2))
+                )
+            )
+        );
+        assert_eq!(
+            parse("1 << 2 + 3"),
+            binary(
+                ShiftLeft,
+                Expression::Integer(1),
+                binary(Add, Expression::Integer(2), Expression::Integer(3))
+            )
+        );
+        assert_eq!(
+            parse("(1 + 2) * 3"),
+            binary(
+                Multiply,
+                binary(Add, Expression::Integer(1), Expression::Integer(2)),
+                Expression::Integer(3)
+            )
+        );
+    }
+
+    #[test]
+    fn unary_operators_bind_tighter_than_binary() {
+        assert_eq!(
+            parse("-a * *b"),
+            binary(
+                BinaryOperator::Multiply,
+                unary(UnaryOperator::Negate, identifier("a")),
+                unary(UnaryOperator::Deref, identifier("b"))
+            )
+        );
+        assert_eq!(
+            parse("!~&x"),
+            unary(
+                UnaryOperator::Not,
+                unary(
+                    UnaryOperator::BitNot,
+                    unary(UnaryOperator::AddressOf, identifier("x"))
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn assignment_groups_from_the_right() {
+        assert_eq!(
+            parse("a = b = 1 + 1"),
+            Expression::Assign {
+                target: Box::new(identifier("a")),
+                value: Box::new(Expression::Assign {
+                    target: Box::new(identifier("b")),
+                    value: Box::new(binary(
+                        BinaryOperator::Add,
+                        Expression::Integer(1),
+                        Expression::Integer(1)
+                    )),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn parenthesized_name_is_only_a_cast_before_an_operand() {
+        assert_eq!(
+            parse("(x) - 1"),
+            binary(
+                BinaryOperator::Subtract,
+                identifier("x"),
+                Expression::Integer(1)
+            )
+        );
+        assert_eq!(parse("(x)"), identifier("x"));
+        assert_eq!(parse("(point)p"), cast("point", 0, identifier("p")));
+        assert_eq!(parse("(point)(p)"), cast("point", 0, identifier("p")));
+        // Type keywords are always a cast, so the `-` is a negation
+        assert_eq!(
+            parse("(u64)-1"),
+            cast(
+                "u64",
+                0,
+                unary(UnaryOperator::Negate, Expression::Integer(1))
+            )
+        );
+        assert_eq!(
+            parse("(point*) - 1"),
+            cast(
+                "point",
+                1,
+                unary(UnaryOperator::Negate, Expression::Integer(1))
+            )
+        );
+    }
+
+    #[test]
+    fn cast_type_drops_qualifiers() {
+        assert_eq!(
+            parse("*(const unsigned long **)($rsp + 8)"),
+            unary(
+                UnaryOperator::Deref,
+                cast(
+                    "unsigned long",
+                    2,
+                    binary(
+                        BinaryOperator::Add,
+                        Expression::Register("rsp".to_owned()),
+                        Expression::Integer(8)
+                    )
+                )
+            )
+        );
+        assert_eq!(
+            parse("(struct point *)0"),
+            cast("struct point", 1, Expression::Integer(0))
+        );
+    }
+
+    #[test]
+    fn parses_postfix_operators() {
+        assert_eq!(
+            parse("p->next->value"),
+            member(
+                unary(
+                    UnaryOperator::Deref,
+                    member(unary(UnaryOperator::Deref, identifier("p")), "next")
+                ),
+                "value"
+            )
+        );
+        assert_eq!(
+            parse("points[i + 1].x"),
+            member(
+                Expression::Index {
+                    operand: Box::new(identifier("points")),
+                    index: Box::new(binary(
+                        BinaryOperator::Add,
+                        identifier("i"),
+                        Expression::Integer(1)
+                    )),
+                },
+                "x"
+            )
+        );
+        // Postfix operators bind tighter than prefix ones
+        assert_eq!(
+            parse("*a.b"),
+            unary(UnaryOperator::Deref, member(identifier("a"), "b"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert_eq!(error(""), "unexpected end of the expression");
+        assert_eq!(error("1 +"), "unexpected end of the expression");
+        assert_eq!(
+            error("1 2"),
+            "unexpected `2` after the end of the expression"
+        );
+        assert_eq!(error("(1"), "unexpected end of the expression");
+        assert_eq!(error("a[1"), "unexpected end of the expression");
+        assert_eq!(error("a[1)"), "expected `]`, found `)`");
+        assert_eq!(error("a.1"), "expected a name, found `1`");
+        assert_eq!(error("a->"), "unexpected end of the expression");
+        assert_eq!(error(")"), "unexpected `)`");
+        assert_eq!(error("a # b"), "unexpected character `#`");
+        assert_eq!(error("$"), "expected a register name after `$`");
+        assert_eq!(error("0x"), "invalid number `0x`");
+        assert_eq!(error("12ab"), "invalid number `12ab`");
+        assert_eq!(error("1.2.3"), "invalid number `1.2.3`");
+        assert_eq!(error("'ab'"), "invalid character literal");
+        assert_eq!(error("'a"), "invalid character literal");
+        assert_eq!(error("''"), "invalid character literal");
+    }
+}
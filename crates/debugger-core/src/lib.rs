@@ -1,18 +1,57 @@
-use std::{collections::HashMap, mem, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf};
 
+use elf::{ElfBytes, endian::AnyEndian};
 use log::{debug, error, info};
 use nix::{
-    libc,
-    sys::{ptrace, signal::Signal, wait::WaitStatus},
+    libc::user_regs_struct,
+    sys::{
+        ptrace,
+        signal::{SigSet, Signal},
+        signalfd::{SfdFlags, SignalFd},
+        wait::WaitStatus,
+    },
     unistd::{ForkResult, Pid},
 };
 
+use auxv::AuxiliaryVector;
+use breakpoint::{Breakpoint, BreakpointGroupId, BreakpointId};
+use debug_file::DEFAULT_DEBUG_FILE_DIRECTORY;
+use event::SyscallEvent;
 use memory_map::ProcMemoryMaps;
-use watchpoint::Watchpoint;
-
+use module::Module;
+use observer::Observers;
+use pretty_printer::PrettyPrinters;
+use shared_library::DynamicLinkerState;
+use watchpoint::{DebugRegisterOffsets, Watchpoint, WatchpointDataCondition, WatchpointLength};
+
+pub mod auxv;
+pub mod backtrace;
+pub mod breakpoint;
+pub mod debug_file;
+mod debug_info;
+mod dwarf;
+pub mod elf_summary;
+mod evaluation;
+pub mod event;
+pub mod expression;
 mod libc_wrappers;
-mod memory_map;
+pub mod line_table;
+mod load_bias;
+mod location;
+pub mod memory;
+pub mod memory_map;
+pub mod module;
+pub mod observer;
+pub mod pretty_printer;
+pub mod shared_library;
+pub mod source;
+mod stepping;
 pub mod symbols;
+pub mod tls;
+pub mod types;
+mod unwind;
+pub mod value;
+pub mod variables;
 pub mod watchpoint;
 
 #[derive(thiserror::Error, Debug)]
@@ -21,6 +60,8 @@ pub enum Error {
     NoReadExecutablePath(PathBuf),
     #[error("invalid elf file")]
     InvalidElfFile(#[from] elf::ParseError),
+    #[error("invalid DWARF debug information")]
+    InvalidDwarf(#[from] gimli::Error),
     #[error("failed to attach debugger to child process")]
     ChildAttachment,
     #[error("failed to continue execution of child process")]
@@ -35,36 +76,122 @@ pub enum Error {
     WriteRegisters,
     #[error("A breakpoint at address 0x{0:8x} already exists")]
     BreakpointExists(u64),
+    #[error("no breakpoint with id {0} exists")]
+    UnknownBreakpoint(usize),
+    #[error("no breakpoint group with id {0} exists")]
+    UnknownBreakpointGroup(usize),
+    #[error("no function matches {0}")]
+    NoMatchingFunctions(String),
+    #[error("invalid regular expression")]
+    InvalidRegex(#[from] regex::Error),
     #[error("failed get executable path of pid {0}")]
     ReadExecutablePath(Pid),
     #[error("{0} is not a valid debug register index")]
     DebugRegisterIndex(usize),
-    #[error("{0} ist not a valid value for the length of a watchpoint")]
+    #[error("{0} is not a valid value for the length of a watchpoint")]
     WatchpointLengthValue(usize),
+    #[error("address 0x{0:x} is not aligned to the watchpoint length of {1} bytes")]
+    UnalignedWatchpoint(u64, u64),
+    #[error("no executable mapping of {0} found in the tracee")]
+    NoExecutableMapping(PathBuf),
+    #[error("{0} has no .text section")]
+    NoTextSection(PathBuf),
+    #[error("no symbol named {0} found")]
+    UnknownSymbol(String),
+    #[error("no line information for {0}")]
+    NoLineInformation(String),
+    #[error("failed to read source file {0}")]
+    ReadSourceFile(PathBuf),
+    #[error("no variable named {0} found")]
+    UnknownVariable(String),
+    #[error("no register named {0}")]
+    UnknownRegister(String),
+    #[error("no type named {0} found")]
+    UnknownType(String),
+    #[error("invalid expression: {0}")]
+    InvalidExpression(String),
+    #[error("{0}")]
+    EvaluateExpression(String),
+    #[error("no debug information for {0}")]
+    NoDebugInformation(PathBuf),
+    #[error("thread-local storage of the tracee is not available")]
+    ThreadLocalStorageUnavailable,
+    #[error("no frame at level {0}")]
+    UnknownFrame(usize),
+    #[error("the outermost frame has no caller")]
+    OutermostFrame,
+    #[error("invalid stack pointer 0x{0:x}")]
+    InvalidStackPointer(u64),
     #[error("cannot add more than 4 watchpoints")]
     MaxNumWatchpoints,
     #[error("failed to step one instruction")]
     SingleStep,
+    #[error("tracee is already running")]
+    TraceeRunning,
+    #[error("tracee is not running")]
+    TraceeNotRunning,
+    #[error("failed to set up the tracee event file descriptor")]
+    EventFd,
+    #[error("failed to parse memory map entry {0:?}")]
+    ParseMemoryMap(String),
     #[error("an io error occured")]
     IoError(#[from] std::io::Error),
 }
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// A debugger tracing a single tracee process.
+///
+/// Creating a debugger blocks `SIGCHLD` for the calling thread and routes it into a signalfd instead, which
+/// becomes readable whenever the tracee changes its state. The file descriptor is exposed through [`AsRawFd`] and
+/// [`AsFd`], so it can be registered with an event loop (e.g. tokio's `AsyncFd`) and combined with
+/// [`Debugger::poll_event`]. Threads spawned afterwards inherit the blocked signal mask.
+///
+/// [`AsRawFd`]: std::os::fd::AsRawFd
+/// [`AsFd`]: std::os::fd::AsFd
 #[derive(Debug)]
 pub struct Debugger {
     executable_path: PathBuf,
     tracee_pid: Pid,
     memory_maps: ProcMemoryMaps,
-    breakpoints: HashMap<u64, i64>,
+    // Inserted software breakpoints and the original byte replaced by the int3 instruction
+    breakpoints: HashMap<u64, u8>,
+    user_breakpoints: Vec<Breakpoint>,
+    next_breakpoint_id: BreakpointId,
+    next_breakpoint_group_id: BreakpointGroupId,
     watchpoints: HashMap<usize, (u64, Watchpoint)>,
-    executable_data: Vec<u8>,
+    executable: Module,
+    shared_libraries: Vec<Module>,
+    dynamic_linker: DynamicLinkerState,
+    event_fd: SignalFd,
+    running: bool,
+    pending_signal: Option<Signal>,
+    deferred_wait_status: Option<WaitStatus>,
+    observers: Observers,
+    pretty_printers: PrettyPrinters,
+    syscall_tracing: bool,
+    in_syscall: bool,
+    show_mangled_names: bool,
+    debug_file_directories: Vec<PathBuf>,
+    source_path_substitutions: Vec<(PathBuf, PathBuf)>,
+    // Index into the backtrace, reset to the innermost frame whenever the tracee executes
+    selected_frame: usize,
+    // The return address and stack pointer of the callee that finish and next are waiting to return from
+    awaited_return: Option<(u64, u64)>,
 }
 
+#[derive(Debug)]
 pub enum ContinueExecutionOutcome {
     ProcessExited(i32),
+    ProcessKilled(Signal),
     BreakpointHit(u64),
     WatchpointHit((u64, Watchpoint)),
+    SignalReceived(Signal),
+    Syscall(SyscallEvent),
+    /// Only reported if a [`DebugObserver`](observer::DebugObserver) asks to stop in
+    /// [`on_library_loaded`](observer::DebugObserver::on_library_loaded), otherwise the tracee is resumed silently.
+    LibrariesLoaded(Vec<PathBuf>),
+    Stepped(u64),
     Other,
 }
 
@@ -74,6 +201,7 @@ impl Debugger {
         let Ok(executable_data) = std::fs::read(&executable_path) else {
             return Err(Error::NoReadExecutablePath(executable_path));
         };
+        let mut executable = Module::from_elf_data(executable_path.clone(), executable_data, 0)?;
 
         debug!("Forking process and executing {executable_path:?} in a child process...");
 
@@ -81,10 +209,15 @@ impl Debugger {
         let child_pid = match unsafe { nix::unistd::fork() } {
             Ok(ForkResult::Parent { child, .. }) => child,
             Ok(ForkResult::Child) => {
-                let Err(error) = nix::sys::ptrace::traceme().and_then(|()| {
-                    // TODO: Use a better exec (v variant) for passing args
-                    libc_wrappers::execl(&executable_path)
-                });
+                let Err(error) = nix::sys::ptrace::traceme()
+                    .and_then(|()| {
+                        // The signal mask is inherited through exec, so undo the blocking done for our event fd
+                        sigchld_mask().thread_unblock()
+                    })
+                    .and_then(|()| {
+                        // TODO: Use a better exec (v variant) for passing args
+                        libc_wrappers::execl(&executable_path)
+                    });
 
                 // Log the error for now, in the future we might want to return a proper error to the debugger.
                 error!("Failed execution inside new child process {executable_path:?}: {error}");
@@ -112,17 +245,46 @@ impl Debugger {
             }
         }
 
+        set_trace_options(child_pid)?;
+
         let memory_maps = ProcMemoryMaps::from_pid(child_pid)?;
+        let auxv = AuxiliaryVector::from_pid(child_pid)?;
+        executable.load_bias =
+            load_bias::compute_load_bias(&executable_path, &executable.data, &auxv, &memory_maps)?;
+        let event_fd = create_event_fd()?;
 
-        let debugger = Self {
+        let mut debugger = Self {
             executable_path,
             tracee_pid: child_pid,
             memory_maps,
             breakpoints: HashMap::new(),
+            user_breakpoints: Vec::new(),
+            next_breakpoint_id: 0,
+            next_breakpoint_group_id: 0,
             watchpoints: HashMap::new(),
-            executable_data,
+            executable,
+            shared_libraries: Vec::new(),
+            dynamic_linker: DynamicLinkerState::default(),
+            event_fd,
+            running: false,
+            pending_signal: None,
+            deferred_wait_status: None,
+            observers: Observers::default(),
+            pretty_printers: PrettyPrinters::default(),
+            syscall_tracing: false,
+            in_syscall: false,
+            show_mangled_names: false,
+            debug_file_directories: vec![PathBuf::from(DEFAULT_DEBUG_FILE_DIRECTORY)],
+            source_path_substitutions: Vec::new(),
+            selected_frame: 0,
+            awaited_return: None,
         };
 
+        debugger
+            .executable
+            .load_debug_file(&debugger.debug_file_directories);
+        debugger.initialize_shared_library_tracking(&auxv)?;
+
         info!(
             "Successfully attached debugger to child process with pid {}",
             debugger.tracee_pid
@@ -155,62 +317,79 @@ impl Debugger {
             return Err(Error::NoReadExecutablePath(executable_path));
         };
 
-        let memory_maps = ProcMemoryMaps::from_pid(pid)?;
+        // Wait for the SIGSTOP, which is sent by the attach request
+        nix::sys::wait::waitpid(pid, None).map_err(|errno| {
+            error!("waitpid unexpectedly failed: {errno}");
+            Error::ChildAttachment
+        })?;
+
+        set_trace_options(pid)?;
 
-        let debugger = Self {
+        let memory_maps = ProcMemoryMaps::from_pid(pid)?;
+        let auxv = AuxiliaryVector::from_pid(pid)?;
+        let load_bias =
+            load_bias::compute_load_bias(&executable_path, &executable_data, &auxv, &memory_maps)?;
+        let executable =
+            Module::from_elf_data(executable_path.clone(), executable_data, load_bias)?;
+        let event_fd = create_event_fd()?;
+
+        let mut debugger = Self {
             executable_path,
             tracee_pid: pid,
             memory_maps,
             breakpoints: HashMap::new(),
+            user_breakpoints: Vec::new(),
+            next_breakpoint_id: 0,
+            next_breakpoint_group_id: 0,
             watchpoints: HashMap::new(),
-            executable_data,
+            executable,
+            shared_libraries: Vec::new(),
+            dynamic_linker: DynamicLinkerState::default(),
+            event_fd,
+            running: false,
+            pending_signal: None,
+            deferred_wait_status: None,
+            observers: Observers::default(),
+            pretty_printers: PrettyPrinters::default(),
+            syscall_tracing: false,
+            in_syscall: false,
+            show_mangled_names: false,
+            debug_file_directories: vec![PathBuf::from(DEFAULT_DEBUG_FILE_DIRECTORY)],
+            source_path_substitutions: Vec::new(),
+            selected_frame: 0,
+            awaited_return: None,
         };
 
+        debugger
+            .executable
+            .load_debug_file(&debugger.debug_file_directories);
+        debugger.initialize_shared_library_tracking(&auxv)?;
+
         info!("Successfully attached debugger to running process with pid {pid}");
 
         Ok(debugger)
     }
 
-    pub fn get_text_offset_address(&mut self, offset: u64) -> u64 {
-        let text_section = self.memory_maps.get_text_section();
-
-        text_section.range_from - text_section.offset + offset
+    pub fn tracee_pid(&self) -> Pid {
+        self.tracee_pid
     }
 
-    pub fn set_breakpoint_at(&mut self, breakpoint_address: u64) -> Result<()> {
-        let breakpoint_address_ptr = breakpoint_address as *mut core::ffi::c_void;
-
-        let replaced_word =
-            ptrace::read(self.tracee_pid, breakpoint_address_ptr).map_err(|errno| {
-                error!("Could not read from address 0x{breakpoint_address:8x?}: {errno}");
-
-                Error::ReadMemory(breakpoint_address)
-            })?;
-
-        let breakpoint_word = (replaced_word & (!0xFFi64)) | 0xCCi64;
-        ptrace::write(self.tracee_pid, breakpoint_address_ptr, breakpoint_word).map_err(
-            |errno| {
-                error!("Could not write to address 0x{breakpoint_address:8x?}: {errno}");
-
-                Error::WriteMemory(breakpoint_address)
-            },
-        )?;
-
-        self.breakpoints.insert(breakpoint_address, replaced_word);
-
-        info!("Set breakpoint at 0x{breakpoint_address:012x}");
-
-        Ok(())
+    /// The offset between link-time addresses in the executable and runtime addresses in the tracee.
+    pub fn load_bias(&self) -> u64 {
+        self.executable.load_bias
     }
 
-    pub fn set_breakpoint_at_text_offset(&mut self, text_offset: u64) -> Result<()> {
-        let breakpoint_address = self.get_text_offset_address(text_offset);
-
-        if self.breakpoints.contains_key(&breakpoint_address) {
-            return Err(Error::BreakpointExists(breakpoint_address));
-        }
-
-        self.set_breakpoint_at(breakpoint_address)
+    /// Resolves an offset into the `.text` section of the executable to its runtime address.
+    pub fn get_text_offset_address(&self, offset: u64) -> Result<u64> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(&self.executable.data)?;
+        let text_section = elf_bytes
+            .section_header_by_name(".text")?
+            .ok_or_else(|| Error::NoTextSection(self.executable_path.clone()))?;
+
+        Ok(text_section
+            .sh_addr
+            .wrapping_add(offset)
+            .wrapping_add(self.executable.load_bias))
     }
 
     pub fn set_watchpoint_at(&mut self, address: u64, watchpoint: Watchpoint) -> Result<()> {
@@ -221,21 +400,17 @@ impl Debugger {
             return Err(Error::MaxNumWatchpoints);
         }
 
-        let mut debug_control = self.get_debug_control()?;
-
-        self.set_debug_register(debug_register, address as i64);
-        debug_control |= 1 << (debug_register * 2);
-
-        match watchpoint {
-            Watchpoint::Execution => {}
-            Watchpoint::Data { condition, length } => {
-                debug_control &= !(0b1111 << (16 + (4 * debug_register)));
-                debug_control |= (condition as i64) << (16 + (4 * debug_register));
-                debug_control |= (length as i64) << (16 + (4 * debug_register));
-            }
+        // The debug registers ignore the low bits of the address, which would watch the wrong bytes
+        if let Watchpoint::Data { length, .. } = watchpoint
+            && !address.is_multiple_of(length.bytes())
+        {
+            return Err(Error::UnalignedWatchpoint(address, length.bytes()));
         }
 
-        self.set_debug_control(debug_control)?;
+        let debug_control = self.get_debug_control()?;
+
+        self.set_debug_register(debug_register, address as i64)?;
+        self.set_debug_control(watchpoint.debug_control(debug_control, debug_register))?;
 
         info!("Set watchpoint {watchpoint:?} at 0x{address:012x}");
 
@@ -245,33 +420,71 @@ impl Debugger {
         Ok(())
     }
 
+    /// Watches a variable. Without an explicit length, the size of the variable is watched.
+    pub fn set_watchpoint_at_variable(
+        &mut self,
+        variable_name: &str,
+        condition: WatchpointDataCondition,
+        length: Option<WatchpointLength>,
+    ) -> Result<()> {
+        let (address, symbol) = self.resolve_variable(variable_name)?;
+
+        let length = match length {
+            Some(length) => length,
+            None => WatchpointLength::try_from(symbol.size as usize)?,
+        };
+
+        self.set_watchpoint_at(address, Watchpoint::Data { condition, length })
+    }
+
     pub fn set_watchpoint_at_text_offset(
         &mut self,
         text_offset: u64,
         watchpoint: Watchpoint,
     ) -> Result<()> {
-        let breakpoint_address = self.get_text_offset_address(text_offset);
+        let breakpoint_address = self.get_text_offset_address(text_offset)?;
 
         self.set_watchpoint_at(breakpoint_address, watchpoint)
     }
 
-    pub fn get_tracee_pc(&self) -> Result<u64> {
-        let regs = ptrace::getregs(self.tracee_pid).map_err(|errno| {
+    pub fn get_tracee_registers(&self) -> Result<user_regs_struct> {
+        ptrace::getregs(self.tracee_pid).map_err(|errno| {
             error!("Could not read registers of tracee: {errno}");
 
             Error::ReadRegisters
-        })?;
+        })
+    }
 
-        Ok(regs.rip)
+    pub fn get_tracee_pc(&self) -> Result<u64> {
+        Ok(self.get_tracee_registers()?.rip)
+    }
+
+    pub fn get_tracee_sp(&self) -> Result<u64> {
+        Ok(self.get_tracee_registers()?.rsp)
     }
 
     pub fn set_tracee_pc(&self, new_pc: u64) -> Result<()> {
-        let mut regs = ptrace::getregs(self.tracee_pid).map_err(|errno| {
-            error!("Could not read registers of tracee: {errno}");
+        let mut regs = self.get_tracee_registers()?;
+        regs.rip = new_pc;
 
-            Error::ReadRegisters
+        ptrace::setregs(self.tracee_pid, regs).map_err(|errno| {
+            error!("Could not write registers of tracee: {errno}");
+
+            Error::WriteRegisters
         })?;
-        regs.rip = new_pc;
+
+        Ok(())
+    }
+
+    /// Writes a register of the tracee by its name, see [`FrameRegisters::get_by_name`].
+    ///
+    /// [`FrameRegisters::get_by_name`]: backtrace::FrameRegisters::get_by_name
+    pub fn set_tracee_register(&self, name: &str, value: u64) -> Result<()> {
+        let mut regs = self.get_tracee_registers()?;
+        let field = backtrace::register_number(name)
+            .and_then(|register| backtrace::user_regs_field(&mut regs, register))
+            .ok_or_else(|| Error::UnknownRegister(name.to_owned()))?;
+        *field = value;
 
         ptrace::setregs(self.tracee_pid, regs).map_err(|errno| {
             error!("Could not write registers of tracee: {errno}");
@@ -290,86 +503,168 @@ impl Debugger {
         })
     }
 
-    pub fn step_instructions(&self, steps: u32) -> Result<u64> {
-        for _ in 0..steps {
-            ptrace::step(self.tracee_pid, None).map_err(|errno| {
-                error!("failed to single step execution {errno}");
+    // Steps a single instruction. If the tracee is currently stopped on a software breakpoint, the original
+    // instruction is swapped back in for the duration of the step.
+    fn single_step(&self) -> Result<WaitStatus> {
+        let pc = self.get_tracee_pc()?;
+        let original_byte = self.breakpoints.get(&pc).copied();
+
+        if let Some(original_byte) = original_byte {
+            self.write_memory_raw(pc, &[original_byte])?;
+        }
+
+        ptrace::step(self.tracee_pid, None).map_err(|errno| {
+            error!("failed to single step execution {errno}");
+
+            Error::SingleStep
+        })?;
+
+        let wait_status = self.wait_for_tracee()?;
+
+        if original_byte.is_some()
+            && !matches!(
+                wait_status,
+                WaitStatus::Exited(..) | WaitStatus::Signaled(..)
+            )
+        {
+            self.write_memory_raw(pc, &[0xCC])?;
+        }
+
+        Ok(wait_status)
+    }
 
-                Error::SingleStep
-            })?;
+    // Steps a single instruction like single_step. Returns `None` if the step completed without anything else
+    // happening, otherwise the wait status of the event to report, e.g. of a triggered watchpoint or a signal.
+    fn step_instruction(&self) -> Result<Option<WaitStatus>> {
+        match self.single_step()? {
+            WaitStatus::Stopped(_pid, Signal::SIGTRAP) if !self.is_watchpoint_hit_pending() => {
+                Ok(None)
+            }
+            wait_status => Ok(Some(wait_status)),
+        }
+    }
 
-            self.wait_for_tracee()?;
+    pub fn step_instructions(&mut self, steps: u32) -> Result<u64> {
+        if self.running {
+            return Err(Error::TraceeRunning);
+        }
+        self.selected_frame = 0;
+
+        for _ in 0..steps {
+            self.single_step()?;
         }
 
         self.get_tracee_pc()
     }
 
     pub fn continue_execution(&mut self) -> Result<ContinueExecutionOutcome> {
-        nix::sys::ptrace::cont(self.tracee_pid, None).map_err(|errno| {
-            error!("failed ptrace cont call: {errno}");
+        self.resume()?;
 
-            Error::ContinueExecution
-        })?;
+        self.wait_event(None)
+            .map(|outcome| outcome.expect("blocking wait to always yield an event"))
+    }
 
-        let wait_status = self.wait_for_tracee()?;
+    // Checks the debug status for a triggered watchpoint, without resetting it like take_watchpoint_hit
+    fn is_watchpoint_hit_pending(&self) -> bool {
+        self.get_debug_status()
+            .is_ok_and(|status| status & 0b1111 != 0)
+    }
+
+    // Checks the debug status register for a triggered watchpoint after a SIGTRAP
+    fn take_watchpoint_hit(&self) -> Result<Option<ContinueExecutionOutcome>> {
+        let Ok(status) = self.get_debug_status() else {
+            return Ok(None);
+        };
+
+        for i in 0..4 {
+            if status & (1 << i) > 0 {
+                // The status bits are sticky, so they have to be reset to not report this hit again
+                self.set_debug_register(DebugRegisterOffsets::DebugStatus as usize, 0)?;
+
+                let (address, watchpoint) = self.watchpoints.get(&i).expect("breakpoint to exist");
+                info!("Hit watchpoint {watchpoint:?} at address 0x{address:012x?}");
+                return Ok(Some(ContinueExecutionOutcome::WatchpointHit((
+                    *address,
+                    *watchpoint,
+                ))));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn handle_wait_status(&mut self, wait_status: WaitStatus) -> Result<ContinueExecutionOutcome> {
+        self.running = false;
 
         match wait_status {
             WaitStatus::Exited(_pid, exit_code) => {
                 info!("Process exited with code {exit_code}");
                 Ok(ContinueExecutionOutcome::ProcessExited(exit_code))
             }
+            WaitStatus::Signaled(_pid, signal, _core_dumped) => {
+                info!("Process was killed by signal {signal}");
+                Ok(ContinueExecutionOutcome::ProcessKilled(signal))
+            }
             WaitStatus::Stopped(_pid, Signal::SIGTRAP) => {
-                if let Ok(status) = self.get_debug_status() {
-                    for i in 0..4 {
-                        if status & (1 << i) > 0 {
-                            let (address, watchpoint) =
-                                self.watchpoints.get(&i).expect("breakpoint to exist");
-                            info!("Hit watchpoint {watchpoint:?} at address 0x{address:012x?}");
-                            return Ok(ContinueExecutionOutcome::WatchpointHit((
-                                *address,
-                                *watchpoint,
-                            )));
-                        }
-                    }
+                if let Some(outcome) = self.take_watchpoint_hit()? {
+                    return Ok(outcome);
                 }
 
                 let stopped_pc = self.get_tracee_pc()?;
                 let breakpoint_pc = stopped_pc - 1;
 
                 // There has to be a better mechanism to detect a software breakpoint
-                // Replaces the software breakpoint with the original word at the breakpoint address,
-                // steps a single instruction and replaces the int3 instruction back into the breakpoint address
-                if let Some(replaced_word) = self.breakpoints.get(&breakpoint_pc) {
+                // The tracee is rewound to the breakpoint address, the original instruction is executed once the
+                // tracee is resumed or stepped.
+                if self.breakpoints.contains_key(&breakpoint_pc) {
                     info!("Hit Software Breakpoint at {breakpoint_pc:08x}");
 
                     self.set_tracee_pc(breakpoint_pc)?;
-                    ptrace::write(
-                        self.tracee_pid,
-                        breakpoint_pc as *mut core::ffi::c_void,
-                        *replaced_word,
-                    )
-                    .map_err(|errno| {
-                        error!("failed to write to address {breakpoint_pc:08x}: {errno}");
-
-                        Error::WriteMemory(breakpoint_pc)
-                    })?;
-
-                    ptrace::step(self.tracee_pid, None).map_err(|errno| {
-                        error!("failed ptrace step call: {errno}");
-
-                        Error::ContinueExecution
-                    })?;
-
-                    self.wait_for_tracee()?;
-
-                    self.set_breakpoint_at(breakpoint_pc)?;
 
                     Ok(ContinueExecutionOutcome::BreakpointHit(breakpoint_pc))
                 } else {
                     Ok(ContinueExecutionOutcome::Other)
                 }
             }
+            WaitStatus::PtraceSyscall(_pid) => {
+                let syscall = self.read_syscall_event()?;
+                debug!("Tracee stopped at syscall {syscall:?}");
+                Ok(ContinueExecutionOutcome::Syscall(syscall))
+            }
+            WaitStatus::Stopped(_pid, signal) => {
+                info!("Tracee received signal {signal}");
+                // Deliver the signal once the tracee is resumed, like it would have been without a tracer
+                self.pending_signal = Some(signal);
+                Ok(ContinueExecutionOutcome::SignalReceived(signal))
+            }
             _ => Ok(ContinueExecutionOutcome::Other),
         }
     }
 }
+
+fn sigchld_mask() -> SigSet {
+    let mut mask = SigSet::empty();
+    mask.add(Signal::SIGCHLD);
+    mask
+}
+
+fn set_trace_options(pid: Pid) -> Result<()> {
+    // Distinguishes syscall stops from regular SIGTRAP stops
+    ptrace::setoptions(pid, ptrace::Options::PTRACE_O_TRACESYSGOOD).map_err(|errno| {
+        error!("failed to set ptrace options: {errno}");
+
+        Error::ChildAttachment
+    })
+}
+
+fn create_event_fd() -> Result<SignalFd> {
+    let mask = sigchld_mask();
+
+    mask.thread_block()
+        .and_then(|()| SignalFd::with_flags(&mask, SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC))
+        .map_err(|errno| {
+            error!("failed to create signalfd for SIGCHLD: {errno}");
+
+            Error::EventFd
+        })
+}
@@ -0,0 +1,487 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use gimli::{ColumnType, Reader};
+
+use crate::{Debugger, Result, dwarf::load_dwarf, module::Module};
+
+/// A row of the DWARF line number program, mapping a link-time address to a source location.
+#[derive(Debug, Clone, Copy)]
+pub struct LineRow {
+    pub address: u64,
+    /// Index into [`LineTable::files`].
+    pub file: usize,
+    pub line: u64,
+    /// The column, 0 if the row applies to the whole line.
+    pub column: u64,
+    /// Whether the address is a recommended breakpoint location for the line.
+    pub is_stmt: bool,
+    /// Marks the first address after a sequence of instructions, it does not belong to any line.
+    pub end_sequence: bool,
+}
+
+/// A source location resolved from the line table.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceLocation<'a> {
+    pub path: &'a Path,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// A call of a function whose code was inlined into the caller, from a `DW_TAG_inlined_subroutine` entry.
+#[derive(Debug, Clone)]
+pub struct InlinedSubroutine {
+    /// The demangled name of the inlined function, e.g. `p::add` for Rust.
+    pub name: Option<String>,
+    /// The link-time address where the inlined code is entered.
+    pub entry_pc: u64,
+    /// The link-time address ranges of the inlined code, which can be interleaved with the code of the caller.
+    pub ranges: Vec<Range<u64>>,
+    /// The file containing the call, if it is known.
+    pub call_file: Option<PathBuf>,
+    pub call_line: u64,
+    pub call_column: u64,
+    /// The number of inlined subroutines this call is nested in.
+    pub depth: usize,
+}
+
+impl InlinedSubroutine {
+    pub fn contains(&self, address: u64) -> bool {
+        self.ranges.iter().any(|range| range.contains(&address))
+    }
+
+    pub fn call_location(&self) -> Option<SourceLocation<'_>> {
+        Some(SourceLocation {
+            path: self.call_file.as_deref()?,
+            line: self.call_line,
+            column: self.call_column,
+        })
+    }
+}
+
+/// The `.debug_line` information of a module, parsed once when the module is loaded.
+#[derive(Debug, Default)]
+pub struct LineTable {
+    files: Vec<PathBuf>,
+    // Index of every path in `files`, for deduplicating them while parsing
+    file_indices: HashMap<PathBuf, usize>,
+    // Rows of all sequences, sorted by their address
+    rows: Vec<LineRow>,
+}
+
+impl LineTable {
+    pub fn from_elf_data(data: &[u8]) -> Result<Self> {
+        let dwarf = load_dwarf(data)?;
+
+        let mut table = Self::default();
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+
+            // File indices are local to a line program, map them to the deduplicated files of the table
+            let mut file_indices = Vec::new();
+            let mut rows = program.rows();
+            while let Some((header, row)) = rows.next_row()? {
+                let file_index = row.file_index() as usize;
+                if file_indices.len() <= file_index {
+                    file_indices.resize(file_index + 1, None);
+                }
+
+                let file = match file_indices[file_index] {
+                    Some(file) => file,
+                    None => {
+                        let path = file_path(&dwarf, &unit, header, row.file_index())?;
+                        let file = table.intern_file(path);
+                        file_indices[file_index] = Some(file);
+                        file
+                    }
+                };
+
+                table.rows.push(LineRow {
+                    address: row.address(),
+                    file,
+                    line: row.line().map_or(0, |line| line.get()),
+                    column: match row.column() {
+                        ColumnType::LeftEdge => 0,
+                        ColumnType::Column(column) => column.get(),
+                    },
+                    is_stmt: row.is_stmt(),
+                    end_sequence: row.end_sequence(),
+                });
+            }
+        }
+
+        // The end of a sequence sorts before a sequence starting at the same address
+        table
+            .rows
+            .sort_by_key(|row| (row.address, !row.end_sequence));
+
+        Ok(table)
+    }
+
+    fn intern_file(&mut self, path: PathBuf) -> usize {
+        *self.file_indices.entry(path).or_insert_with_key(|path| {
+            self.files.push(path.clone());
+            self.files.len() - 1
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn rows(&self) -> &[LineRow] {
+        &self.rows
+    }
+
+    /// Finds the row covering a link-time address.
+    pub fn find_by_address(&self, address: u64) -> Option<&LineRow> {
+        let end = self.rows.partition_point(|row| row.address <= address);
+
+        self.rows[..end]
+            .last()
+            .filter(|row| !row.end_sequence && row.line != 0)
+    }
+
+    /// Finds the row covering a link-time address together with the address range it covers.
+    pub fn find_row_range(&self, address: u64) -> Option<(&LineRow, Range<u64>)> {
+        let end = self.rows.partition_point(|row| row.address <= address);
+        let row = self.find_by_address(address)?;
+        let range_end = self.rows.get(end).map_or(u64::MAX, |next| next.address);
+
+        Some((row, row.address..range_end))
+    }
+
+    /// Checks whether a link-time address is the start of a statement, which is where stepping by line stops.
+    pub fn is_statement_start(&self, address: u64) -> bool {
+        let start = self.rows.partition_point(|row| row.address < address);
+
+        self.rows[start..]
+            .iter()
+            .take_while(|row| row.address == address)
+            .any(|row| row.is_stmt && !row.end_sequence && row.line != 0)
+    }
+
+    pub fn source_location(&self, row: &LineRow) -> SourceLocation<'_> {
+        SourceLocation {
+            path: &self.files[row.file],
+            line: row.line,
+            column: row.column,
+        }
+    }
+
+    /// Finds the link-time addresses of a line in all files whose path ends with `file`. If the line has no code, the
+    /// next line with code is used instead.
+    pub fn find_line_addresses(&self, file: &Path, line: u64) -> Vec<u64> {
+        let matching_files: Vec<usize> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| path.ends_with(file))
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut addresses = Vec::new();
+        for file in matching_files {
+            let candidates = || {
+                self.rows.iter().filter(move |row| {
+                    row.file == file && row.is_stmt && !row.end_sequence && row.line >= line
+                })
+            };
+
+            let Some(best_line) = candidates().map(|row| row.line).min() else {
+                continue;
+            };
+
+            addresses.extend(
+                candidates()
+                    .filter(|row| row.line == best_line)
+                    .map(|row| row.address),
+            );
+        }
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        addresses
+    }
+}
+
+// Also used for the call files of inlined subroutines, whose debug information is read with a different reader
+pub(crate) fn file_path<R: Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    header: &gimli::LineProgramHeader<R>,
+    file_index: u64,
+) -> Result<PathBuf> {
+    let Some(file) = header.file(file_index) else {
+        return Ok(PathBuf::new());
+    };
+
+    let mut path = PathBuf::new();
+    if let Some(comp_dir) = &unit.comp_dir {
+        path.push(comp_dir.to_string_lossy()?.as_ref());
+    }
+    if let Some(directory) = file.directory(header) {
+        let directory = dwarf.attr_string(unit, directory)?;
+        path.push(directory.to_string_lossy()?.as_ref());
+    }
+    // Pushing an absolute path replaces the compilation or include directory
+    let name = dwarf.attr_string(unit, file.path_name())?;
+    path.push(name.to_string_lossy()?.as_ref());
+
+    Ok(path)
+}
+
+impl Debugger {
+    /// Finds the source location of a runtime address of the tracee.
+    pub fn find_source_location(&self, address: u64) -> Option<SourceLocation<'_>> {
+        self.modules()
+            .filter(|module| module.contains(address))
+            .find_map(|module| {
+                let row = module
+                    .lines
+                    .find_by_address(address.wrapping_sub(module.load_bias))?;
+
+                Some(module.lines.source_location(row))
+            })
+    }
+
+    // The inlined subroutines whose code contains a runtime address together with their module, the innermost one first
+    pub(crate) fn inlined_subroutines_at(
+        &self,
+        address: u64,
+    ) -> Vec<(&Module, &InlinedSubroutine)> {
+        self.modules()
+            .enumerate()
+            .filter(|(_, module)| module.contains(address))
+            .filter_map(|(index, module)| Some((module, module.inlined_subroutines(index)?)))
+            .flat_map(|(module, inlined)| {
+                inlined
+                    .at(address.wrapping_sub(module.load_bias))
+                    .iter()
+                    .map(move |index| (module, &inlined.subroutines[*index]))
+            })
+            .collect()
+    }
+
+    /// Resolves a function to the runtime addresses of its code: the address of its symbol, followed by the entries
+    /// of the calls it was inlined into.
+    pub fn resolve_function_addresses(&self, function_name: &str) -> Vec<u64> {
+        let mut addresses: Vec<u64> = self
+            .resolve_symbol_runtime_address(function_name)
+            .into_iter()
+            .collect();
+
+        for (index, module) in self.modules().enumerate() {
+            let Some(inlined) = module.inlined_subroutines(index) else {
+                continue;
+            };
+            let mut inlined: Vec<u64> = inlined
+                .subroutines
+                .iter()
+                .filter(|subroutine| subroutine.name.as_deref() == Some(function_name))
+                .map(|subroutine| subroutine.entry_pc.wrapping_add(module.load_bias))
+                .collect();
+            inlined.sort_unstable();
+            inlined.dedup();
+            inlined.retain(|address| !addresses.contains(address));
+
+            addresses.extend(inlined);
+        }
+
+        addresses
+    }
+
+    /// Resolves a source line to the runtime addresses of its code. If the line is part of multiple functions, e.g.
+    /// due to inlining, there is an address for every function.
+    pub fn resolve_line_addresses(&self, file: &Path, line: u64) -> Vec<u64> {
+        let mut addresses = Vec::new();
+
+        for module in self.modules() {
+            let mut module_addresses: Vec<(Option<u64>, u64)> = module
+                .lines
+                .find_line_addresses(file, line)
+                .into_iter()
+                .map(|address| {
+                    let function = module
+                        .symbols
+                        .find_by_address(address)
+                        .map(|symbol| symbol.address);
+                    (function, address)
+                })
+                .collect();
+
+            // A line can have multiple statements within a function, e.g. the header of a for loop. Only the first one is
+            // used.
+            module_addresses.sort_unstable();
+            module_addresses.dedup_by(|(function, _), (previous_function, _)| {
+                function.is_some() && function == previous_function
+            });
+
+            addresses.extend(
+                module_addresses
+                    .into_iter()
+                    .map(|(_, address)| address.wrapping_add(module.load_bias)),
+            );
+        }
+
+        addresses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(address: u64, line: u64, is_stmt: bool) -> LineRow {
+        LineRow {
+            address,
+            file: 0,
+            line,
+            column: 0,
+            is_stmt,
+            end_sequence: false,
+        }
+    }
+
+    fn end_sequence(address: u64) -> LineRow {
+        LineRow {
+            end_sequence: true,
+            ..row(address, 0, false)
+        }
+    }
+
+    fn table(rows: Vec<LineRow>) -> LineTable {
+        LineTable {
+            files: vec![PathBuf::from("/src/main.c")],
+            rows,
+            ..LineTable::default()
+        }
+    }
+
+    #[test]
+    fn intern_file_deduplicates_paths() {
+        let mut table = LineTable::default();
+
+        assert_eq!(table.intern_file(PathBuf::from("/src/a.c")), 0);
+        assert_eq!(table.intern_file(PathBuf::from("/src/b.h")), 1);
+        assert_eq!(table.intern_file(PathBuf::from("/src/a.c")), 0);
+        assert_eq!(
+            table.files(),
+            [PathBuf::from("/src/a.c"), PathBuf::from("/src/b.h")]
+        );
+    }
+
+    #[test]
+    fn find_line_addresses_uses_next_line_with_code() {
+        let header = |address, line| LineRow {
+            file: 1,
+            ..row(address, line, true)
+        };
+        let mut table = table(vec![
+            row(0x1000, 10, true),
+            row(0x1004, 12, true),
+            row(0x1008, 12, false),
+            header(0x1010, 3),
+            row(0x1020, 12, true),
+            end_sequence(0x1030),
+            // An inlined copy of the header function
+            header(0x2000, 3),
+            header(0x2008, 4),
+        ]);
+        table.files.push(PathBuf::from("/src/include/util.h"));
+
+        assert_eq!(
+            table.find_line_addresses(Path::new("main.c"), 12),
+            [0x1004, 0x1020]
+        );
+        // Line 11 has no code, so line 12 is used
+        assert_eq!(
+            table.find_line_addresses(Path::new("/src/main.c"), 11),
+            [0x1004, 0x1020]
+        );
+        assert_eq!(
+            table.find_line_addresses(Path::new("include/util.h"), 3),
+            [0x1010, 0x2000]
+        );
+        assert!(
+            table
+                .find_line_addresses(Path::new("main.c"), 13)
+                .is_empty()
+        );
+        // Only whole path components match
+        assert!(table.find_line_addresses(Path::new("in.c"), 10).is_empty());
+    }
+
+    #[test]
+    fn find_row_range_ends_at_next_row() {
+        let table = table(vec![
+            row(0x1000, 10, true),
+            row(0x1008, 11, true),
+            row(0x1010, 11, false),
+            end_sequence(0x1020),
+            row(0x2000, 20, true),
+        ]);
+
+        let (row, range) = table.find_row_range(0x1004).unwrap();
+        assert_eq!(row.line, 10);
+        assert_eq!(range, 0x1000..0x1008);
+
+        let (row, range) = table.find_row_range(0x1010).unwrap();
+        assert_eq!((row.line, row.is_stmt), (11, false));
+        assert_eq!(range, 0x1010..0x1020);
+
+        // The last row covers everything up to the end of the address space
+        let (row, range) = table.find_row_range(0x2004).unwrap();
+        assert_eq!(row.line, 20);
+        assert_eq!(range, 0x2000..u64::MAX);
+    }
+
+    #[test]
+    fn find_row_range_outside_sequences() {
+        let table = table(vec![
+            row(0x1000, 10, true),
+            end_sequence(0x1020),
+            row(0x2000, 0, true),
+            row(0x2008, 21, true),
+        ]);
+
+        assert!(table.find_row_range(0xfff).is_none());
+        assert!(table.find_row_range(0x1020).is_none());
+        assert!(table.find_row_range(0x1fff).is_none());
+        // Line 0 marks compiler generated code without a source line
+        assert!(table.find_row_range(0x2004).is_none());
+        assert!(table.find_row_range(0x2008).is_some());
+    }
+
+    #[test]
+    fn is_statement_start_checks_all_rows_at_address() {
+        let table = table(vec![
+            row(0x1000, 10, true),
+            row(0x1004, 10, false),
+            row(0x1008, 0, true),
+            end_sequence(0x1010),
+            // A sequence may start where the previous one ended
+            row(0x1010, 30, false),
+            row(0x1010, 31, true),
+        ]);
+
+        assert!(table.is_statement_start(0x1000));
+        assert!(!table.is_statement_start(0x1002));
+        assert!(!table.is_statement_start(0x1004));
+        assert!(!table.is_statement_start(0x1008));
+        assert!(table.is_statement_start(0x1010));
+        assert!(!table.is_statement_start(0x2000));
+    }
+}
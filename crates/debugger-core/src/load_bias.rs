@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use elf::{
+    ElfBytes,
+    abi::{ET_DYN, ET_EXEC, PT_LOAD, PT_PHDR},
+    endian::AnyEndian,
+};
+use log::*;
+
+use crate::{
+    Error, Result,
+    auxv::{AT_ENTRY, AT_PHDR, AuxiliaryVector},
+    memory_map::ProcMemoryMaps,
+};
+
+/// Computes the difference between the link-time virtual addresses of the main executable and the addresses it was
+/// actually loaded at.
+///
+/// Non-PIE executables (`ET_EXEC`) are always loaded at their link-time addresses. For PIE executables (`ET_DYN`) the
+/// bias is taken from the auxiliary vector, or from the memory maps of the tracee as a last resort.
+pub(crate) fn compute_load_bias(
+    executable_path: &Path,
+    executable_data: &[u8],
+    auxv: &AuxiliaryVector,
+    memory_maps: &ProcMemoryMaps,
+) -> Result<u64> {
+    let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(executable_data)?;
+
+    match elf_bytes.ehdr.e_type {
+        ET_EXEC => return Ok(0),
+        ET_DYN => {}
+        other => {
+            warn!("unexpected elf type {other:#x}, assuming a position independent executable")
+        }
+    }
+
+    let segments: Vec<_> = elf_bytes
+        .segments()
+        .map(|segments| segments.iter().collect())
+        .unwrap_or_default();
+
+    // The program headers are mapped into memory, so AT_PHDR is their runtime address
+    if let Some(phdr_address) = auxv.get(AT_PHDR) {
+        let phdr_vaddr = segments
+            .iter()
+            .find(|segment| segment.p_type == PT_PHDR)
+            .map(|segment| segment.p_vaddr)
+            .or_else(|| {
+                segments
+                    .iter()
+                    .find(|segment| segment.p_type == PT_LOAD && segment.p_offset == 0)
+                    .map(|segment| segment.p_vaddr + elf_bytes.ehdr.e_phoff)
+            });
+
+        if let Some(phdr_vaddr) = phdr_vaddr {
+            return Ok(phdr_address.wrapping_sub(phdr_vaddr));
+        }
+    }
+
+    if let Some(entry_address) = auxv.get(AT_ENTRY) {
+        return Ok(entry_address.wrapping_sub(elf_bytes.ehdr.e_entry));
+    }
+
+    debug!(
+        "auxiliary vector is incomplete, falling back to memory maps for computing the load bias"
+    );
+
+    let first_load_vaddr = segments
+        .iter()
+        .filter(|segment| segment.p_type == PT_LOAD)
+        .map(|segment| segment.p_vaddr & !(segment.p_align.max(1) - 1))
+        .min()
+        .unwrap_or(0);
+
+    let canonical_path = executable_path.canonicalize()?;
+    let base_address = memory_maps
+        .find_by_pathname(&canonical_path)
+        .find(|map| map.offset == 0)
+        .map(|map| map.range_from)
+        .or_else(|| memory_maps.get_text_section().map(|map| map.range_from))
+        .ok_or_else(|| Error::NoExecutableMapping(executable_path.to_owned()))?;
+
+    Ok(base_address.wrapping_sub(first_load_vaddr))
+}
@@ -0,0 +1,249 @@
+use gimli::{
+    AttributeValue, EvaluationResult, Expression, Location, Piece, Value as DwarfValue, ValueType,
+};
+use log::*;
+
+use crate::{
+    Debugger, Result,
+    backtrace::FrameRegisters,
+    debug_info::{DebugInfo, DwarfEntry, DwarfUnit},
+    dwarf::SharedDwarfReader,
+    module::Module,
+    value::ValueLocation,
+};
+
+// Everything the location expressions of a unit may refer to
+pub(crate) struct LocationContext<'s, 'a> {
+    pub(crate) debugger: &'a Debugger,
+    pub(crate) module: &'a Module,
+    pub(crate) debug_info: &'s DebugInfo<'a>,
+    pub(crate) unit: &'s DwarfUnit,
+    /// The registers of the frame, global variables are read without a frame.
+    pub(crate) registers: Option<FrameRegisters>,
+    pub(crate) cfa: Option<u64>,
+    /// The value of `DW_AT_frame_base` of the function of the frame.
+    pub(crate) frame_base: Option<u64>,
+    /// The link-time address selecting the entry of location lists.
+    pub(crate) address: u64,
+}
+
+impl<'a> LocationContext<'_, 'a> {
+    /// Finds the value of a variable or parameter entry. Constants that were folded by the compiler have no location,
+    /// but a `DW_AT_const_value` instead.
+    pub(crate) fn variable_location(
+        &self,
+        entry: &DwarfEntry,
+        size: Option<u64>,
+    ) -> Result<ValueLocation> {
+        if let Some(location) = entry.attr_value(gimli::DW_AT_location) {
+            return self.location(location);
+        }
+
+        let Some((_, value)) = self
+            .debug_info
+            .attr(self.unit, entry, gimli::DW_AT_const_value)?
+        else {
+            return Ok(ValueLocation::OptimizedOut);
+        };
+
+        let mut data = match value {
+            AttributeValue::Block(data) => data.to_vec(),
+            AttributeValue::Sdata(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::Udata(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::Data1(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::Data2(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::Data4(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::Data8(value) => value.to_le_bytes().to_vec(),
+            AttributeValue::String(data) => data.to_vec(),
+            _ => return Ok(ValueLocation::OptimizedOut),
+        };
+        if let Some(size) = size {
+            data.resize(size as usize, 0);
+        }
+
+        Ok(ValueLocation::Data(data))
+    }
+
+    // Evaluates a location expression or looks up the expression for the current address in a location list
+    pub(crate) fn location(
+        &self,
+        location: AttributeValue<SharedDwarfReader>,
+    ) -> Result<ValueLocation> {
+        match location {
+            AttributeValue::Exprloc(expression) => self.evaluate(expression),
+            AttributeValue::Block(data) => self.evaluate(Expression(data)),
+            location => {
+                let Some(mut locations) =
+                    self.debug_info.dwarf.attr_locations(self.unit, location)?
+                else {
+                    return Ok(ValueLocation::OptimizedOut);
+                };
+
+                while let Some(entry) = locations.next()? {
+                    if (entry.range.begin..entry.range.end).contains(&self.address) {
+                        return self.evaluate(entry.data);
+                    }
+                }
+
+                // Not live at the current address
+                Ok(ValueLocation::OptimizedOut)
+            }
+        }
+    }
+
+    pub(crate) fn evaluate(
+        &self,
+        expression: Expression<SharedDwarfReader>,
+    ) -> Result<ValueLocation> {
+        let mut evaluation = expression.evaluation(self.unit.encoding());
+
+        let mut result = evaluation.evaluate()?;
+        loop {
+            result = match result {
+                EvaluationResult::Complete => break,
+                // Larger reads of DW_OP_deref_type do not fit into a generic value and are unsupported
+                EvaluationResult::RequiresMemory { address, size, .. } if size <= 8 => {
+                    let data = self.debugger.read_memory(address, size as usize)?;
+                    let mut bytes = [0; 8];
+                    bytes[..data.len()].copy_from_slice(&data);
+                    evaluation.resume_with_memory(DwarfValue::Generic(u64::from_le_bytes(bytes)))?
+                }
+                EvaluationResult::RequiresRegister { register, .. } => {
+                    let Some(value) = self.register(register.0) else {
+                        return Ok(ValueLocation::OptimizedOut);
+                    };
+                    evaluation.resume_with_register(DwarfValue::Generic(value))?
+                }
+                EvaluationResult::RequiresFrameBase => {
+                    let Some(frame_base) = self.frame_base else {
+                        return Ok(ValueLocation::OptimizedOut);
+                    };
+                    evaluation.resume_with_frame_base(frame_base)?
+                }
+                EvaluationResult::RequiresCallFrameCfa => {
+                    let Some(cfa) = self.cfa else {
+                        return Ok(ValueLocation::OptimizedOut);
+                    };
+                    evaluation.resume_with_call_frame_cfa(cfa)?
+                }
+                EvaluationResult::RequiresTls(offset) => {
+                    let address = self
+                        .debugger
+                        .thread_local_offset_address(self.module, offset)?;
+                    evaluation.resume_with_tls(address)?
+                }
+                EvaluationResult::RequiresRelocatedAddress(address) => evaluation
+                    .resume_with_relocated_address(address.wrapping_add(self.module.load_bias))?,
+                EvaluationResult::RequiresIndexedAddress { index, relocate } => {
+                    let mut address = self.debug_info.dwarf.address(self.unit, index)?;
+                    if relocate {
+                        address = address.wrapping_add(self.module.load_bias);
+                    }
+                    evaluation.resume_with_indexed_address(address)?
+                }
+                EvaluationResult::RequiresBaseType(offset) => {
+                    let base_type = ValueType::from_entry(&self.unit.entry(offset)?)?
+                        .unwrap_or(ValueType::Generic);
+                    evaluation.resume_with_base_type(base_type)?
+                }
+                // E.g. values of parameters at the entry of the function, which cannot be recovered later
+                other => {
+                    debug!("Unsupported requirement in location expression: {other:?}");
+                    return Ok(ValueLocation::OptimizedOut);
+                }
+            };
+        }
+
+        self.assemble_pieces(evaluation.result())
+    }
+
+    fn register(&self, register: u16) -> Option<u64> {
+        self.registers.as_ref()?.get(register)
+    }
+
+    // A value split into pieces, e.g. a struct whose members are kept in different registers, is assembled into its
+    // bytes. Pieces that do not start at a byte boundary are not supported.
+    fn assemble_pieces(&self, pieces: Vec<Piece<SharedDwarfReader>>) -> Result<ValueLocation> {
+        if let [piece] = pieces.as_slice()
+            && piece.size_in_bits.is_none()
+        {
+            return match piece.location.clone() {
+                Location::Address { address } => Ok(ValueLocation::Address(address)),
+                location => Ok(self
+                    .piece_data(location, None)?
+                    .map_or(ValueLocation::OptimizedOut, ValueLocation::Data)),
+            };
+        }
+
+        let mut data = Vec::new();
+        for piece in pieces {
+            let Some(size_in_bits) = piece.size_in_bits else {
+                return Ok(ValueLocation::OptimizedOut);
+            };
+            if size_in_bits % 8 != 0
+                || piece
+                    .bit_offset
+                    .is_some_and(|bit_offset| bit_offset % 8 != 0)
+            {
+                debug!("Unsupported piece of {size_in_bits} bits in location expression");
+                return Ok(ValueLocation::OptimizedOut);
+            }
+            let size = (size_in_bits / 8) as usize;
+
+            let piece_data = match piece.location {
+                Location::Address { address } => {
+                    let offset = piece.bit_offset.unwrap_or(0) / 8;
+                    Some(self.debugger.read_memory(address + offset, size)?)
+                }
+                location => self.piece_data(location, Some(size))?.map(|piece_data| {
+                    let offset = (piece.bit_offset.unwrap_or(0) / 8) as usize;
+                    piece_data.get(offset..).unwrap_or_default().to_vec()
+                }),
+            };
+            let Some(mut piece_data) = piece_data else {
+                return Ok(ValueLocation::OptimizedOut);
+            };
+            piece_data.resize(size, 0);
+            data.extend_from_slice(&piece_data);
+        }
+
+        Ok(ValueLocation::Data(data))
+    }
+
+    // The bytes of a piece that is not stored in memory
+    fn piece_data(
+        &self,
+        location: Location<SharedDwarfReader>,
+        size: Option<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        let data = match location {
+            Location::Register { register } => self
+                .register(register.0)
+                .map(|value| value.to_le_bytes().to_vec()),
+            Location::Value { value } => Some(value_bytes(value)),
+            Location::Bytes { value } => Some(value.to_vec()),
+            Location::Address { address } => {
+                Some(self.debugger.read_memory(address, size.unwrap_or(8))?)
+            }
+            Location::Empty | Location::ImplicitPointer { .. } => None,
+        };
+
+        Ok(data)
+    }
+}
+
+fn value_bytes(value: DwarfValue) -> Vec<u8> {
+    match value {
+        DwarfValue::Generic(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::I8(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::U8(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::I16(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::U16(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::I32(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::U32(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::I64(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::U64(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::F32(value) => value.to_le_bytes().to_vec(),
+        DwarfValue::F64(value) => value.to_le_bytes().to_vec(),
+    }
+}
@@ -0,0 +1,127 @@
+use log::*;
+use nix::sys::ptrace;
+
+use crate::{Debugger, Error, Result};
+
+const WORD_SIZE: u64 = std::mem::size_of::<i64>() as u64;
+
+impl Debugger {
+    fn read_word(&self, address: u64) -> Result<i64> {
+        ptrace::read(self.tracee_pid, address as *mut core::ffi::c_void).map_err(|errno| {
+            error!("Could not read from address 0x{address:8x?}: {errno}");
+
+            Error::ReadMemory(address)
+        })
+    }
+
+    fn write_word(&self, address: u64, word: i64) -> Result<()> {
+        ptrace::write(self.tracee_pid, address as *mut core::ffi::c_void, word).map_err(|errno| {
+            error!("Could not write to address 0x{address:8x?}: {errno}");
+
+            Error::WriteMemory(address)
+        })
+    }
+
+    // Reads memory as it currently is in the tracee, including inserted breakpoint instructions
+    pub(crate) fn read_memory_raw(&self, address: u64, length: usize) -> Result<Vec<u8>> {
+        let start = address & !(WORD_SIZE - 1);
+        let end = address
+            .checked_add(length as u64)
+            .ok_or(Error::ReadMemory(address))?;
+
+        let mut data = Vec::with_capacity(length + 2 * WORD_SIZE as usize);
+        let mut word_address = start;
+        while word_address < end {
+            data.extend_from_slice(&self.read_word(word_address)?.to_ne_bytes());
+            word_address += WORD_SIZE;
+        }
+
+        let skip = (address - start) as usize;
+        Ok(data[skip..skip + length].to_vec())
+    }
+
+    // Writes memory as is, without taking inserted breakpoint instructions into account
+    pub(crate) fn write_memory_raw(&self, address: u64, data: &[u8]) -> Result<()> {
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let current = address + offset as u64;
+            let word_address = current & !(WORD_SIZE - 1);
+            let skip = (current - word_address) as usize;
+            let count = (WORD_SIZE as usize - skip).min(data.len() - offset);
+
+            // Only read the old word, if it is not overwritten completely
+            let mut word = if count == WORD_SIZE as usize {
+                [0; WORD_SIZE as usize]
+            } else {
+                self.read_word(word_address)?.to_ne_bytes()
+            };
+            word[skip..skip + count].copy_from_slice(&data[offset..offset + count]);
+            self.write_word(word_address, i64::from_ne_bytes(word))?;
+
+            offset += count;
+        }
+
+        Ok(())
+    }
+
+    /// Reads tracee memory. Software breakpoints inserted by the debugger are hidden, i.e. the original bytes are
+    /// returned.
+    pub fn read_memory(&self, address: u64, length: usize) -> Result<Vec<u8>> {
+        let mut data = self.read_memory_raw(address, length)?;
+
+        for (breakpoint_address, original_byte) in &self.breakpoints {
+            if let Some(offset) = breakpoint_address.checked_sub(address)
+                && offset < length as u64
+            {
+                data[offset as usize] = *original_byte;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Writes tracee memory. Software breakpoints inside of the written range are kept in place.
+    pub fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+        let mut data = data.to_vec();
+
+        for (breakpoint_address, original_byte) in self.breakpoints.iter_mut() {
+            if let Some(offset) = breakpoint_address.checked_sub(address)
+                && offset < data.len() as u64
+            {
+                *original_byte = data[offset as usize];
+                data[offset as usize] = 0xCC;
+            }
+        }
+
+        self.write_memory_raw(address, &data)
+    }
+
+    pub fn read_u64(&self, address: u64) -> Result<u64> {
+        let data = self.read_memory(address, WORD_SIZE as usize)?;
+
+        Ok(u64::from_ne_bytes(
+            data.try_into()
+                .expect("read to return the requested length"),
+        ))
+    }
+
+    /// Reads a NUL-terminated string of at most `max_length` bytes.
+    pub fn read_c_string(&self, address: u64, max_length: usize) -> Result<String> {
+        let mut bytes = Vec::new();
+        let mut word_address = address;
+
+        while bytes.len() < max_length {
+            let chunk = self.read_memory(word_address, WORD_SIZE as usize)?;
+            if let Some(nul) = chunk.iter().position(|byte| *byte == 0) {
+                bytes.extend_from_slice(&chunk[..nul]);
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+            word_address += WORD_SIZE;
+        }
+        bytes.truncate(max_length);
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
@@ -1,5 +1,13 @@
+use std::{fmt, path::Path};
+
+use log::*;
 use nix::unistd::Pid;
 
+use crate::{Debugger, Error, Result};
+
+// The kernel appends this to the pathname of files that were deleted after they were mapped
+const DELETED_SUFFIX: &str = " (deleted)";
+
 #[derive(thiserror::Error, Debug)]
 enum ParseError {
     #[error("The permission string needs to be of length 4")]
@@ -8,18 +16,20 @@ enum ParseError {
     IntegerField(&'static str, u32),
     #[error("Expected field {0} to be present")]
     ExpectedField(&'static str),
+    #[error("The range ends before it starts")]
+    InvertedRange,
 }
 
 #[derive(Debug, Clone)]
 pub struct MemoryMapPermissions {
-    read: bool,
-    write: bool,
-    execute: bool,
-    private: bool,
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    pub private: bool,
 }
 
 impl MemoryMapPermissions {
-    fn from_str(value: &str) -> Result<Self, ParseError> {
+    fn from_str(value: &str) -> std::result::Result<Self, ParseError> {
         if value.len() != 4 {
             return Err(ParseError::PermissionLength);
         }
@@ -46,11 +56,31 @@ pub struct MemoryMap {
     pub device_minor: u64,
     pub offset: u64,
     pub inode_number: u64,
+    /// The mapped file, or a pseudo path like `[heap]` or `[stack]`. Anonymous mappings have no pathname.
     pub pathname: Option<String>,
+    /// Whether the mapped file was deleted after it was mapped.
+    pub deleted: bool,
+}
+
+impl fmt::Display for MemoryMapPermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' },
+            if self.private { 'p' } else { 's' }
+        )
+    }
 }
 
 impl MemoryMap {
-    fn parse_range(entry: &str, separator: char, radix: u32) -> Result<(u64, u64), ParseError> {
+    fn parse_range(
+        entry: &str,
+        separator: char,
+        radix: u32,
+    ) -> std::result::Result<(u64, u64), ParseError> {
         let mut range = entry.split(separator);
         let range_from = range
             .next()
@@ -64,30 +94,51 @@ impl MemoryMap {
         Ok((range_from, range_to))
     }
 
-    fn from_str(entry: &str) -> Result<Self, ParseError> {
-        let mut parts = entry.split(" ").filter(|s| !s.is_empty());
-        let range = parts.next().ok_or(ParseError::ExpectedField("range"))?;
+    // Splits off the next field, which are separated by one or more spaces
+    fn next_field<'a>(rest: &mut &'a str) -> Option<&'a str> {
+        let trimmed = rest.trim_start_matches(' ');
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        let end = trimmed.find(' ').unwrap_or(trimmed.len());
+        let (field, remainder) = trimmed.split_at(end);
+        *rest = remainder;
+
+        Some(field)
+    }
+
+    fn from_str(entry: &str) -> std::result::Result<Self, ParseError> {
+        let mut rest = entry;
+
+        let range = Self::next_field(&mut rest).ok_or(ParseError::ExpectedField("range"))?;
         let (range_from, range_to) = Self::parse_range(range, '-', 16)?;
+        if range_to < range_from {
+            return Err(ParseError::InvertedRange);
+        }
 
-        let permissions = parts
-            .next()
-            .ok_or(ParseError::ExpectedField("permissions"))?;
+        let permissions =
+            Self::next_field(&mut rest).ok_or(ParseError::ExpectedField("permissions"))?;
         let permissions = MemoryMapPermissions::from_str(permissions)?;
 
-        let offset = parts
-            .next()
+        let offset = Self::next_field(&mut rest)
             .and_then(|s| u64::from_str_radix(s, 16).ok())
             .ok_or(ParseError::ExpectedField("offset"))?;
 
-        let device = parts.next().ok_or(ParseError::ExpectedField("device"))?;
+        let device = Self::next_field(&mut rest).ok_or(ParseError::ExpectedField("device"))?;
         let (device_major, device_minor) = Self::parse_range(device, ':', 16)?;
 
-        let inode_number = parts
-            .next()
+        let inode_number = Self::next_field(&mut rest)
             .and_then(|s| s.parse::<u64>().ok())
             .ok_or(ParseError::ExpectedField("inode_number"))?;
 
-        let pathname = parts.next().map(|s| s.to_owned());
+        // The pathname is the remainder of the line and may contain spaces itself
+        let pathname = rest.trim_start_matches(' ');
+        let (pathname, deleted) = match pathname.strip_suffix(DELETED_SUFFIX) {
+            Some(pathname) => (pathname, true),
+            None => (pathname, false),
+        };
+        let pathname = (!pathname.is_empty()).then(|| pathname.to_owned());
 
         let memory_map = Self {
             range_from,
@@ -98,10 +149,27 @@ impl MemoryMap {
             offset,
             inode_number,
             pathname,
+            deleted,
         };
 
         Ok(memory_map)
     }
+
+    pub fn size(&self) -> u64 {
+        self.range_to - self.range_from
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        (self.range_from..self.range_to).contains(&address)
+    }
+
+    /// The path of the mapped file, `None` for anonymous mappings and pseudo paths like `[heap]`.
+    pub fn file_path(&self) -> Option<&Path> {
+        self.pathname
+            .as_deref()
+            .filter(|pathname| !(pathname.starts_with('[') && pathname.ends_with(']')))
+            .map(Path::new)
+    }
 }
 
 #[derive(Debug)]
@@ -110,21 +178,152 @@ pub struct ProcMemoryMaps {
 }
 
 impl ProcMemoryMaps {
-    pub fn from_pid(pid: Pid) -> Result<Self, std::io::Error> {
+    pub fn from_pid(pid: Pid) -> Result<Self> {
         let maps_content = std::fs::read_to_string(format!("/proc/{pid}/maps"))?;
 
-        let memory_maps: Vec<MemoryMap> = maps_content
+        let memory_maps = maps_content
             .lines()
-            .map(|line| MemoryMap::from_str(line).expect("no parsing errors to occur"))
-            .collect();
+            .map(|line| {
+                MemoryMap::from_str(line).map_err(|err| {
+                    error!("Failed to parse memory map {line:?}: {err}");
+
+                    Error::ParseMemoryMap(line.to_owned())
+                })
+            })
+            .collect::<Result<_>>()?;
 
         Ok(Self { memory_maps })
     }
 
-    pub fn get_text_section(&self) -> &MemoryMap {
+    pub fn iter(&self) -> impl Iterator<Item = &MemoryMap> {
+        self.memory_maps.iter()
+    }
+
+    pub fn find_by_pathname<'a>(
+        &'a self,
+        pathname: &'a Path,
+    ) -> impl Iterator<Item = &'a MemoryMap> {
         self.memory_maps
             .iter()
-            .find(|map| map.permissions.execute)
-            .expect("there to be atleast one text section per process")
+            .filter(move |map| map.pathname.as_deref().map(Path::new) == Some(pathname))
+    }
+
+    pub fn find_by_address(&self, address: u64) -> Option<&MemoryMap> {
+        self.memory_maps.iter().find(|map| map.contains(address))
+    }
+
+    /// All mappings that have at least the requested permissions.
+    pub fn find_by_permissions(
+        &self,
+        read: bool,
+        write: bool,
+        execute: bool,
+    ) -> impl Iterator<Item = &MemoryMap> {
+        self.memory_maps.iter().filter(move |map| {
+            (!read || map.permissions.read)
+                && (!write || map.permissions.write)
+                && (!execute || map.permissions.execute)
+        })
+    }
+
+    /// The first executable mapping of the process.
+    pub fn get_text_section(&self) -> Option<&MemoryMap> {
+        self.memory_maps.iter().find(|map| map.permissions.execute)
+    }
+}
+
+impl<'a> IntoIterator for &'a ProcMemoryMaps {
+    type Item = &'a MemoryMap;
+    type IntoIter = std::slice::Iter<'a, MemoryMap>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.memory_maps.iter()
+    }
+}
+
+impl Debugger {
+    pub fn memory_maps(&self) -> &ProcMemoryMaps {
+        &self.memory_maps
+    }
+
+    /// Reads the memory maps of the tracee again. They are refreshed automatically when shared libraries are loaded or
+    /// unloaded, but other mappings like the heap can change at any time.
+    pub fn refresh_memory_maps(&mut self) -> Result<()> {
+        self.memory_maps = ProcMemoryMaps::from_pid(self.tracee_pid)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_file_mapping() {
+        let map = MemoryMap::from_str(
+            "55d0c3a00000-55d0c3a01000 r-xp 00001000 fd:01 1837258                    /usr/bin/cat",
+        )
+        .unwrap();
+
+        assert_eq!(map.range_from, 0x55d0c3a00000);
+        assert_eq!(map.range_to, 0x55d0c3a01000);
+        assert_eq!(map.permissions.to_string(), "r-xp");
+        assert_eq!(map.offset, 0x1000);
+        assert_eq!((map.device_major, map.device_minor), (0xfd, 0x01));
+        assert_eq!(map.inode_number, 1837258);
+        assert_eq!(map.pathname.as_deref(), Some("/usr/bin/cat"));
+        assert!(!map.deleted);
+        assert_eq!(map.size(), 0x1000);
+        assert!(map.contains(0x55d0c3a00fff));
+        assert!(!map.contains(0x55d0c3a01000));
+        assert_eq!(map.file_path(), Some(Path::new("/usr/bin/cat")));
+    }
+
+    #[test]
+    fn parses_anonymous_and_pseudo_mappings() {
+        let anonymous =
+            MemoryMap::from_str("7f0000000000-7f0000002000 rw-p 00000000 00:00 0 ").unwrap();
+        assert_eq!(anonymous.pathname, None);
+        assert_eq!(anonymous.file_path(), None);
+
+        let stack = MemoryMap::from_str("7ffd1000-7ffd2000 rw-s 00000000 00:00 0          [stack]")
+            .unwrap();
+        assert_eq!(stack.pathname.as_deref(), Some("[stack]"));
+        assert_eq!(stack.file_path(), None);
+        assert!(!stack.permissions.private);
+    }
+
+    #[test]
+    fn parses_pathname_with_spaces_and_deleted_suffix() {
+        let map = MemoryMap::from_str("1000-2000 r--p 00000000 08:02 42 /tmp/my lib.so (deleted)")
+            .unwrap();
+
+        assert_eq!(map.pathname.as_deref(), Some("/tmp/my lib.so"));
+        assert!(map.deleted);
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!(matches!(
+            MemoryMap::from_str("1000-2000 rwx 00000000 08:02 42"),
+            Err(ParseError::PermissionLength)
+        ));
+        assert!(matches!(
+            MemoryMap::from_str("1000-zz r--p 00000000 08:02 42"),
+            Err(ParseError::IntegerField("range_to", 16))
+        ));
+        assert!(matches!(
+            MemoryMap::from_str("1000-2000 r--p 00000000 08:02"),
+            Err(ParseError::ExpectedField("inode_number"))
+        ));
+        assert!(matches!(
+            MemoryMap::from_str("2000-1000 r--p 00000000 08:02 42"),
+            Err(ParseError::InvertedRange)
+        ));
+        assert!(matches!(
+            MemoryMap::from_str(""),
+            Err(ParseError::ExpectedField("range"))
+        ));
     }
 }
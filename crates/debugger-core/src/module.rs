@@ -0,0 +1,149 @@
+use std::{
+    cell::OnceCell,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use elf::{
+    ElfBytes,
+    abi::{PT_LOAD, PT_TLS},
+    endian::AnyEndian,
+};
+
+use log::*;
+
+use crate::{
+    Debugger, Result, debug_file::DebugFile, debug_info::LoadedDebugInfo, line_table::LineTable,
+    symbols::SymbolIndex,
+};
+
+/// The initialization image of thread-local storage, described by the `PT_TLS` segment.
+#[derive(Debug, Clone, Copy)]
+pub struct TlsSegment {
+    pub virtual_address: u64,
+    pub memory_size: u64,
+    pub alignment: u64,
+}
+
+/// An ELF file mapped into the tracee, either the main executable or a shared library.
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub load_bias: u64,
+    pub symbols: SymbolIndex,
+    pub lines: LineTable,
+    pub tls: Option<TlsSegment>,
+    /// The index of the TLS block of this module in the dynamic thread vector, as assigned by the dynamic linker.
+    pub tls_module_id: Option<u64>,
+    /// The separate debug file, if the module is stripped and one was found.
+    pub debug_file: Option<DebugFile>,
+    pub(crate) data: Arc<[u8]>,
+    // Link-time address range covered by the loadable segments
+    link_range: Range<u64>,
+    // Parsed on first use, `None` if the debug information is malformed
+    pub(crate) debug_info: OnceCell<Option<LoadedDebugInfo>>,
+}
+
+impl Module {
+    pub fn from_elf_data(path: PathBuf, data: Vec<u8>, load_bias: u64) -> Result<Self> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(&data)?;
+
+        let load_segments: Vec<_> = elf_bytes
+            .segments()
+            .map(|segments| {
+                segments
+                    .iter()
+                    .filter(|segment| segment.p_type == PT_LOAD)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let link_start = load_segments.iter().map(|segment| segment.p_vaddr).min();
+        let link_end = load_segments
+            .iter()
+            .map(|segment| segment.p_vaddr + segment.p_memsz)
+            .max();
+        let link_range = link_start.unwrap_or(0)..link_end.unwrap_or(0);
+
+        let tls = elf_bytes.segments().and_then(|segments| {
+            segments
+                .iter()
+                .find(|segment| segment.p_type == PT_TLS)
+                .map(|segment| TlsSegment {
+                    virtual_address: segment.p_vaddr,
+                    memory_size: segment.p_memsz,
+                    alignment: segment.p_align.max(1),
+                })
+        });
+
+        let symbols = SymbolIndex::from_elf_data(&data)?;
+        let lines = load_line_table(&path, &data);
+
+        Ok(Self {
+            path,
+            load_bias,
+            symbols,
+            lines,
+            tls,
+            tls_module_id: None,
+            debug_file: None,
+            data: data.into(),
+            link_range,
+            debug_info: OnceCell::new(),
+        })
+    }
+
+    /// The runtime address range covered by the loadable segments of this module.
+    pub fn address_range(&self) -> Range<u64> {
+        self.link_range.start.wrapping_add(self.load_bias)
+            ..self.link_range.end.wrapping_add(self.load_bias)
+    }
+
+    // The separate debug file carries the DWARF sections of a stripped module
+    pub(crate) fn debug_data(&self) -> &Arc<[u8]> {
+        match &self.debug_file {
+            Some(debug_file) => &debug_file.data,
+            None => &self.data,
+        }
+    }
+
+    // Missing debug information only limits inspecting variables, so a malformed `.debug_info` is not fatal
+    pub(crate) fn debug_info(&self) -> Option<&LoadedDebugInfo> {
+        self.debug_info
+            .get_or_init(|| {
+                LoadedDebugInfo::load(self.debug_data())
+                    .inspect_err(|err| {
+                        warn!(
+                            "Failed to read the debug information of {}: {err}",
+                            self.path.display()
+                        );
+                    })
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Checks whether a runtime address lies within one of the loadable segments of this module.
+    pub fn contains(&self, address: u64) -> bool {
+        self.link_range
+            .contains(&address.wrapping_sub(self.load_bias))
+    }
+}
+
+// Missing line information only limits source level debugging, so a malformed line table is not fatal
+pub(crate) fn load_line_table(path: &Path, data: &[u8]) -> LineTable {
+    LineTable::from_elf_data(data).unwrap_or_else(|err| {
+        warn!("Failed to read the line table of {}: {err}", path.display());
+        LineTable::default()
+    })
+}
+
+impl Debugger {
+    pub fn modules(&self) -> impl Iterator<Item = &Module> {
+        std::iter::once(&self.executable).chain(&self.shared_libraries)
+    }
+
+    pub fn executable_module(&self) -> &Module {
+        &self.executable
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fmt, path::Path};
+
+use nix::sys::signal::Signal;
+
+use crate::{
+    ContinueExecutionOutcome, Debugger, Result, event::SyscallEvent, watchpoint::Watchpoint,
+};
+
+/// What the debugger should do after an observer has been notified about an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObserverDecision {
+    /// Resume the tracee without reporting the event to the caller.
+    Continue,
+    /// Execute a single instruction and notify the observers again through [`DebugObserver::on_step`].
+    Step,
+    /// Report the event to the caller and keep the tracee stopped.
+    Stop,
+}
+
+/// Hooks that are called for tracee events, before they are reported to the caller of
+/// [`Debugger::continue_execution`], [`Debugger::poll_event`] or [`Debugger::wait_event`].
+///
+/// If multiple observers are registered, the most restrictive decision wins: [`ObserverDecision::Stop`] over
+/// [`ObserverDecision::Step`] over [`ObserverDecision::Continue`].
+pub trait DebugObserver {
+    fn on_breakpoint(&mut self, _debugger: &Debugger, _address: u64) -> ObserverDecision {
+        ObserverDecision::Stop
+    }
+
+    fn on_watchpoint(
+        &mut self,
+        _debugger: &Debugger,
+        _address: u64,
+        _watchpoint: Watchpoint,
+    ) -> ObserverDecision {
+        ObserverDecision::Stop
+    }
+
+    fn on_signal(&mut self, _debugger: &Debugger, _signal: Signal) -> ObserverDecision {
+        ObserverDecision::Stop
+    }
+
+    fn on_step(&mut self, _debugger: &Debugger, _pc: u64) -> ObserverDecision {
+        ObserverDecision::Stop
+    }
+
+    /// Only called if syscall tracing is enabled, see [`Debugger::set_syscall_tracing`].
+    fn on_syscall(&mut self, _debugger: &Debugger, _syscall: SyscallEvent) -> ObserverDecision {
+        ObserverDecision::Continue
+    }
+
+    fn on_library_loaded(
+        &mut self,
+        _debugger: &Debugger,
+        _path: &Path,
+        _base_address: u64,
+    ) -> ObserverDecision {
+        ObserverDecision::Continue
+    }
+
+    /// Called once the tracee exited or was killed. `exit_code` is `None` if it was killed by `signal`.
+    fn on_exit(&mut self, _debugger: &Debugger, _exit_code: Option<i32>, _signal: Option<Signal>) {}
+}
+
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Box<dyn DebugObserver>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl Debugger {
+    pub fn add_observer(&mut self, observer: Box<dyn DebugObserver>) {
+        self.observers.0.push(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    fn notify_observers(&mut self, outcome: &ContinueExecutionOutcome) -> ObserverDecision {
+        // Take the observers out of self, so they can be handed a reference to the debugger
+        let mut observers = std::mem::take(&mut self.observers);
+
+        let decision = observers
+            .0
+            .iter_mut()
+            .map(|observer| match outcome {
+                ContinueExecutionOutcome::ProcessExited(exit_code) => {
+                    observer.on_exit(self, Some(*exit_code), None);
+                    ObserverDecision::Stop
+                }
+                ContinueExecutionOutcome::ProcessKilled(signal) => {
+                    observer.on_exit(self, None, Some(*signal));
+                    ObserverDecision::Stop
+                }
+                ContinueExecutionOutcome::BreakpointHit(address) => {
+                    observer.on_breakpoint(self, *address)
+                }
+                ContinueExecutionOutcome::WatchpointHit((address, watchpoint)) => {
+                    observer.on_watchpoint(self, *address, *watchpoint)
+                }
+                ContinueExecutionOutcome::SignalReceived(signal) => {
+                    observer.on_signal(self, *signal)
+                }
+                ContinueExecutionOutcome::Syscall(syscall) => observer.on_syscall(self, *syscall),
+                ContinueExecutionOutcome::LibrariesLoaded(paths) => paths
+                    .iter()
+                    .filter_map(|path| {
+                        let library = self.modules().find(|module| &module.path == path)?;
+                        Some(observer.on_library_loaded(self, path, library.load_bias))
+                    })
+                    .fold(ObserverDecision::Continue, ObserverDecision::max),
+                ContinueExecutionOutcome::Stepped(pc) => observer.on_step(self, *pc),
+                ContinueExecutionOutcome::Other => ObserverDecision::Stop,
+            })
+            .fold(ObserverDecision::Continue, ObserverDecision::max);
+
+        self.observers = observers;
+
+        decision
+    }
+
+    // Lets the registered observers decide what to do with an event. Returns `None` if the tracee was resumed and
+    // the event should not be reported.
+    pub(crate) fn apply_observers(
+        &mut self,
+        mut outcome: ContinueExecutionOutcome,
+    ) -> Result<Option<ContinueExecutionOutcome>> {
+        // Library loads are only reported if an observer asks for it
+        if self.observers.0.is_empty()
+            && !matches!(outcome, ContinueExecutionOutcome::LibrariesLoaded(_))
+        {
+            return Ok(Some(outcome));
+        }
+
+        loop {
+            match self.notify_observers(&outcome) {
+                ObserverDecision::Stop => return Ok(Some(outcome)),
+                ObserverDecision::Continue => {
+                    self.resume()?;
+                    return Ok(None);
+                }
+                ObserverDecision::Step => {
+                    // A watchpoint triggered by the stepped instruction is reported instead of the step
+                    outcome = match self.step_instruction()? {
+                        None => ContinueExecutionOutcome::Stepped(self.get_tracee_pc()?),
+                        Some(wait_status) => self.handle_wait_status(wait_status)?,
+                    };
+                }
+            }
+        }
+    }
+}
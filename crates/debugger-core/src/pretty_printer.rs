@@ -0,0 +1,749 @@
+use std::fmt;
+
+use crate::{
+    Debugger, Error, Result,
+    types::{Member, Type, TypeId, is_tuple_field},
+    value::{MAX_ELEMENTS, MAX_STRING_LENGTH, Value, ValueLocation, integer, mask},
+    variables::Inspector,
+};
+
+/// How a pretty printer shows a value. Children are formatted like any other value, so they are pretty printed
+/// themselves, e.g. the strings in a `Vec<String>`.
+#[derive(Debug, Clone)]
+pub struct PrettyValue {
+    /// Shown in front of the children, e.g. `Vec(size=3)`, the name of an enum variant or a whole string.
+    pub summary: String,
+    pub children: PrettyChildren,
+    /// Whether only the first children are included, e.g. of a long `Vec`.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum PrettyChildren {
+    None,
+    /// Shown like an array, `Vec(size=2) = {1, 2}`.
+    Elements(Vec<Value>),
+    /// Shown like a map, `HashMap(size=1) = {["a"] = 1}`.
+    Entries(Vec<(Value, Value)>),
+    /// Shown like a struct variant of an enum, `Circle {r = 1.5}`.
+    Fields(Vec<(String, Value)>),
+    /// Shown like a tuple variant of an enum, `Some(5)`.
+    Tuple(Vec<Value>),
+    /// A single value, e.g. the contents of a `Box`, shown like `Box(0x1234) = {x = 1}`.
+    Value(Value),
+}
+
+impl PrettyValue {
+    pub fn new(summary: impl Into<String>, children: PrettyChildren) -> Self {
+        PrettyValue {
+            summary: summary.into(),
+            children,
+            truncated: false,
+        }
+    }
+}
+
+/// Formats values of certain types in a readable way instead of showing their internal structure, e.g. the
+/// collections of the Rust standard library. Printers are registered with [`Debugger::add_pretty_printer`].
+pub trait PrettyPrinter {
+    /// A short name, e.g. to list the registered printers.
+    fn name(&self) -> &str;
+
+    /// Returns `None` if the printer does not handle the value. The type of the value has its typedefs removed and
+    /// `type_name` is its name qualified by the path of its namespaces, e.g. `alloc::vec::Vec<i32, alloc::alloc::Global>`.
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>>;
+}
+
+pub(crate) struct PrettyPrinters {
+    printers: Vec<Box<dyn PrettyPrinter>>,
+    enabled: bool,
+}
+
+impl Default for PrettyPrinters {
+    fn default() -> Self {
+        PrettyPrinters {
+            printers: vec![
+                Box::new(EnumPrinter),
+                Box::new(TuplePrinter),
+                Box::new(StrPrinter),
+                Box::new(SlicePrinter),
+                Box::new(StringPrinter),
+                Box::new(VecPrinter),
+                Box::new(VecDequePrinter),
+                Box::new(HashMapPrinter),
+                Box::new(BoxPrinter),
+                Box::new(RcPrinter),
+            ],
+            enabled: true,
+        }
+    }
+}
+
+impl fmt::Debug for PrettyPrinters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrettyPrinters({})", self.printers.len())
+    }
+}
+
+impl Debugger {
+    /// Registers a pretty printer. It takes precedence over the built-in printers and the ones registered before.
+    pub fn add_pretty_printer(&mut self, printer: Box<dyn PrettyPrinter>) {
+        self.pretty_printers.printers.push(printer);
+    }
+
+    /// Shows the raw structure of all values when disabled.
+    pub fn set_pretty_printing(&mut self, enabled: bool) {
+        self.pretty_printers.enabled = enabled;
+    }
+
+    pub fn pretty_printing(&self) -> bool {
+        self.pretty_printers.enabled
+    }
+
+    /// The names of the pretty printers in the order they are tried.
+    pub fn pretty_printer_names(&self) -> impl Iterator<Item = &str> {
+        self.pretty_printers
+            .printers
+            .iter()
+            .rev()
+            .map(|printer| printer.name())
+    }
+}
+
+impl Inspector<'_> {
+    // Asks the printers for a readable form of the value, the last registered first
+    pub(crate) fn pretty_print(&self, value: &Value) -> Result<Option<PrettyValue>> {
+        let printers = &self.debugger.pretty_printers;
+        if !printers.enabled || value.ty.is_none() {
+            return Ok(None);
+        }
+
+        let type_name = self.debug_info.qualified_type_name(value.ty)?;
+        for printer in printers.printers.iter().rev() {
+            if let Some(pretty) = printer.print(self, value, &type_name)? {
+                return Ok(Some(pretty));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Follows structs with a single field, like `NonNull<T>` or `Cell<T>`, to the innermost field. Fields without
+    /// a size, like `PhantomData<T>`, are ignored.
+    pub fn unwrap_newtype(&self, value: &Value) -> Result<Value> {
+        let mut value = value.clone();
+
+        loop {
+            let (ty, parsed) = self.debug_info.strip_typedefs(value.ty)?;
+            let Type::Composite {
+                members,
+                variant_part: None,
+                ..
+            } = parsed
+            else {
+                return Ok(Value { ty, ..value });
+            };
+
+            let mut fields = Vec::new();
+            for member in members {
+                if self.type_size(member.ty)? != Some(0) {
+                    fields.push(member);
+                }
+            }
+            match fields.as_slice() {
+                [field] if field.bit_field.is_none() => {
+                    value = value.at_offset(field.offset, field.ty);
+                }
+                _ => return Ok(Value { ty, ..value }),
+            }
+        }
+    }
+
+    /// Reads an integer or pointer, also when it is wrapped into a struct with a single field.
+    pub fn read_integer(&self, value: &Value) -> Result<u64> {
+        self.as_address(&self.unwrap_newtype(value)?)
+    }
+
+    /// The type of a generic parameter of a struct, e.g. `T` of `Vec<T>`.
+    pub fn template_parameter(&self, ty: Option<TypeId>, name: &str) -> Result<Option<TypeId>> {
+        let Type::Composite {
+            template_parameters,
+            ..
+        } = self.debug_info.strip_typedefs(ty)?.1
+        else {
+            return Ok(None);
+        };
+
+        Ok(template_parameters
+            .into_iter()
+            .find(|parameter| parameter.name == name)
+            .and_then(|parameter| parameter.ty))
+    }
+
+    /// The member of the variant that is active according to the discriminant, e.g. `Some` of an `Option`. `None` if
+    /// the type has no variants or the discriminant matches none of them.
+    pub fn active_variant(&self, value: &Value) -> Result<Option<Member>> {
+        let Type::Composite {
+            variant_part: Some(variant_part),
+            ..
+        } = self.debug_info.strip_typedefs(value.ty)?.1
+        else {
+            return Ok(None);
+        };
+
+        let discriminant = match &variant_part.discriminant {
+            Some(member) => {
+                let size = self.type_size(member.ty)?.unwrap_or(0);
+                let Some(data) = self.read_value(&value.at_offset(member.offset, None), size)?
+                else {
+                    return Ok(None);
+                };
+                Some((integer(&data, false) as u128, mask(size * 8)))
+            }
+            None => None,
+        };
+
+        // Niche optimized enums only give values for the variants without fields
+        let variant = variant_part
+            .variants
+            .iter()
+            .find(|variant| {
+                variant
+                    .discriminant
+                    .zip(discriminant)
+                    .is_some_and(|(expected, (actual, mask))| expected as u128 & mask == actual)
+            })
+            .or_else(|| {
+                variant_part
+                    .variants
+                    .iter()
+                    .find(|variant| variant.discriminant.is_none())
+            });
+
+        Ok(variant.and_then(|variant| variant.members.first().cloned()))
+    }
+
+    // The elements of a contiguous buffer, at most as many as are shown
+    fn buffer_elements(
+        &self,
+        address: u64,
+        element: Option<TypeId>,
+        length: u64,
+    ) -> Result<(Vec<Value>, bool)> {
+        let size = self.type_size(element)?.unwrap_or(0);
+        let elements = (0..length.min(MAX_ELEMENTS as u64))
+            .map(|index| Value {
+                ty: element,
+                location: ValueLocation::Address(address.wrapping_add(index * size)),
+            })
+            .collect();
+
+        Ok((elements, length > MAX_ELEMENTS as u64))
+    }
+
+    // The start of the allocation of a `RawVec` and its capacity, which moved into `RawVecInner` in Rust 1.84
+    fn raw_vec(&self, raw_vec: &Value) -> Result<(u64, u64)> {
+        let inner = self
+            .member(raw_vec, "inner")
+            .or_else(|_| Ok::<_, Error>(raw_vec.clone()))?;
+        let address = self.read_integer(&self.member(&inner, "ptr")?)?;
+        let capacity = self.read_integer(&self.member(&inner, "cap")?)?;
+
+        Ok((address, capacity))
+    }
+
+    // The bytes of a `Vec<u8>`, e.g. of a `String`
+    fn vec_bytes(&self, vec: &Value) -> Result<(Vec<u8>, bool)> {
+        let (address, _) = self.raw_vec(&self.member(vec, "buf")?)?;
+        let length = self.read_integer(&self.member(vec, "len")?)?;
+
+        self.read_bytes(address, length)
+    }
+
+    fn read_bytes(&self, address: u64, length: u64) -> Result<(Vec<u8>, bool)> {
+        let shown = length.min(MAX_STRING_LENGTH as u64);
+        let bytes = self.debugger.read_memory(address, shown as usize)?;
+
+        Ok((bytes, length > shown))
+    }
+}
+
+// The path of a generic type without its parameters, e.g. `alloc::vec::Vec` for `alloc::vec::Vec<i32, Global>`
+fn generic_path(type_name: &str) -> &str {
+    type_name
+        .split_once('<')
+        .map_or(type_name, |(path, _)| path)
+}
+
+// Rust strings are shown like `Debug` formats them
+fn quote_str(bytes: &[u8], truncated: bool) -> PrettyValue {
+    let mut summary = format!("{:?}", String::from_utf8_lossy(bytes));
+    if truncated {
+        summary += "...";
+    }
+
+    PrettyValue::new(summary, PrettyChildren::None)
+}
+
+fn sized_summary(type_name: &str, size: u64) -> String {
+    let path = generic_path(type_name);
+    let name = path.rsplit_once("::").map_or(path, |(_, name)| name);
+
+    format!("{name}(size={size})")
+}
+
+// Enums with fields, like `Option<T>` or `Result<T, E>`, are shown as their active variant, e.g. `Some(5)`
+struct EnumPrinter;
+
+impl PrettyPrinter for EnumPrinter {
+    fn name(&self) -> &str {
+        "enum"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        _type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        let Some(variant) = inspector.active_variant(value)? else {
+            return Ok(None);
+        };
+        let summary = variant.name.clone().unwrap_or_default();
+        let variant_value = value.at_offset(variant.offset, variant.ty);
+
+        let Type::Composite { members, .. } = inspector.debug_info.strip_typedefs(variant.ty)?.1
+        else {
+            return Ok(Some(PrettyValue::new(
+                summary,
+                PrettyChildren::Value(variant_value),
+            )));
+        };
+
+        let children = if members.is_empty() {
+            PrettyChildren::None
+        } else if members
+            .iter()
+            .all(|member| is_tuple_field(member.name.as_deref()))
+        {
+            PrettyChildren::Tuple(
+                members
+                    .iter()
+                    .map(|member| variant_value.at_offset(member.offset, member.ty))
+                    .collect(),
+            )
+        } else {
+            PrettyChildren::Fields(
+                members
+                    .iter()
+                    .map(|member| {
+                        (
+                            member.name.clone().unwrap_or_default(),
+                            variant_value.at_offset(member.offset, member.ty),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+
+        Ok(Some(PrettyValue::new(summary, children)))
+    }
+}
+
+struct TuplePrinter;
+
+impl PrettyPrinter for TuplePrinter {
+    fn name(&self) -> &str {
+        "tuple"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if !type_name.starts_with('(') {
+            return Ok(None);
+        }
+        let Type::Composite { members, .. } = inspector.debug_info.strip_typedefs(value.ty)?.1
+        else {
+            return Ok(None);
+        };
+
+        let fields = members
+            .iter()
+            .map(|member| value.at_offset(member.offset, member.ty))
+            .collect();
+
+        Ok(Some(PrettyValue::new("", PrettyChildren::Tuple(fields))))
+    }
+}
+
+// `&str`, `&mut str` and `Box<str>` are fat pointers to UTF-8 bytes
+struct StrPrinter;
+
+impl PrettyPrinter for StrPrinter {
+    fn name(&self) -> &str {
+        "str"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if !matches!(type_name, "&str" | "&mut str" | "*const str" | "*mut str")
+            && !type_name.starts_with("alloc::boxed::Box<str,")
+        {
+            return Ok(None);
+        }
+
+        let address = inspector.read_integer(&inspector.member(value, "data_ptr")?)?;
+        let length = inspector.read_integer(&inspector.member(value, "length")?)?;
+        let (bytes, truncated) = inspector.read_bytes(address, length)?;
+
+        Ok(Some(quote_str(&bytes, truncated)))
+    }
+}
+
+// Slices are fat pointers to their first element
+struct SlicePrinter;
+
+impl PrettyPrinter for SlicePrinter {
+    fn name(&self) -> &str {
+        "slice"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if !type_name.starts_with("&[")
+            && !type_name.starts_with("&mut [")
+            && !type_name.starts_with("alloc::boxed::Box<[")
+        {
+            return Ok(None);
+        }
+
+        let data_ptr = inspector.member(value, "data_ptr")?;
+        let element = match inspector.debug_info.strip_typedefs(data_ptr.ty)?.1 {
+            Type::Pointer { pointee, .. } => pointee,
+            _ => return Ok(None),
+        };
+        let address = inspector.read_integer(&data_ptr)?;
+        let length = inspector.read_integer(&inspector.member(value, "length")?)?;
+        let (elements, truncated) = inspector.buffer_elements(address, element, length)?;
+
+        Ok(Some(PrettyValue {
+            summary: format!("{type_name}(size={length})"),
+            children: PrettyChildren::Elements(elements),
+            truncated,
+        }))
+    }
+}
+
+struct StringPrinter;
+
+impl PrettyPrinter for StringPrinter {
+    fn name(&self) -> &str {
+        "String"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if type_name != "alloc::string::String" {
+            return Ok(None);
+        }
+
+        let (bytes, truncated) = inspector.vec_bytes(&inspector.member(value, "vec")?)?;
+
+        Ok(Some(quote_str(&bytes, truncated)))
+    }
+}
+
+struct VecPrinter;
+
+impl PrettyPrinter for VecPrinter {
+    fn name(&self) -> &str {
+        "Vec"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if generic_path(type_name) != "alloc::vec::Vec" {
+            return Ok(None);
+        }
+        let Some(element) = inspector.template_parameter(value.ty, "T")? else {
+            return Ok(None);
+        };
+
+        let (address, _) = inspector.raw_vec(&inspector.member(value, "buf")?)?;
+        let length = inspector.read_integer(&inspector.member(value, "len")?)?;
+        let (elements, truncated) = inspector.buffer_elements(address, Some(element), length)?;
+
+        Ok(Some(PrettyValue {
+            summary: sized_summary(type_name, length),
+            children: PrettyChildren::Elements(elements),
+            truncated,
+        }))
+    }
+}
+
+// A ring buffer, the elements start at `head` and wrap around at the capacity
+struct VecDequePrinter;
+
+impl PrettyPrinter for VecDequePrinter {
+    fn name(&self) -> &str {
+        "VecDeque"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if generic_path(type_name) != "alloc::collections::vec_deque::VecDeque" {
+            return Ok(None);
+        }
+        let Some(element) = inspector.template_parameter(value.ty, "T")? else {
+            return Ok(None);
+        };
+
+        let (address, capacity) = inspector.raw_vec(&inspector.member(value, "buf")?)?;
+        let head = inspector.read_integer(&inspector.member(value, "head")?)?;
+        let length = inspector.read_integer(&inspector.member(value, "len")?)?;
+        let size = inspector.type_size(Some(element))?.unwrap_or(0);
+
+        let elements = (0..length.min(MAX_ELEMENTS as u64))
+            .map(|index| Value {
+                ty: Some(element),
+                location: ValueLocation::Address(
+                    address.wrapping_add((head + index) % capacity.max(1) * size),
+                ),
+            })
+            .collect();
+
+        Ok(Some(PrettyValue {
+            summary: sized_summary(type_name, length),
+            children: PrettyChildren::Elements(elements),
+            truncated: length > MAX_ELEMENTS as u64,
+        }))
+    }
+}
+
+// `HashMap` and `HashSet` of std wrap the ones of hashbrown, which store their entries in a `RawTable`. Its control
+// bytes tell which buckets are in use, the buckets are stored in reverse order in front of them.
+struct HashMapPrinter;
+
+// The control bytes are matched in groups of the SSE2 register width
+const CONTROL_GROUP_WIDTH: u64 = 16;
+
+impl PrettyPrinter for HashMapPrinter {
+    fn name(&self) -> &str {
+        "HashMap"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        let is_set = match generic_path(type_name) {
+            "std::collections::hash::map::HashMap" | "hashbrown::map::HashMap" => false,
+            "std::collections::hash::set::HashSet" | "hashbrown::set::HashSet" => true,
+            _ => return Ok(None),
+        };
+
+        let mut table = value.clone();
+        for name in ["base", "map", "table"] {
+            if let Ok(member) = inspector.member(&table, name) {
+                table = member;
+            }
+        }
+        let Some(entry) = inspector.template_parameter(table.ty, "T")? else {
+            return Ok(None);
+        };
+        let inner = inspector.member(&table, "table")?;
+        let bucket_mask = inspector.read_integer(&inspector.member(&inner, "bucket_mask")?)?;
+        let control = inspector.read_integer(&inspector.member(&inner, "ctrl")?)?;
+        let items = inspector.read_integer(&inspector.member(&inner, "items")?)?;
+        let entry_size = inspector.type_size(Some(entry))?.unwrap_or(0);
+
+        // Uninitialized or corrupted tables are shown as they are
+        let Some(buckets) = bucket_mask
+            .checked_add(1)
+            .filter(|buckets| buckets.is_power_of_two() && items <= *buckets)
+        else {
+            return Ok(None);
+        };
+
+        let mut entries = Vec::new();
+        if items > 0 {
+            // Only enough control groups for the shown entries are read, sparse tables may show fewer of them
+            let length = buckets.min(MAX_ELEMENTS as u64 * CONTROL_GROUP_WIDTH);
+            let Ok(control_bytes) = inspector.debugger.read_memory(control, length as usize) else {
+                return Ok(None);
+            };
+            for (bucket, _) in control_bytes
+                .iter()
+                .enumerate()
+                .filter(|(_, control_byte)| **control_byte & 0x80 == 0)
+                .take(MAX_ELEMENTS)
+            {
+                let address = control.wrapping_sub((bucket as u64 + 1) * entry_size);
+                entries.push(Value {
+                    ty: Some(entry),
+                    location: ValueLocation::Address(address),
+                });
+            }
+        }
+        let truncated = items > entries.len() as u64;
+
+        let children = if is_set {
+            let elements = entries
+                .iter()
+                .map(|entry| inspector.member(entry, "__0"))
+                .collect::<Result<_>>()?;
+            PrettyChildren::Elements(elements)
+        } else {
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    Ok((
+                        inspector.member(entry, "__0")?,
+                        inspector.member(entry, "__1")?,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            PrettyChildren::Entries(entries)
+        };
+
+        Ok(Some(PrettyValue {
+            summary: sized_summary(type_name, items),
+            children,
+            truncated,
+        }))
+    }
+}
+
+// `Box<T>` is a pointer named after the box, shown together with the value it owns
+struct BoxPrinter;
+
+impl PrettyPrinter for BoxPrinter {
+    fn name(&self) -> &str {
+        "Box"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        if generic_path(type_name) != "alloc::boxed::Box" {
+            return Ok(None);
+        }
+        let Type::Pointer { pointee, .. } = inspector.debug_info.strip_typedefs(value.ty)?.1 else {
+            return Ok(None);
+        };
+
+        let address = inspector.read_integer(value)?;
+        let owned = Value {
+            ty: pointee,
+            location: ValueLocation::Address(address),
+        };
+
+        Ok(Some(PrettyValue::new(
+            format!("Box(0x{address:x})"),
+            PrettyChildren::Value(owned),
+        )))
+    }
+}
+
+// `Rc<T>` and `Arc<T>` point to an allocation holding the reference counts and the value. Like `Rc::weak_count`, the
+// weak reference held by all strong references together is not counted.
+struct RcPrinter;
+
+impl PrettyPrinter for RcPrinter {
+    fn name(&self) -> &str {
+        "Rc"
+    }
+
+    fn print(
+        &self,
+        inspector: &Inspector,
+        value: &Value,
+        type_name: &str,
+    ) -> Result<Option<PrettyValue>> {
+        let (name, value_member) = match generic_path(type_name) {
+            "alloc::rc::Rc" => ("Rc", "value"),
+            "alloc::sync::Arc" => ("Arc", "data"),
+            _ => return Ok(None),
+        };
+
+        let allocation = inspector.unwrap_newtype(&inspector.member(value, "ptr")?)?;
+        let strong = inspector.read_integer(&inspector.member(&allocation, "strong")?)?;
+        let weak = inspector.read_integer(&inspector.member(&allocation, "weak")?)?;
+        let shared = inspector.member(&allocation, value_member)?;
+
+        Ok(Some(PrettyValue::new(
+            format!("{name}(strong={strong}, weak={})", weak.saturating_sub(1)),
+            PrettyChildren::Value(shared),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_path_strips_parameters() {
+        assert_eq!(
+            generic_path("alloc::vec::Vec<i32, alloc::alloc::Global>"),
+            "alloc::vec::Vec"
+        );
+        assert_eq!(
+            generic_path("alloc::string::String"),
+            "alloc::string::String"
+        );
+    }
+
+    #[test]
+    fn sized_summary_uses_the_type_name() {
+        assert_eq!(
+            sized_summary("std::collections::hash::map::HashMap<&str, i32>", 2),
+            "HashMap(size=2)"
+        );
+        assert_eq!(sized_summary("Vec<u8>", 0), "Vec(size=0)");
+    }
+
+    #[test]
+    fn quote_str_escapes_like_debug() {
+        let quoted = quote_str(b"a \"b\"\n", false);
+        assert_eq!(quoted.summary, r#""a \"b\"\n""#);
+        assert!(matches!(quoted.children, PrettyChildren::None));
+
+        assert_eq!(quote_str(b"abc", true).summary, r#""abc"..."#);
+        assert_eq!(quote_str(&[0x61, 0xff], false).summary, "\"a\u{fffd}\"");
+    }
+}
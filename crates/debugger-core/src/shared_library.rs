@@ -0,0 +1,296 @@
+use std::path::{Path, PathBuf};
+
+use elf::{
+    ElfBytes,
+    abi::{DT_DEBUG, DT_NULL, PT_DYNAMIC, PT_INTERP},
+    endian::AnyEndian,
+};
+use log::*;
+
+use crate::{
+    Debugger, Result,
+    auxv::{AT_BASE, AuxiliaryVector},
+    module::Module,
+};
+
+// Layout of struct r_debug and struct link_map on x86-64, see <link.h>
+const R_DEBUG_MAP_OFFSET: u64 = 8;
+const R_DEBUG_BRK_OFFSET: u64 = 16;
+const R_DEBUG_STATE_OFFSET: u64 = 24;
+const LINK_MAP_ADDR_OFFSET: u64 = 0;
+const LINK_MAP_NAME_OFFSET: u64 = 8;
+const LINK_MAP_NEXT_OFFSET: u64 = 24;
+
+// r_debug.r_state while the list of loaded objects is not being modified
+const RT_CONSISTENT: u32 = 0;
+
+// libthread_db describes private fields of struct link_map with a {size in bits, number of elements, offset} triple,
+// which glibc exports from libc (libpthread before 2.34)
+const TLS_MODID_DESCRIPTOR_SYMBOL: &str = "_thread_db_link_map_l_tls_modid";
+
+// Guards against walking a corrupted link map forever
+const MAX_LINK_MAP_ENTRIES: usize = 4096;
+const MAX_PATH_LENGTH: usize = 4096;
+
+/// Where the dynamic linker publishes the list of loaded objects and notifies debuggers about changes.
+#[derive(Debug, Default)]
+pub(crate) struct DynamicLinkerState {
+    r_debug_address: Option<u64>,
+    breakpoint_address: Option<u64>,
+}
+
+impl Debugger {
+    pub fn shared_libraries(&self) -> &[Module] {
+        &self.shared_libraries
+    }
+
+    /// Loads the dynamic linker and sets an internal breakpoint on its debugger notification function
+    /// (`_dl_debug_state`), which it calls before and after modifying the list of loaded objects.
+    pub(crate) fn initialize_shared_library_tracking(
+        &mut self,
+        auxv: &AuxiliaryVector,
+    ) -> Result<()> {
+        let Some(interpreter_base) = auxv.get(AT_BASE).filter(|base| *base != 0) else {
+            debug!("No dynamic linker present, executable is statically linked");
+            return Ok(());
+        };
+
+        let Some(interpreter_path) = self.find_interpreter_path(interpreter_base)? else {
+            warn!(
+                "Could not find the path of the dynamic linker, shared libraries are not tracked"
+            );
+            return Ok(());
+        };
+
+        let interpreter_data = std::fs::read(&interpreter_path)?;
+        let interpreter =
+            Module::from_elf_data(interpreter_path, interpreter_data, interpreter_base)?;
+
+        let runtime_address = |name: &str| {
+            interpreter
+                .symbols
+                .find_by_name(name)
+                .map(|symbol| symbol.address + interpreter.load_bias)
+        };
+        let r_debug_address = match runtime_address("_r_debug") {
+            Some(address) => Some(address),
+            None => self.find_r_debug_in_dynamic_section()?,
+        };
+        let breakpoint_address = match runtime_address("_dl_debug_state") {
+            Some(address) => Some(address),
+            None => r_debug_address
+                .map(|r_debug| self.read_u64(r_debug + R_DEBUG_BRK_OFFSET))
+                .transpose()?
+                .filter(|address| *address != 0),
+        };
+
+        self.shared_libraries.push(interpreter);
+        self.dynamic_linker.r_debug_address = r_debug_address;
+
+        match breakpoint_address {
+            Some(address) => {
+                self.insert_breakpoint_instruction(address)?;
+                self.dynamic_linker.breakpoint_address = Some(address);
+            }
+            None => warn!(
+                "Could not find the dynamic linker breakpoint, shared libraries are not tracked"
+            ),
+        }
+
+        // The libraries of a process we attached to are already loaded
+        self.refresh_shared_libraries()?;
+
+        Ok(())
+    }
+
+    fn find_interpreter_path(&self, interpreter_base: u64) -> Result<Option<PathBuf>> {
+        if let Some(pathname) = self
+            .memory_maps
+            .find_by_address(interpreter_base)
+            .and_then(|map| map.file_path())
+        {
+            return Ok(Some(pathname.to_owned()));
+        }
+
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(&self.executable.data)?;
+        let interpreter_segment = elf_bytes
+            .segments()
+            .and_then(|segments| segments.iter().find(|segment| segment.p_type == PT_INTERP));
+
+        let Some(segment) = interpreter_segment else {
+            return Ok(None);
+        };
+
+        let path = elf_bytes.segment_data(&segment)?;
+        let path = path.split(|byte| *byte == 0).next().unwrap_or_default();
+
+        Ok(Some(PathBuf::from(String::from_utf8_lossy(path).as_ref())))
+    }
+
+    // The dynamic linker stores the address of r_debug in the DT_DEBUG entry of the executable's dynamic section
+    fn find_r_debug_in_dynamic_section(&self) -> Result<Option<u64>> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(&self.executable.data)?;
+        let dynamic_segment = elf_bytes
+            .segments()
+            .and_then(|segments| segments.iter().find(|segment| segment.p_type == PT_DYNAMIC));
+
+        let Some(segment) = dynamic_segment else {
+            return Ok(None);
+        };
+
+        let start = segment.p_vaddr + self.executable.load_bias;
+        for entry_address in (start..start + segment.p_memsz).step_by(16) {
+            let tag = self.read_u64(entry_address)? as i64;
+            match tag {
+                DT_NULL => break,
+                DT_DEBUG => {
+                    let value = self.read_u64(entry_address + 8)?;
+                    return Ok(Some(value).filter(|value| *value != 0));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub(crate) fn is_dynamic_linker_breakpoint(&self, address: u64) -> bool {
+        self.dynamic_linker.breakpoint_address == Some(address)
+    }
+
+    /// Walks the link map of the dynamic linker and loads the symbols of all newly loaded libraries. Libraries that
+    /// were unloaded are removed. Returns the paths of the newly loaded libraries.
+    pub fn refresh_shared_libraries(&mut self) -> Result<Vec<PathBuf>> {
+        if self.dynamic_linker.r_debug_address.is_none() {
+            self.dynamic_linker.r_debug_address = self.find_r_debug_in_dynamic_section()?;
+        }
+        let Some(r_debug_address) = self.dynamic_linker.r_debug_address else {
+            return Ok(Vec::new());
+        };
+
+        let state = self.read_u64(r_debug_address + R_DEBUG_STATE_OFFSET)? as u32;
+        if state != RT_CONSISTENT {
+            debug!("Link map is being modified, skipping refresh");
+            return Ok(Vec::new());
+        }
+
+        let mut link_map = self.read_u64(r_debug_address + R_DEBUG_MAP_OFFSET)?;
+        if link_map == 0 {
+            debug!("Link map is not initialized yet, skipping refresh");
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        while link_map != 0 && entries.len() < MAX_LINK_MAP_ENTRIES {
+            let load_bias = self.read_u64(link_map + LINK_MAP_ADDR_OFFSET)?;
+            let name_address = self.read_u64(link_map + LINK_MAP_NAME_OFFSET)?;
+            let name = if name_address != 0 {
+                self.read_c_string(name_address, MAX_PATH_LENGTH)?
+            } else {
+                String::new()
+            };
+
+            entries.push((link_map, load_bias, name));
+            link_map = self.read_u64(link_map + LINK_MAP_NEXT_OFFSET)?;
+        }
+
+        let mut previous_libraries = std::mem::take(&mut self.shared_libraries);
+        let mut loaded = Vec::new();
+
+        // The main executable has an empty name
+        for (_, load_bias, name) in entries.iter().filter(|(_, _, name)| !name.is_empty()) {
+            let load_bias = *load_bias;
+            if let Some(index) = previous_libraries
+                .iter()
+                .position(|library| library.load_bias == load_bias)
+            {
+                self.shared_libraries
+                    .push(previous_libraries.swap_remove(index));
+                continue;
+            }
+
+            // Objects like the vDSO have no backing file
+            let path = Path::new(name);
+            let Ok(data) = std::fs::read(path) else {
+                debug!("Skipping shared object {name}, which is not readable");
+                continue;
+            };
+
+            let library =
+                Module::from_elf_data(path.to_owned(), data, load_bias).map(|mut library| {
+                    library.load_debug_file(&self.debug_file_directories);
+                    library
+                });
+            match library {
+                Ok(library) => {
+                    info!("Loaded shared library {name} at 0x{load_bias:012x}");
+                    loaded.push(library.path.clone());
+                    self.shared_libraries.push(library);
+                }
+                Err(err) => warn!("Failed to load shared library {name}: {err}"),
+            }
+        }
+
+        self.read_tls_module_ids(&entries);
+
+        let unloaded = !previous_libraries.is_empty();
+        for library in previous_libraries {
+            info!("Unloaded shared library {}", library.path.display());
+            self.unresolve_breakpoints_in(library.address_range());
+        }
+
+        if !loaded.is_empty() || unloaded {
+            self.refresh_memory_maps()?;
+        }
+
+        self.resolve_pending_breakpoints()?;
+
+        Ok(loaded)
+    }
+
+    // Reads the IDs of the TLS blocks, which the dynamic linker assigned to the libraries, from their link map entries
+    fn read_tls_module_ids(&mut self, entries: &[(u64, u64, String)]) {
+        let needs_id = |library: &Module| library.tls.is_some() && library.tls_module_id.is_none();
+        if !self.shared_libraries.iter().any(needs_id) {
+            return;
+        }
+        let Some(offset) = self.link_map_tls_modid_offset() else {
+            debug!(
+                "Offset of l_tls_modid in the link map is unknown, TLS of shared libraries is unavailable"
+            );
+            return;
+        };
+
+        for (link_map, load_bias, _) in entries {
+            let Some(index) = self
+                .shared_libraries
+                .iter()
+                .position(|library| library.load_bias == *load_bias && needs_id(library))
+            else {
+                continue;
+            };
+
+            match self.read_u64(link_map + offset) {
+                // Not assigned by the dynamic linker yet
+                Ok(0) => {}
+                Ok(module_id) => self.shared_libraries[index].tls_module_id = Some(module_id),
+                Err(err) => warn!("Failed to read the TLS module ID from the link map: {err}"),
+            }
+        }
+    }
+
+    fn link_map_tls_modid_offset(&self) -> Option<u64> {
+        let address = self.resolve_symbol_runtime_address(TLS_MODID_DESCRIPTOR_SYMBOL)?;
+        let descriptor = self.read_memory(address, 12).ok()?;
+        let field = |index: usize| {
+            u32::from_ne_bytes(
+                descriptor[index * 4..index * 4 + 4]
+                    .try_into()
+                    .expect("slice to be 4 bytes long"),
+            )
+        };
+
+        // l_tls_modid is a single size_t
+        (field(0) == 64 && field(1) == 1).then(|| u64::from(field(2)))
+    }
+}
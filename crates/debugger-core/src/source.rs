@@ -0,0 +1,63 @@
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use log::*;
+
+use crate::{Debugger, Error, Result};
+
+impl Debugger {
+    /// Replaces the prefix `from` of source paths from the debug information with `to`, e.g. for binaries that were
+    /// built in a different directory or sandbox. Substitutions are tried in the order they were added.
+    pub fn add_source_path_substitution(&mut self, from: PathBuf, to: PathBuf) {
+        self.source_path_substitutions.push((from, to));
+    }
+
+    pub fn source_path_substitutions(&self) -> &[(PathBuf, PathBuf)] {
+        &self.source_path_substitutions
+    }
+
+    /// Applies the source path substitutions to a path from the debug information. The path is returned unchanged if
+    /// no substitution applies.
+    pub fn resolve_source_path(&self, path: &Path) -> PathBuf {
+        self.source_path_substitutions
+            .iter()
+            .find_map(|(from, to)| path.strip_prefix(from).ok().map(|rest| to.join(rest)))
+            .unwrap_or_else(|| path.to_owned())
+    }
+
+    /// Finds the full path of a source file of the loaded modules whose path ends with `file`.
+    pub fn find_source_file(&self, file: &Path) -> Option<&Path> {
+        self.modules()
+            .flat_map(|module| module.lines.files())
+            .find(|path| path.ends_with(file))
+            .map(PathBuf::as_path)
+    }
+
+    /// Reads the lines of a source file with their 1-based line numbers. Lines past the end of the file are omitted.
+    pub fn read_source_lines(
+        &self,
+        path: &Path,
+        lines: RangeInclusive<u64>,
+    ) -> Result<Vec<(u64, String)>> {
+        let resolved_path = self.resolve_source_path(path);
+
+        let content = std::fs::read(&resolved_path).map_err(|err| {
+            error!(
+                "Failed to read source file {}: {err}",
+                resolved_path.display()
+            );
+
+            Error::ReadSourceFile(resolved_path.clone())
+        })?;
+
+        Ok(String::from_utf8_lossy(&content)
+            .lines()
+            .zip(1..)
+            .skip_while(|(_, number)| number < lines.start())
+            .take_while(|(_, number)| number <= lines.end())
+            .map(|(line, number)| (number, line.to_owned()))
+            .collect())
+    }
+}
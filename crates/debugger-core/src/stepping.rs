@@ -0,0 +1,391 @@
+use std::{ops::Range, path::PathBuf};
+
+use log::*;
+use nix::sys::{signal::Signal, wait::WaitStatus};
+
+use crate::{ContinueExecutionOutcome, Debugger, Error, Result};
+
+// The longest possible x86-64 instruction, used to recognize the return address pushed by a call
+const MAX_INSTRUCTION_LENGTH: u64 = 15;
+
+// A row of the line table with its runtime address range
+#[derive(Debug)]
+struct LineStepRange {
+    file: PathBuf,
+    line: u64,
+    range: Range<u64>,
+}
+
+impl Debugger {
+    /// Steps until the tracee reaches a different source line, entering called functions that have line
+    /// information and inlined code. Functions without line information are stepped over, and stepping stops once the
+    /// current function returns.
+    pub fn step_line(&mut self) -> Result<ContinueExecutionOutcome> {
+        self.step_source_line(false)
+    }
+
+    /// Steps until the tracee reaches a different source line of the current function, stepping over calls and
+    /// inlined code.
+    pub fn next_line(&mut self) -> Result<ContinueExecutionOutcome> {
+        self.step_source_line(true)
+    }
+
+    // Single steps instructions until a statement of another line starts. Calls are run until they return to the
+    // address after the call, events happening in the meantime (e.g. breakpoints) end the step. Returns
+    // `ContinueExecutionOutcome::Stepped` once a new line is reached.
+    fn step_source_line(&mut self, over_calls: bool) -> Result<ContinueExecutionOutcome> {
+        if self.running {
+            return Err(Error::TraceeRunning);
+        }
+        self.selected_frame = 0;
+
+        let start_pc = self.get_tracee_pc()?;
+        let start = self.line_step_range(start_pc);
+        let start_inlined = self.inlined_subroutine_indices_at(start_pc);
+        if start.is_none() {
+            info!("No line information at 0x{start_pc:012x}, stepping until a line is reached");
+        }
+
+        // The prologue of a function that was stepped into, it is skipped before stopping
+        let mut prologue: Option<Range<u64>> = None;
+
+        loop {
+            let pc = self.get_tracee_pc()?;
+            let sp = self.get_tracee_sp()?;
+            let top_of_stack = self.read_u64(sp)?;
+
+            // Single stepping swaps out the breakpoint instruction, library loads have to be picked up manually
+            if self.is_dynamic_linker_breakpoint(pc) {
+                self.refresh_shared_libraries()?;
+            }
+
+            match self.single_step()? {
+                WaitStatus::Stopped(_pid, Signal::SIGTRAP) => {
+                    if let Some(outcome) = self.take_watchpoint_hit()? {
+                        return Ok(outcome);
+                    }
+                }
+                wait_status => return self.handle_wait_status(wait_status),
+            }
+
+            let mut new_pc = self.get_tracee_pc()?;
+            let new_sp = self.get_tracee_sp()?;
+
+            // A return popped the return address from the stack. Returning from a function with line information
+            // ends the step in the middle of the caller's line.
+            if new_sp > sp && new_pc == top_of_stack {
+                if start.is_some() || self.line_step_range(new_pc).is_some() {
+                    return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+                }
+                continue;
+            }
+
+            if let Some(return_address) = self.call_return_address(pc, sp, new_pc, new_sp)? {
+                match self.line_step_range(new_pc) {
+                    Some(entry) if !over_calls => {
+                        prologue = Some(entry.range);
+                        continue;
+                    }
+                    _ => {
+                        // Resuming would execute the instruction of a breakpoint at the entry of the callee without
+                        // reporting it
+                        if self.is_breakpoint_enabled_at(new_pc)
+                            && self.breakpoint_condition_met(new_pc)
+                        {
+                            info!("Hit Software Breakpoint at {new_pc:08x}");
+                            return Ok(ContinueExecutionOutcome::BreakpointHit(new_pc));
+                        }
+
+                        if let Some(outcome) = self.run_until_return(return_address, new_sp)? {
+                            return Ok(outcome);
+                        }
+                        new_pc = return_address;
+                    }
+                }
+            }
+
+            let entered_inlined = self
+                .inlined_subroutine_indices_at(new_pc)
+                .into_iter()
+                .any(|subroutine| !start_inlined.contains(&subroutine));
+            // Breakpoints in inlined code that is stepped over are reported like those in called functions
+            if over_calls
+                && entered_inlined
+                && self.is_breakpoint_enabled_at(new_pc)
+                && self.breakpoint_condition_met(new_pc)
+            {
+                info!("Hit Software Breakpoint at {new_pc:08x}");
+                return Ok(ContinueExecutionOutcome::BreakpointHit(new_pc));
+            }
+
+            let Some(current) = self.line_step_range(new_pc) else {
+                // Jumped into code without line information, e.g. a tail call
+                if start.is_some() {
+                    return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+                }
+                continue;
+            };
+
+            if !self.is_statement_start(new_pc) {
+                continue;
+            }
+
+            if let Some(prologue) = &prologue {
+                if prologue.contains(&new_pc) {
+                    continue;
+                }
+                return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+            }
+
+            // Entering inlined code is like entering a called function, even if the call was inlined into the same
+            // line, e.g. for `square(a) + square(b)`
+            if entered_inlined {
+                if over_calls {
+                    continue;
+                }
+                return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+            }
+
+            let Some(start) = &start else {
+                return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+            };
+
+            // Another statement of the same line, unless execution jumped back to it, e.g. in a loop
+            let same_line = current.file == start.file && current.line == start.line;
+            if start.range.contains(&new_pc) || (same_line && new_pc > start_pc) {
+                continue;
+            }
+
+            return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+        }
+    }
+
+    /// Runs until the selected frame returns to its caller. Returns [`ContinueExecutionOutcome::Stepped`] with the
+    /// return address, unless the tracee stopped for another reason first. The frame of an inlined function has no
+    /// return, it is finished once the pc leaves the inlined code.
+    pub fn finish(&mut self) -> Result<ContinueExecutionOutcome> {
+        if self.running {
+            return Err(Error::TraceeRunning);
+        }
+
+        let backtrace = self.backtrace()?;
+        let frame = *backtrace
+            .frames
+            .get(self.selected_frame)
+            .ok_or(Error::UnknownFrame(self.selected_frame))?;
+
+        let inlined_ranges = self
+            .inlined_subroutines_at(frame.lookup_address())
+            .get(frame.inline_depth)
+            .map(|(module, subroutine)| {
+                subroutine
+                    .ranges
+                    .iter()
+                    .map(|range| {
+                        range.start.wrapping_add(module.load_bias)
+                            ..range.end.wrapping_add(module.load_bias)
+                    })
+                    .collect::<Vec<_>>()
+            });
+        if let Some(ranges) = inlined_ranges {
+            // Functions called by the inlined code return to it first
+            if frame.index != frame.inline_depth {
+                let sp = frame.registers.sp().ok_or(Error::OutermostFrame)?;
+                let callee_sp = sp.checked_sub(8).ok_or(Error::InvalidStackPointer(sp))?;
+                if let Some(outcome) = self.run_until_return(frame.pc, callee_sp)? {
+                    return Ok(outcome);
+                }
+            }
+
+            return self.step_out_of(&ranges);
+        }
+
+        let Some((caller_pc, caller_sp)) = backtrace
+            .frames
+            .get(self.selected_frame + 1)
+            .and_then(|caller| Some((caller.pc, caller.registers.sp()?)))
+        else {
+            return Err(Error::OutermostFrame);
+        };
+
+        // The return address is stored right below the stack pointer of the caller
+        let callee_sp = caller_sp
+            .checked_sub(8)
+            .ok_or(Error::InvalidStackPointer(caller_sp))?;
+        match self.run_until_return(caller_pc, callee_sp)? {
+            Some(outcome) => Ok(outcome),
+            None => Ok(ContinueExecutionOutcome::Stepped(caller_pc)),
+        }
+    }
+
+    // Single steps until the pc leaves the address ranges of inlined code. Calls are run until they return.
+    fn step_out_of(&mut self, ranges: &[Range<u64>]) -> Result<ContinueExecutionOutcome> {
+        loop {
+            let pc = self.get_tracee_pc()?;
+            let sp = self.get_tracee_sp()?;
+
+            if self.is_dynamic_linker_breakpoint(pc) {
+                self.refresh_shared_libraries()?;
+            }
+
+            match self.single_step()? {
+                WaitStatus::Stopped(_pid, Signal::SIGTRAP) => {
+                    if let Some(outcome) = self.take_watchpoint_hit()? {
+                        return Ok(outcome);
+                    }
+                }
+                wait_status => return self.handle_wait_status(wait_status),
+            }
+
+            let mut new_pc = self.get_tracee_pc()?;
+            let new_sp = self.get_tracee_sp()?;
+            if let Some(return_address) = self.call_return_address(pc, sp, new_pc, new_sp)? {
+                if self.is_breakpoint_enabled_at(new_pc) && self.breakpoint_condition_met(new_pc) {
+                    info!("Hit Software Breakpoint at {new_pc:08x}");
+                    return Ok(ContinueExecutionOutcome::BreakpointHit(new_pc));
+                }
+
+                if let Some(outcome) = self.run_until_return(return_address, new_sp)? {
+                    return Ok(outcome);
+                }
+                new_pc = return_address;
+            }
+
+            if !ranges.iter().any(|range| range.contains(&new_pc)) {
+                return Ok(ContinueExecutionOutcome::Stepped(new_pc));
+            }
+        }
+    }
+
+    // A call pushes the address of the instruction following it and jumps to the callee
+    fn call_return_address(
+        &self,
+        pc: u64,
+        sp: u64,
+        new_pc: u64,
+        new_sp: u64,
+    ) -> Result<Option<u64>> {
+        if new_sp != sp.wrapping_sub(8) {
+            return Ok(None);
+        }
+
+        let return_address = self.read_u64(new_sp)?;
+        let is_call = return_address > pc
+            && return_address - pc <= MAX_INSTRUCTION_LENGTH
+            && new_pc != return_address;
+
+        Ok(is_call.then_some(return_address))
+    }
+
+    // Lets a called function run until it returns to `return_address`. Recursive calls return to the same address
+    // with a deeper stack, so the stack pointer has to be above the one of the callee. Returns the outcome if the
+    // tracee stopped for another reason.
+    fn run_until_return(
+        &mut self,
+        return_address: u64,
+        callee_sp: u64,
+    ) -> Result<Option<ContinueExecutionOutcome>> {
+        let temporary = !self.breakpoints.contains_key(&return_address);
+        if temporary {
+            self.insert_breakpoint_instruction(return_address)?;
+        }
+
+        self.awaited_return = Some((return_address, callee_sp));
+        let outcome = self.wait_for_return(return_address);
+        self.awaited_return = None;
+        let outcome = outcome?;
+
+        if temporary {
+            match outcome {
+                Some(
+                    ContinueExecutionOutcome::ProcessExited(_)
+                    | ContinueExecutionOutcome::ProcessKilled(_),
+                ) => {
+                    self.breakpoints.remove(&return_address);
+                }
+                _ => self.remove_breakpoint_instruction(return_address)?,
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    fn wait_for_return(&mut self, return_address: u64) -> Result<Option<ContinueExecutionOutcome>> {
+        loop {
+            self.resume()?;
+            let outcome = self
+                .wait_event(None)?
+                .expect("blocking wait to always yield an event");
+
+            match outcome {
+                ContinueExecutionOutcome::BreakpointHit(address) if address == return_address => {
+                    if self.is_awaited_return(address)? {
+                        // A user breakpoint at the return address is still reported if its condition is true
+                        let reported = self.is_breakpoint_enabled_at(address)
+                            && self.breakpoint_condition_met(address);
+                        return Ok(reported.then_some(outcome));
+                    }
+                    // A recursive call returned, which only stops for a user breakpoint
+                    if self.is_breakpoint_enabled_at(address) {
+                        return Ok(Some(outcome));
+                    }
+                }
+                ContinueExecutionOutcome::LibrariesLoaded(_) => {}
+                outcome => return Ok(Some(outcome)),
+            }
+        }
+    }
+
+    // Checks whether the tracee stopped at the return address awaited by finish or next, and not in a deeper
+    // recursive call returning to the same address
+    pub(crate) fn is_awaited_return(&self, address: u64) -> Result<bool> {
+        match self.awaited_return {
+            Some((return_address, callee_sp)) if return_address == address => {
+                Ok(self.get_tracee_sp()? > callee_sp)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn line_step_range(&self, address: u64) -> Option<LineStepRange> {
+        self.modules()
+            .filter(|module| module.contains(address))
+            .find_map(|module| {
+                let (row, range) = module
+                    .lines
+                    .find_row_range(address.wrapping_sub(module.load_bias))?;
+
+                Some(LineStepRange {
+                    file: module.lines.files()[row.file].clone(),
+                    line: row.line,
+                    range: range.start.wrapping_add(module.load_bias)
+                        ..range.end.wrapping_add(module.load_bias),
+                })
+            })
+    }
+
+    fn is_statement_start(&self, address: u64) -> bool {
+        self.modules()
+            .filter(|module| module.contains(address))
+            .any(|module| {
+                module
+                    .lines
+                    .is_statement_start(address.wrapping_sub(module.load_bias))
+            })
+    }
+
+    // Identifies the inlined subroutines containing an address by their index in the debug information of their module
+    fn inlined_subroutine_indices_at(&self, address: u64) -> Vec<usize> {
+        self.modules()
+            .enumerate()
+            .filter(|(_, module)| module.contains(address))
+            .filter_map(|(index, module)| Some((module, module.inlined_subroutines(index)?)))
+            .flat_map(|(module, inlined)| {
+                inlined
+                    .at(address.wrapping_sub(module.load_bias))
+                    .iter()
+                    .copied()
+            })
+            .collect()
+    }
+}
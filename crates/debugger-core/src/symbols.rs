@@ -1,63 +1,630 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Range,
+    path::Path,
+};
+
 use crate::Debugger;
 
-use crate::Result;
+use crate::{Error, Result};
+use cpp_demangle::DemangleOptions;
 use elf::{
-    ElfBytes, abi::STT_FUNC, endian::AnyEndian, string_table::StringTable, symbol::SymbolTable,
+    ElfBytes,
+    abi::{
+        R_X86_64_JUMP_SLOT, STB_GLOBAL, STB_WEAK, STT_FILE, STT_FUNC, STT_GNU_IFUNC, STT_OBJECT,
+        STT_SECTION, STT_TLS,
+    },
+    endian::AnyEndian,
+    string_table::StringTable,
+    symbol::SymbolTable,
 };
 
-impl Debugger {
-    fn parse_symbol_table(&self) -> Result<Option<(SymbolTable<'_, AnyEndian>, StringTable)>> {
-        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(&self.executable_data)?;
-        elf_bytes.symbol_table().map_err(Into::into)
-    }
-
-    fn find_symbol_by_name(&self, symbol_name: &str) -> Result<Option<elf::symbol::Symbol>> {
-        self.parse_symbol_table().map(|tables| {
-            tables.and_then(|(symbol_table, string_table)| {
-                symbol_table.iter().find(|x| {
-                    // 0 means no symbol name
-                    if x.st_name == 0 {
-                        return false;
-                    }
-                    // TODO parse errors here should bubble up instead of being ignored
-                    string_table.get(x.st_name as usize).map_err(|_| ()) == Ok(symbol_name)
-                })
+// Every PLT entry on x86-64 is 16 bytes long
+const PLT_ENTRY_SIZE: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Object,
+    Tls,
+    Other,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolKind::Function => write!(f, "function"),
+            SymbolKind::Object => write!(f, "object"),
+            SymbolKind::Tls => write!(f, "tls"),
+            SymbolKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// The table a symbol was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSource {
+    SymbolTable,
+    DynamicSymbolTable,
+    /// The `.symtab` of a separate debug file.
+    DebugFile,
+    /// Synthesized for a PLT stub from the `.rela.plt` relocations.
+    Plt,
+}
+
+impl fmt::Display for SymbolSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolSource::SymbolTable => write!(f, "symtab"),
+            SymbolSource::DynamicSymbolTable => write!(f, "dynsym"),
+            SymbolSource::DebugFile => write!(f, "debug"),
+            SymbolSource::Plt => write!(f, "plt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The raw, possibly mangled, name from the symbol table.
+    pub name: String,
+    /// The demangled name for Rust (legacy and v0) and Itanium C++ symbols, without the Rust hash suffix.
+    pub demangled_name: Option<String>,
+    /// The link-time value of the symbol. For TLS symbols this is the offset into the TLS block of its module.
+    pub address: u64,
+    pub size: u64,
+    pub kind: SymbolKind,
+    pub global: bool,
+    pub source: SymbolSource,
+}
+
+impl Symbol {
+    /// The demangled name if there is one, the raw name otherwise.
+    pub fn display_name(&self) -> &str {
+        self.demangled_name.as_deref().unwrap_or(&self.name)
+    }
+
+    // All names a symbol can be looked up by. C++ symbols can also be found without their parameter list.
+    fn lookup_names(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        if let Some(demangled_name) = &self.demangled_name {
+            names.push(demangled_name.clone());
+        }
+        if rustc_demangle::try_demangle(&self.name).is_err()
+            && let Some(without_parameters) = demangle_cpp(&self.name, false)
+        {
+            names.push(without_parameters);
+        }
+        names.dedup();
+
+        names
+    }
+}
+
+/// Demangles Rust legacy, Rust v0 and Itanium C++ symbol names. Returns `None` for names that are not mangled.
+pub fn demangle(name: &str) -> Option<String> {
+    // Synthesized PLT symbols carry the name of the symbol they jump to
+    if let Some(target) = name.strip_suffix("@plt") {
+        return demangle(target).map(|demangled| format!("{demangled}@plt"));
+    }
+
+    // Rust legacy symbols also follow the Itanium scheme, so Rust has to be tried first
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        // The alternate format omits the hash
+        return Some(format!("{demangled:#}"));
+    }
+
+    demangle_cpp(name, true)
+}
+
+fn demangle_cpp(name: &str, with_parameters: bool) -> Option<String> {
+    if !name.starts_with("_Z") {
+        return None;
+    }
+
+    let symbol = cpp_demangle::Symbol::new(name).ok()?;
+    let options = if with_parameters {
+        DemangleOptions::new()
+    } else {
+        DemangleOptions::new().no_params()
+    };
+
+    symbol.demangle(&options).ok()
+}
+
+/// All named and defined symbols of an ELF file, parsed once when the file is loaded.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, Vec<usize>>,
+    // Indices of function and data symbols, sorted by their address
+    by_address: Vec<usize>,
+    // Disjoint, sorted address ranges and the innermost symbol covering each of them
+    covered_ranges: Vec<(Range<u64>, usize)>,
+}
+
+impl SymbolIndex {
+    /// Reads the symbols from `.symtab`. Symbols from `.dynsym`, which are still present in stripped files, are merged
+    /// in together with synthesized `<name>@plt` symbols for the PLT stubs.
+    pub fn from_elf_data(data: &[u8]) -> Result<Self> {
+        Self::from_elf_files(data, None)
+    }
+
+    /// Like [`SymbolIndex::from_elf_data`], but also merges in the `.symtab` of a separate debug file.
+    pub fn from_elf_files(data: &[u8], debug_data: Option<&[u8]>) -> Result<Self> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+
+        let mut symbols = match elf_bytes.symbol_table()? {
+            Some((symbol_table, string_table)) => {
+                parse_symbols(&symbol_table, &string_table, SymbolSource::SymbolTable)?
+            }
+            None => Vec::new(),
+        };
+
+        if let Some(debug_data) = debug_data {
+            let debug_elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(debug_data)?;
+            if let Some((symbol_table, string_table)) = debug_elf_bytes.symbol_table()? {
+                let debug_symbols =
+                    parse_symbols(&symbol_table, &string_table, SymbolSource::DebugFile)?;
+                merge_symbols(&mut symbols, debug_symbols);
+            }
+        }
+
+        if let Some((dynamic_symbol_table, dynamic_string_table)) =
+            elf_bytes.dynamic_symbol_table()?
+        {
+            let dynamic_symbols = parse_symbols(
+                &dynamic_symbol_table,
+                &dynamic_string_table,
+                SymbolSource::DynamicSymbolTable,
+            )?;
+            merge_symbols(&mut symbols, dynamic_symbols);
+
+            symbols.extend(parse_plt_symbols(
+                &elf_bytes,
+                &dynamic_symbol_table,
+                &dynamic_string_table,
+            )?);
+        }
+
+        Ok(Self::from_symbols(symbols))
+    }
+
+    fn from_symbols(symbols: Vec<Symbol>) -> Self {
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, symbol) in symbols.iter().enumerate() {
+            for name in symbol.lookup_names() {
+                by_name.entry(name).or_default().push(index);
+            }
+        }
+        // Global symbols take precedence over local ones with the same name
+        for indices in by_name.values_mut() {
+            indices.sort_by_key(|index| !symbols[*index].global);
+        }
+
+        let mut by_address: Vec<usize> = symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                matches!(symbol.kind, SymbolKind::Function | SymbolKind::Object)
+                    && symbol.address != 0
+            })
+            .map(|(index, _)| index)
+            .collect();
+        // Lookups pick the last matching symbol. For aliases of the same address, that should be the global one with
+        // the least leading underscores, e.g. puts over _IO_puts.
+        by_address.sort_by_key(|index| {
+            let symbol = &symbols[*index];
+            let leading_underscores = symbol.name.len() - symbol.name.trim_start_matches('_').len();
+            (
+                symbol.address,
+                std::cmp::Reverse(symbol.size),
+                symbol.global,
+                std::cmp::Reverse(leading_underscores),
+            )
+        });
+
+        let covered_ranges = covered_ranges(&symbols, &by_address);
+
+        Self {
+            symbols,
+            by_name,
+            by_address,
+            covered_ranges,
+        }
+    }
+
+    /// Finds a symbol by its raw or demangled name.
+    pub fn find_by_name(&self, name: &str) -> Option<&Symbol> {
+        self.by_name
+            .get(name)
+            .and_then(|indices| indices.first())
+            .map(|index| &self.symbols[*index])
+    }
+
+    /// Finds the symbol containing a link-time address.
+    pub fn find_by_address(&self, address: u64) -> Option<&Symbol> {
+        let end = self
+            .by_address
+            .partition_point(|index| self.symbols[*index].address <= address);
+
+        // A symbol starting at the address always contains it, symbols without a size only match exactly
+        if let Some(index) = end.checked_sub(1).map(|end| self.by_address[end])
+            && self.symbols[index].address == address
+        {
+            return Some(&self.symbols[index]);
+        }
+
+        let range_index = self
+            .covered_ranges
+            .partition_point(|(range, _)| range.end <= address);
+        self.covered_ranges
+            .get(range_index)
+            .filter(|(range, _)| range.contains(&address))
+            .map(|(_, index)| &self.symbols[*index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Symbol> {
+        self.iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Function)
+    }
+
+    /// Global and static variables, including thread-local ones.
+    pub fn variables(&self) -> impl Iterator<Item = &Symbol> {
+        self.iter()
+            .filter(|symbol| matches!(symbol.kind, SymbolKind::Object | SymbolKind::Tls))
+    }
+}
+
+// Splits the address space covered by symbols into disjoint ranges. Symbols can be nested, e.g. a function and a
+// local label inside it, and each range belongs to the last symbol in address order that covers it, which is the
+// innermost one.
+fn covered_ranges(symbols: &[Symbol], by_address: &[usize]) -> Vec<(Range<u64>, usize)> {
+    let mut ranges = Vec::new();
+    // Symbols covering the current address and their end, the innermost on top. Symbols that ended are removed once
+    // they get on top.
+    let mut open: Vec<(usize, u64)> = Vec::new();
+    let mut cursor = 0;
+
+    let mut advance_to = |open: &mut Vec<(usize, u64)>, cursor: &mut u64, limit: u64| {
+        while *cursor < limit {
+            while open.last().is_some_and(|(_, end)| *end <= *cursor) {
+                open.pop();
+            }
+            let Some(&(index, end)) = open.last() else {
+                *cursor = limit;
+                break;
+            };
+
+            let range_end = end.min(limit);
+            ranges.push((*cursor..range_end, index));
+            *cursor = range_end;
+        }
+    };
+
+    for &index in by_address {
+        let symbol = &symbols[index];
+        // Malformed sizes can reach past the end of the address space
+        let Some(end) = symbol.address.checked_add(symbol.size) else {
+            continue;
+        };
+        if symbol.size == 0 {
+            continue;
+        }
+
+        advance_to(&mut open, &mut cursor, symbol.address);
+        open.push((index, end));
+    }
+    advance_to(&mut open, &mut cursor, u64::MAX);
+
+    ranges
+}
+
+// Adds the symbols, which are not known under the same name and address yet
+fn merge_symbols(symbols: &mut Vec<Symbol>, additional_symbols: Vec<Symbol>) {
+    let known: HashSet<(String, u64)> = symbols
+        .iter()
+        .map(|symbol| (symbol.name.clone(), symbol.address))
+        .collect();
+
+    symbols.extend(
+        additional_symbols
+            .into_iter()
+            .filter(|symbol| !known.contains(&(symbol.name.clone(), symbol.address))),
+    );
+}
+
+fn parse_symbols(
+    symbol_table: &SymbolTable<'_, AnyEndian>,
+    string_table: &StringTable<'_>,
+    source: SymbolSource,
+) -> Result<Vec<Symbol>> {
+    symbol_table
+        .iter()
+        .filter(|symbol| {
+            // 0 means no symbol name
+            symbol.st_name != 0
+                && !symbol.is_undefined()
+                && !matches!(symbol.st_symtype(), STT_SECTION | STT_FILE)
+        })
+        .map(|symbol| {
+            let kind = match symbol.st_symtype() {
+                // Breakpoints on indirect functions stop in their resolver
+                STT_FUNC | STT_GNU_IFUNC => SymbolKind::Function,
+                STT_OBJECT => SymbolKind::Object,
+                STT_TLS => SymbolKind::Tls,
+                _ => SymbolKind::Other,
+            };
+
+            let name = string_table.get(symbol.st_name as usize)?;
+
+            Ok(Symbol {
+                name: name.to_owned(),
+                demangled_name: demangle(name),
+                address: symbol.st_value,
+                size: symbol.st_size,
+                kind,
+                global: matches!(symbol.st_bind(), STB_GLOBAL | STB_WEAK),
+                source,
+            })
+        })
+        .collect()
+}
+
+// The n-th relocation in .rela.plt belongs to the n-th PLT stub. With IBT enabled, the stubs called by the program
+// live in .plt.sec. Otherwise they follow the special first entry of .plt, which invokes the dynamic linker.
+fn parse_plt_symbols(
+    elf_bytes: &ElfBytes<'_, AnyEndian>,
+    dynamic_symbol_table: &SymbolTable<'_, AnyEndian>,
+    dynamic_string_table: &StringTable<'_>,
+) -> Result<Vec<Symbol>> {
+    let Some(rela_plt) = elf_bytes.section_header_by_name(".rela.plt")? else {
+        return Ok(Vec::new());
+    };
+
+    let first_stub_address = if let Some(plt_sec) = elf_bytes.section_header_by_name(".plt.sec")? {
+        plt_sec.sh_addr
+    } else if let Some(plt) = elf_bytes.section_header_by_name(".plt")? {
+        plt.sh_addr + PLT_ENTRY_SIZE
+    } else {
+        return Ok(Vec::new());
+    };
+
+    elf_bytes
+        .section_data_as_relas(&rela_plt)?
+        .enumerate()
+        .filter(|(_, rela)| rela.r_type == R_X86_64_JUMP_SLOT && rela.r_sym != 0)
+        .map(|(index, rela)| {
+            let symbol = dynamic_symbol_table.get(rela.r_sym as usize)?;
+            let name = dynamic_string_table.get(symbol.st_name as usize)?;
+
+            let name = format!("{name}@plt");
+
+            Ok(Symbol {
+                demangled_name: demangle(&name),
+                name,
+                address: first_stub_address + index as u64 * PLT_ENTRY_SIZE,
+                size: PLT_ENTRY_SIZE,
+                kind: SymbolKind::Function,
+                global: false,
+                source: SymbolSource::Plt,
             })
         })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SymbolizedAddress<'a> {
+    pub name: &'a str,
+    pub offset: u64,
+    pub module: &'a Path,
+}
+
+impl fmt::Display for SymbolizedAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.offset == 0 {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}+{:#x}", self.name, self.offset)
+        }
+    }
+}
+
+impl Debugger {
+    /// Finds the symbol containing a runtime address of the tracee.
+    pub fn symbolize(&self, address: u64) -> Option<SymbolizedAddress<'_>> {
+        self.modules()
+            .filter(|module| module.contains(address))
+            .find_map(|module| {
+                let link_address = address.wrapping_sub(module.load_bias);
+
+                module
+                    .symbols
+                    .find_by_address(link_address)
+                    .map(|symbol| SymbolizedAddress {
+                        name: self.symbol_name(symbol),
+                        offset: link_address - symbol.address,
+                        module: &module.path,
+                    })
+            })
+    }
+
+    /// Shows raw symbol names instead of demangled ones, e.g. in [`Debugger::symbolize`].
+    pub fn set_show_mangled_names(&mut self, enabled: bool) {
+        self.show_mangled_names = enabled;
+    }
+
+    pub fn show_mangled_names(&self) -> bool {
+        self.show_mangled_names
+    }
+
+    /// The name of a symbol as it should be presented, depending on [`Debugger::set_show_mangled_names`].
+    pub fn symbol_name<'a>(&self, symbol: &'a Symbol) -> &'a str {
+        if self.show_mangled_names {
+            &symbol.name
+        } else {
+            symbol.display_name()
+        }
+    }
+
+    pub fn find_symbol_by_name(&self, symbol_name: &str) -> Option<&Symbol> {
+        self.executable.symbols.find_by_name(symbol_name)
     }
 
-    pub fn find_symbol_address_by_name(&self, symbol_name: &str) -> Result<Option<u64>> {
+    pub fn find_symbol_address_by_name(&self, symbol_name: &str) -> Option<u64> {
         self.find_symbol_by_name(symbol_name)
-            .map(|symbol| symbol.map(|symbol| symbol.st_value))
-    }
-
-    pub fn list_function_symbols(&self) -> Result<Vec<FunctionSymbol>> {
-        self.parse_symbol_table().and_then(|tables| {
-            tables.map_or_else(
-                || Ok(Vec::new()),
-                |(symbol_table, string_table)| {
-                    symbol_table
-                        .iter()
-                        .filter(|symbol| symbol.st_symtype() == STT_FUNC)
-                        .map(|symbol| {
-                            let name = if symbol.st_name > 0 {
-                                Some(string_table.get(symbol.st_name as usize)?)
-                            } else {
-                                None
-                            };
-                            Ok(FunctionSymbol {
-                                name,
-                                offset: symbol.st_value,
-                            })
-                        })
-                        .collect()
-                },
-            )
+            .map(|symbol| symbol.address)
+    }
+
+    /// Resolves a symbol to the address it is loaded at in the tracee.
+    pub fn resolve_symbol_runtime_address(&self, symbol_name: &str) -> Option<u64> {
+        self.modules().find_map(|module| {
+            module
+                .symbols
+                .find_by_name(symbol_name)
+                .filter(|symbol| symbol.kind != SymbolKind::Tls)
+                .map(|symbol| symbol.address.wrapping_add(module.load_bias))
         })
     }
+
+    /// Finds a variable in all loaded modules and returns it together with its runtime address. Thread-local variables
+    /// are resolved for the thread of the tracee.
+    pub fn resolve_variable(&self, variable_name: &str) -> Result<(u64, &Symbol)> {
+        let (module, symbol) = self
+            .modules()
+            .find_map(|module| {
+                module
+                    .symbols
+                    .find_by_name(variable_name)
+                    .filter(|symbol| matches!(symbol.kind, SymbolKind::Object | SymbolKind::Tls))
+                    .map(|symbol| (module, symbol))
+            })
+            .ok_or_else(|| Error::UnknownVariable(variable_name.to_owned()))?;
+
+        let address = match symbol.kind {
+            SymbolKind::Tls => self.thread_local_address(module, symbol)?,
+            _ => symbol.address.wrapping_add(module.load_bias),
+        };
+
+        Ok((address, symbol))
+    }
+
+    pub fn list_function_symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.executable.symbols.functions()
+    }
+
+    pub fn list_variable_symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.executable.symbols.variables()
+    }
 }
 
-pub struct FunctionSymbol<'a> {
-    pub name: Option<&'a str>,
-    pub offset: u64,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, address: u64, size: u64) -> Symbol {
+        Symbol {
+            name: name.to_owned(),
+            demangled_name: None,
+            address,
+            size,
+            kind: SymbolKind::Function,
+            global: true,
+            source: SymbolSource::SymbolTable,
+        }
+    }
+
+    fn name_at(index: &SymbolIndex, address: u64) -> Option<&str> {
+        index
+            .find_by_address(address)
+            .map(|symbol| symbol.name.as_str())
+    }
+
+    #[test]
+    fn find_by_address_prefers_innermost_symbol() {
+        let index = SymbolIndex::from_symbols(vec![
+            symbol("outer", 0x1000, 0x100),
+            symbol("inner", 0x1010, 0x10),
+            symbol("label", 0x1040, 0),
+            symbol("next", 0x1200, 0x20),
+        ]);
+
+        assert_eq!(name_at(&index, 0x1000), Some("outer"));
+        assert_eq!(name_at(&index, 0x1018), Some("inner"));
+        // The enclosing symbol covers the address again after the nested one ended
+        assert_eq!(name_at(&index, 0x1020), Some("outer"));
+        assert_eq!(name_at(&index, 0x1040), Some("label"));
+        assert_eq!(name_at(&index, 0x1041), Some("outer"));
+        assert_eq!(name_at(&index, 0x1100), None);
+        assert_eq!(name_at(&index, 0x121f), Some("next"));
+        assert_eq!(name_at(&index, 0x1220), None);
+        assert_eq!(name_at(&index, 0xfff), None);
+    }
+
+    #[test]
+    fn find_by_address_prefers_global_alias() {
+        let mut local = symbol("_IO_puts", 0x2000, 0x40);
+        local.global = false;
+        let index = SymbolIndex::from_symbols(vec![local, symbol("puts", 0x2000, 0x40)]);
+
+        assert_eq!(name_at(&index, 0x2000), Some("puts"));
+        assert_eq!(name_at(&index, 0x2010), Some("puts"));
+    }
+
+    #[test]
+    fn demangles_rust_and_cpp_names() {
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h0123456789abcdefE").as_deref(),
+            Some("core::fmt::write")
+        );
+        assert_eq!(
+            demangle("_RNvCs1234_7mycrate3foo").as_deref(),
+            Some("mycrate::foo")
+        );
+        assert_eq!(demangle("_ZN3foo3barEi").as_deref(), Some("foo::bar(int)"));
+        assert_eq!(
+            demangle("_ZN3foo3barEi@plt").as_deref(),
+            Some("foo::bar(int)@plt")
+        );
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("puts@plt"), None);
+    }
+
+    #[test]
+    fn find_by_name_accepts_demangled_names() {
+        let mut cpp = symbol("_ZN3foo3barEi", 0x1000, 0x10);
+        cpp.demangled_name = demangle(&cpp.name);
+        let mut rust = symbol("_ZN4core3fmt5write17h0123456789abcdefE", 0x2000, 0x10);
+        rust.demangled_name = demangle(&rust.name);
+        let index = SymbolIndex::from_symbols(vec![cpp, rust]);
+
+        for name in ["_ZN3foo3barEi", "foo::bar(int)", "foo::bar"] {
+            assert_eq!(
+                index.find_by_name(name).map(|symbol| symbol.address),
+                Some(0x1000)
+            );
+        }
+        assert_eq!(
+            index
+                .find_by_name("core::fmt::write")
+                .map(|symbol| symbol.address),
+            Some(0x2000)
+        );
+        assert!(index.find_by_name("foo::bar(long)").is_none());
+    }
+
+    #[test]
+    fn find_by_address_skips_overflowing_symbol() {
+        let index = SymbolIndex::from_symbols(vec![
+            symbol("broken", 0xffff_ffff_ffff_f000, 0x2000),
+            symbol("last", 0xffff_ffff_ffff_0000, 0x100),
+        ]);
+
+        assert_eq!(name_at(&index, 0xffff_ffff_ffff_0010), Some("last"));
+        assert_eq!(name_at(&index, 0xffff_ffff_ffff_f010), None);
+    }
 }
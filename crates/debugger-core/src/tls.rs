@@ -0,0 +1,72 @@
+use log::*;
+use nix::sys::ptrace;
+
+use crate::{Debugger, Error, Result, module::Module, symbols::Symbol};
+
+// The thread control block starts with a pointer to itself, followed by a pointer to the dynamic thread vector (DTV)
+const TCB_DTV_OFFSET: u64 = 8;
+// Every DTV entry holds the address of a TLS block and a flag whether it is part of the static TLS area
+const DTV_ENTRY_SIZE: u64 = 16;
+// Marks a DTV entry whose TLS block is only allocated on the first access of the thread
+const TLS_DTV_UNALLOCATED: u64 = u64::MAX;
+
+impl Debugger {
+    /// The thread pointer of the tracee, which is stored in the `fs` base register on x86-64.
+    pub fn thread_pointer(&self) -> Result<u64> {
+        let regs = ptrace::getregs(self.tracee_pid).map_err(|errno| {
+            error!("Could not read registers of tracee: {errno}");
+
+            Error::ReadRegisters
+        })?;
+
+        Ok(regs.fs_base)
+    }
+
+    /// Computes the runtime address of a thread-local variable for the thread of the tracee.
+    pub fn thread_local_address(&self, module: &Module, symbol: &Symbol) -> Result<u64> {
+        self.thread_local_offset_address(module, symbol.address)
+    }
+
+    // Computes the runtime address of an offset into the TLS block of a module
+    pub(crate) fn thread_local_offset_address(&self, module: &Module, offset: u64) -> Result<u64> {
+        let Some(tls) = module.tls else {
+            return Err(Error::ThreadLocalStorageUnavailable);
+        };
+
+        // The thread pointer is set up by the dynamic linker or libc, before that there is no TLS
+        let thread_pointer = self.thread_pointer()?;
+        if thread_pointer == 0 {
+            return Err(Error::ThreadLocalStorageUnavailable);
+        }
+
+        // Static TLS model: the block of the executable is placed right below the thread pointer
+        if std::ptr::eq(module, &self.executable) {
+            let first_byte = tls.virtual_address.wrapping_neg() & (tls.alignment - 1);
+            let block_offset = tls
+                .memory_size
+                .saturating_sub(first_byte)
+                .next_multiple_of(tls.alignment)
+                + first_byte;
+
+            return Ok(thread_pointer - block_offset + offset);
+        }
+
+        // Shared libraries may also be in the static TLS area, but their offset is only known to the dynamic linker.
+        // The DTV points to the TLS block of every module regardless.
+        let module_id = module
+            .tls_module_id
+            .ok_or(Error::ThreadLocalStorageUnavailable)?;
+        let dtv = self.read_u64(thread_pointer + TCB_DTV_OFFSET)?;
+        let block = self.read_u64(dtv + module_id * DTV_ENTRY_SIZE)?;
+
+        if block == TLS_DTV_UNALLOCATED {
+            info!(
+                "TLS block of {} is not allocated in this thread yet",
+                module.path.display()
+            );
+            return Err(Error::ThreadLocalStorageUnavailable);
+        }
+
+        Ok(block + offset)
+    }
+}
@@ -0,0 +1,1039 @@
+use gimli::{AttributeValue, DwLang, EntriesTreeNode, Operation, UnitOffset};
+
+use crate::{
+    Error, Result,
+    debug_info::{DebugInfo, DebugInfoCache, DwarfEntry, DwarfUnit, constant_value},
+    dwarf::SharedDwarfReader,
+};
+
+// Guards against cyclic type references in malformed debug information
+const MAX_TYPE_DEPTH: usize = 32;
+
+/// Identifies a type in the `.debug_info` section of a module, or a type made up while evaluating an expression, e.g.
+/// for a cast to `u64*`. Made-up types are only known to the [`Inspector`] that evaluated the expression.
+///
+/// [`Inspector`]: crate::variables::Inspector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeId(pub(crate) TypeSource);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TypeSource {
+    Dwarf {
+        // Index into `Debugger::modules`
+        module: usize,
+        offset: gimli::DebugInfoOffset,
+    },
+    // Index into the made-up types of a `DebugInfoCache`
+    Synthetic(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Boolean,
+    Float,
+    Signed,
+    Unsigned,
+    SignedChar,
+    UnsignedChar,
+    /// A Unicode code point, e.g. the `char` of Rust.
+    Utf,
+    Other,
+}
+
+impl BaseEncoding {
+    pub fn is_signed(&self) -> bool {
+        matches!(self, BaseEncoding::Signed | BaseEncoding::SignedChar)
+    }
+
+    pub fn is_char(&self) -> bool {
+        matches!(self, BaseEncoding::SignedChar | BaseEncoding::UnsignedChar)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerKind {
+    Pointer,
+    Reference,
+    RvalueReference,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositeKind {
+    Struct,
+    Class,
+    Union,
+}
+
+impl CompositeKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            CompositeKind::Struct => "struct",
+            CompositeKind::Class => "class",
+            CompositeKind::Union => "union",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Qualifier {
+    Const,
+    Volatile,
+    Restrict,
+    Atomic,
+}
+
+impl Qualifier {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Qualifier::Const => "const",
+            Qualifier::Volatile => "volatile",
+            Qualifier::Restrict => "restrict",
+            Qualifier::Atomic => "_Atomic",
+        }
+    }
+}
+
+/// A bit field member, which does not start at a byte boundary or does not fill its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    /// The offset of the first bit from the start of the containing struct.
+    pub bit_offset: u64,
+    pub bit_size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// Anonymous structs and unions embedded into another one have no name.
+    pub name: Option<String>,
+    pub ty: Option<TypeId>,
+    /// The offset in bytes from the start of the containing struct.
+    pub offset: u64,
+    pub bit_field: Option<BitField>,
+    /// Whether the member is the subobject of a base class.
+    pub is_base: bool,
+}
+
+/// A generic parameter of a type, e.g. the `T` of `Vec<T>`.
+#[derive(Debug, Clone)]
+pub struct TemplateParameter {
+    pub name: String,
+    pub ty: Option<TypeId>,
+}
+
+/// Alternative members sharing the storage of a struct, e.g. the variants of a Rust enum. The value of the
+/// discriminant member decides which variant is active.
+#[derive(Debug, Clone)]
+pub struct VariantPart {
+    pub discriminant: Option<Member>,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    /// The value of the discriminant selecting the variant, `None` for the variant used for all other values.
+    pub discriminant: Option<i128>,
+    /// Rust has a single member named after the variant, whose type holds the fields of the variant.
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Enumerator {
+    pub name: String,
+    pub value: i128,
+}
+
+/// A type from the DWARF debug information. Other types are referenced by their [`TypeId`], so recursive types can be
+/// represented, `None` references stand for `void`.
+#[derive(Debug, Clone)]
+pub enum Type {
+    Void,
+    Base {
+        name: String,
+        encoding: BaseEncoding,
+        size: u64,
+    },
+    Pointer {
+        kind: PointerKind,
+        /// Some languages, e.g. Rust, name their pointer types.
+        name: Option<String>,
+        pointee: Option<TypeId>,
+        size: u64,
+    },
+    Array {
+        element: Option<TypeId>,
+        /// The number of elements of every dimension, unknown for flexible array members.
+        dimensions: Vec<Option<u64>>,
+    },
+    Composite {
+        kind: CompositeKind,
+        name: Option<String>,
+        size: u64,
+        members: Vec<Member>,
+        template_parameters: Vec<TemplateParameter>,
+        variant_part: Option<VariantPart>,
+    },
+    Enum {
+        name: Option<String>,
+        size: u64,
+        underlying: Option<TypeId>,
+        enumerators: Vec<Enumerator>,
+    },
+    Typedef {
+        name: String,
+        target: Option<TypeId>,
+    },
+    Qualified {
+        qualifier: Qualifier,
+        target: Option<TypeId>,
+    },
+    Function {
+        return_type: Option<TypeId>,
+        parameters: Vec<Option<TypeId>>,
+        variadic: bool,
+    },
+    /// A type that cannot be inspected, named by its DWARF tag.
+    Unsupported(String),
+}
+
+impl<'a> DebugInfo<'a> {
+    fn parse_type(&self, offset: gimli::DebugInfoOffset) -> Result<Type> {
+        let Some((unit, offset)) = self.unit_at(offset) else {
+            return Ok(Type::Unsupported("invalid type reference".to_owned()));
+        };
+        let entry = unit.entry(offset)?;
+
+        let name = self.name(unit, &entry)?;
+        let size = entry
+            .attr_value(gimli::DW_AT_byte_size)
+            .and_then(|size| size.udata_value());
+        let target = self.type_of(unit, &entry)?;
+
+        let parsed = match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                let encoding = match entry.attr_value(gimli::DW_AT_encoding) {
+                    Some(AttributeValue::Encoding(encoding)) => encoding,
+                    _ => gimli::DW_ATE_signed,
+                };
+                let encoding = match encoding {
+                    gimli::DW_ATE_boolean => BaseEncoding::Boolean,
+                    gimli::DW_ATE_float => BaseEncoding::Float,
+                    gimli::DW_ATE_signed => BaseEncoding::Signed,
+                    gimli::DW_ATE_unsigned => BaseEncoding::Unsigned,
+                    gimli::DW_ATE_signed_char => BaseEncoding::SignedChar,
+                    gimli::DW_ATE_unsigned_char => BaseEncoding::UnsignedChar,
+                    gimli::DW_ATE_UTF => BaseEncoding::Utf,
+                    _ => BaseEncoding::Other,
+                };
+
+                Type::Base {
+                    name: name.unwrap_or_default(),
+                    encoding,
+                    size: size.unwrap_or(0),
+                }
+            }
+            gimli::DW_TAG_unspecified_type if name.as_deref().is_none_or(|name| name == "void") => {
+                Type::Void
+            }
+            tag @ (gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type) => Type::Pointer {
+                kind: match tag {
+                    gimli::DW_TAG_pointer_type => PointerKind::Pointer,
+                    gimli::DW_TAG_reference_type => PointerKind::Reference,
+                    _ => PointerKind::RvalueReference,
+                },
+                name,
+                pointee: target,
+                size: size.unwrap_or(u64::from(unit.encoding().address_size)),
+            },
+            gimli::DW_TAG_array_type => Type::Array {
+                element: target,
+                dimensions: self.array_dimensions(unit, offset)?,
+            },
+            tag @ (gimli::DW_TAG_structure_type
+            | gimli::DW_TAG_class_type
+            | gimli::DW_TAG_union_type) => Type::Composite {
+                kind: match tag {
+                    gimli::DW_TAG_structure_type => CompositeKind::Struct,
+                    gimli::DW_TAG_class_type => CompositeKind::Class,
+                    _ => CompositeKind::Union,
+                },
+                name,
+                size: size.unwrap_or(0),
+                members: self.members(unit, offset)?,
+                template_parameters: self.template_parameters(unit, offset)?,
+                variant_part: self.variant_part(unit, offset)?,
+            },
+            gimli::DW_TAG_enumeration_type => Type::Enum {
+                name,
+                size: size.unwrap_or(4),
+                underlying: target,
+                enumerators: self.enumerators(unit, offset)?,
+            },
+            gimli::DW_TAG_typedef => Type::Typedef {
+                name: name.unwrap_or_default(),
+                target,
+            },
+            tag @ (gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_restrict_type
+            | gimli::DW_TAG_atomic_type) => Type::Qualified {
+                qualifier: match tag {
+                    gimli::DW_TAG_const_type => Qualifier::Const,
+                    gimli::DW_TAG_volatile_type => Qualifier::Volatile,
+                    gimli::DW_TAG_restrict_type => Qualifier::Restrict,
+                    _ => Qualifier::Atomic,
+                },
+                target,
+            },
+            gimli::DW_TAG_subroutine_type => {
+                let (parameters, variadic) = self.parameters(unit, offset)?;
+                Type::Function {
+                    return_type: target,
+                    parameters,
+                    variadic,
+                }
+            }
+            tag => Type::Unsupported(match name {
+                Some(name) => name,
+                None => tag.static_string().unwrap_or("unknown type").to_owned(),
+            }),
+        };
+
+        Ok(parsed)
+    }
+
+    // The element counts of the `DW_TAG_subrange_type` children, one for each dimension
+    fn array_dimensions(
+        &self,
+        unit: &DwarfUnit,
+        offset: gimli::UnitOffset,
+    ) -> Result<Vec<Option<u64>>> {
+        let mut dimensions = Vec::new();
+
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_subrange_type {
+                continue;
+            }
+
+            let count = entry
+                .attr_value(gimli::DW_AT_count)
+                .and_then(|count| constant_value(&count))
+                .or_else(|| {
+                    let upper_bound = constant_value(&entry.attr_value(gimli::DW_AT_upper_bound)?)?;
+                    let lower_bound = entry
+                        .attr_value(gimli::DW_AT_lower_bound)
+                        .and_then(|lower_bound| constant_value(&lower_bound))
+                        .unwrap_or(0);
+                    Some(upper_bound - lower_bound + 1)
+                });
+            dimensions.push(count.and_then(|count| u64::try_from(count).ok()));
+        }
+
+        Ok(dimensions)
+    }
+
+    fn members(&self, unit: &DwarfUnit, offset: gimli::UnitOffset) -> Result<Vec<Member>> {
+        let mut members = Vec::new();
+
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            let is_base = match entry.tag() {
+                gimli::DW_TAG_member => false,
+                gimli::DW_TAG_inheritance => true,
+                _ => continue,
+            };
+            // Static members are declared in the struct, but stored elsewhere
+            if entry.has_attr(gimli::DW_AT_external) || entry.has_attr(gimli::DW_AT_declaration) {
+                continue;
+            }
+
+            members.push(self.member(unit, entry, is_base)?);
+        }
+
+        Ok(members)
+    }
+
+    fn member(&self, unit: &DwarfUnit, entry: &DwarfEntry, is_base: bool) -> Result<Member> {
+        let mut offset = entry
+            .attr_value(gimli::DW_AT_data_member_location)
+            .and_then(|location| member_location(unit, location))
+            .unwrap_or(0);
+        let bit_field = self.bit_field(entry, offset);
+        if let Some(bit_field) = bit_field {
+            offset = bit_field.bit_offset / 8;
+        }
+
+        Ok(Member {
+            name: self.name(unit, entry)?,
+            ty: self.type_of(unit, entry)?,
+            offset,
+            bit_field,
+            is_base,
+        })
+    }
+
+    fn template_parameters(
+        &self,
+        unit: &DwarfUnit,
+        offset: gimli::UnitOffset,
+    ) -> Result<Vec<TemplateParameter>> {
+        let mut parameters = Vec::new();
+
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_template_type_parameter {
+                continue;
+            }
+
+            parameters.push(TemplateParameter {
+                name: self.name(unit, entry)?.unwrap_or_default(),
+                ty: self.type_of(unit, entry)?,
+            });
+        }
+
+        Ok(parameters)
+    }
+
+    // The `DW_TAG_variant_part` of a struct, which Rust uses for enums with fields
+    fn variant_part(
+        &self,
+        unit: &DwarfUnit,
+        offset: gimli::UnitOffset,
+    ) -> Result<Option<VariantPart>> {
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            if child.entry().tag() != gimli::DW_TAG_variant_part {
+                continue;
+            }
+
+            let discriminant = match child.entry().attr_value(gimli::DW_AT_discr) {
+                Some(AttributeValue::UnitRef(offset)) => {
+                    Some(self.member(unit, &unit.entry(offset)?, false)?)
+                }
+                _ => None,
+            };
+
+            let mut variants = Vec::new();
+            let mut variant_children = child.children();
+            while let Some(variant) = variant_children.next()? {
+                let entry = variant.entry();
+                if entry.tag() != gimli::DW_TAG_variant {
+                    continue;
+                }
+
+                variants.push(Variant {
+                    discriminant: entry
+                        .attr_value(gimli::DW_AT_discr_value)
+                        .and_then(|value| constant_value(&value)),
+                    members: self.members(unit, entry.offset())?,
+                });
+            }
+
+            return Ok(Some(VariantPart {
+                discriminant,
+                variants,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    fn bit_field(&self, entry: &DwarfEntry, byte_offset: u64) -> Option<BitField> {
+        let bit_size = entry.attr_value(gimli::DW_AT_bit_size)?.udata_value()?;
+
+        if let Some(bit_offset) = entry
+            .attr_value(gimli::DW_AT_data_bit_offset)
+            .and_then(|bit_offset| bit_offset.udata_value())
+        {
+            return Some(BitField {
+                bit_offset,
+                bit_size,
+            });
+        }
+
+        // DWARF 2 and 3 count the bits from the most significant bit of the storage unit
+        let storage_size = entry.attr_value(gimli::DW_AT_byte_size)?.udata_value()?;
+        let bit_offset = entry.attr_value(gimli::DW_AT_bit_offset)?.udata_value()?;
+        Some(BitField {
+            bit_offset: byte_offset * 8 + (storage_size * 8).checked_sub(bit_offset + bit_size)?,
+            bit_size,
+        })
+    }
+
+    fn enumerators(&self, unit: &DwarfUnit, offset: gimli::UnitOffset) -> Result<Vec<Enumerator>> {
+        let mut enumerators = Vec::new();
+
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_enumerator {
+                continue;
+            }
+
+            let value = entry
+                .attr_value(gimli::DW_AT_const_value)
+                .and_then(|value| constant_value(&value));
+            if let (Some(name), Some(value)) = (self.name(unit, entry)?, value) {
+                enumerators.push(Enumerator { name, value });
+            }
+        }
+
+        Ok(enumerators)
+    }
+
+    fn parameters(
+        &self,
+        unit: &DwarfUnit,
+        offset: gimli::UnitOffset,
+    ) -> Result<(Vec<Option<TypeId>>, bool)> {
+        let mut parameters = Vec::new();
+        let mut variadic = false;
+
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            match entry.tag() {
+                gimli::DW_TAG_formal_parameter => parameters.push(self.type_of(unit, entry)?),
+                gimli::DW_TAG_unspecified_parameters => variadic = true,
+                _ => {}
+            }
+        }
+
+        Ok((parameters, variadic))
+    }
+
+    // Finds the definition of a named type. `struct`, `union`, `enum` and `class` in front of the name restrict the
+    // kind of type, paths of namespaces are matched like those of global variables.
+    fn find_type(&self, name: &str) -> Result<Option<TypeId>> {
+        let (tags, name) = match name.split_once(' ') {
+            Some(("struct", name)) => (&[gimli::DW_TAG_structure_type][..], name),
+            Some(("class", name)) => (&[gimli::DW_TAG_class_type][..], name),
+            Some(("union", name)) => (&[gimli::DW_TAG_union_type][..], name),
+            Some(("enum", name)) => (&[gimli::DW_TAG_enumeration_type][..], name),
+            _ => (NAMED_TYPE_TAGS, name),
+        };
+
+        for unit in self.units {
+            let mut tree = unit.entries_tree(None)?;
+            if let Some(offset) = find_named_type(self, unit, tree.root()?, tags, name, "")? {
+                return Ok(offset.to_debug_info_offset(&unit.header).map(|offset| {
+                    TypeId(TypeSource::Dwarf {
+                        module: self.module,
+                        offset,
+                    })
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+const NAMED_TYPE_TAGS: &[gimli::DwTag] = &[
+    gimli::DW_TAG_base_type,
+    gimli::DW_TAG_typedef,
+    gimli::DW_TAG_structure_type,
+    gimli::DW_TAG_class_type,
+    gimli::DW_TAG_union_type,
+    gimli::DW_TAG_enumeration_type,
+];
+
+fn find_named_type<'a>(
+    debug_info: &DebugInfo<'a>,
+    unit: &DwarfUnit,
+    node: EntriesTreeNode<'_, '_, SharedDwarfReader>,
+    tags: &[gimli::DwTag],
+    name: &str,
+    path: &str,
+) -> Result<Option<UnitOffset>> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if matches!(entry.tag(), gimli::DW_TAG_namespace | gimli::DW_TAG_module) {
+            let namespace = debug_info.name(unit, entry)?.unwrap_or_default();
+            let path = format!("{path}{namespace}::");
+            let found = find_named_type(debug_info, unit, child, tags, name, &path)?;
+            if found.is_some() {
+                return Ok(found);
+            }
+        } else if tags.contains(&entry.tag()) && !entry.has_attr(gimli::DW_AT_declaration) {
+            let Some(type_name) = debug_info.name(unit, entry)? else {
+                continue;
+            };
+            if type_name == name || name.strip_prefix(path) == Some(&type_name) {
+                return Ok(Some(entry.offset()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// The path of the namespaces containing an entry, e.g. `alloc::vec::` for `Vec`, or `None` if the entry is not
+// directly nested in namespaces. DWARF has no links from entries to their parents, so the namespaces are searched.
+fn namespace_path<'a>(
+    debug_info: &DebugInfo<'a>,
+    unit: &DwarfUnit,
+    node: EntriesTreeNode<'_, '_, SharedDwarfReader>,
+    offset: UnitOffset,
+    path: &str,
+) -> Result<Option<String>> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if entry.offset() == offset {
+            return Ok(Some(path.to_owned()));
+        }
+        if matches!(entry.tag(), gimli::DW_TAG_namespace | gimli::DW_TAG_module) {
+            let namespace = debug_info.name(unit, entry)?.unwrap_or_default();
+            let path = format!("{path}{namespace}::");
+            let found = namespace_path(debug_info, unit, child, offset, &path)?;
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// The offset of a member is usually a constant, older compilers emit a `DW_OP_plus_uconst` expression instead
+fn member_location(unit: &DwarfUnit, location: AttributeValue<SharedDwarfReader>) -> Option<u64> {
+    match location {
+        AttributeValue::Exprloc(expression) => {
+            let mut operations = expression.operations(unit.encoding());
+            match operations.next().ok()?? {
+                Operation::PlusConstant { value } => Some(value),
+                _ => None,
+            }
+        }
+        location => u64::try_from(constant_value(&location)?).ok(),
+    }
+}
+
+// Rust names the fields of tuples and tuple structs `__0`, `__1` and so on
+pub(crate) fn is_tuple_field(name: Option<&str>) -> bool {
+    name.and_then(|name| name.strip_prefix("__"))
+        .is_some_and(|index| index.parse::<usize>().is_ok())
+}
+
+fn is_c_language(language: Option<DwLang>) -> bool {
+    matches!(
+        language,
+        Some(
+            gimli::DW_LANG_C
+                | gimli::DW_LANG_C89
+                | gimli::DW_LANG_C99
+                | gimli::DW_LANG_C11
+                | gimli::DW_LANG_C17
+        )
+    )
+}
+
+impl DebugInfoCache<'_> {
+    pub(crate) fn parse_type(&self, ty: TypeId) -> Result<Type> {
+        let (module, offset) = match ty.0 {
+            TypeSource::Dwarf { module, offset } => (module, offset),
+            TypeSource::Synthetic(index) => {
+                return Ok(self.synthetic_types.borrow()[index].clone());
+            }
+        };
+        let debug_info = self
+            .get(module)
+            .ok_or_else(|| Error::NoDebugInformation(self.module(module).path.clone()))?;
+
+        debug_info.parse_type(offset)
+    }
+
+    /// Finds a type by its name in the debug information of all modules, starting with the executable.
+    pub(crate) fn find_type(&self, name: &str) -> Result<Option<TypeId>> {
+        for module in 0..self.len() {
+            let Some(debug_info) = self.get(module) else {
+                continue;
+            };
+            if let Some(ty) = debug_info.find_type(name)? {
+                return Ok(Some(ty));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Makes up a type that is not described by the debug information.
+    pub(crate) fn add_type(&self, ty: Type) -> TypeId {
+        let mut synthetic_types = self.synthetic_types.borrow_mut();
+        synthetic_types.push(ty);
+
+        TypeId(TypeSource::Synthetic(synthetic_types.len() - 1))
+    }
+
+    // The language of the compilation unit defining a type, which decides how the type is named
+    fn language(&self, ty: TypeId) -> Option<DwLang> {
+        let TypeSource::Dwarf { module, offset } = ty.0 else {
+            return None;
+        };
+        let debug_info = self.get(module)?;
+        let (unit, _) = debug_info.unit_at(offset)?;
+        debug_info.language(unit)
+    }
+
+    /// Follows typedefs and qualifiers to the type they refer to.
+    pub(crate) fn strip_typedefs(&self, mut ty: Option<TypeId>) -> Result<(Option<TypeId>, Type)> {
+        for _ in 0..MAX_TYPE_DEPTH {
+            let Some(id) = ty else {
+                return Ok((None, Type::Void));
+            };
+
+            match self.parse_type(id)? {
+                Type::Typedef { target, .. } | Type::Qualified { target, .. } => ty = target,
+                parsed => return Ok((Some(id), parsed)),
+            }
+        }
+
+        Ok((ty, Type::Unsupported("too deeply nested type".to_owned())))
+    }
+
+    /// The size of a value of the type in bytes, if it is known.
+    pub(crate) fn type_size(&self, ty: Option<TypeId>) -> Result<Option<u64>> {
+        self.type_size_at_depth(ty, 0)
+    }
+
+    // Malformed debug information can contain arrays of themselves, so the element types are only followed so deep
+    fn type_size_at_depth(&self, ty: Option<TypeId>, depth: usize) -> Result<Option<u64>> {
+        if depth > MAX_TYPE_DEPTH {
+            return Ok(None);
+        }
+
+        let size = match self.strip_typedefs(ty)?.1 {
+            Type::Base { size, .. }
+            | Type::Pointer { size, .. }
+            | Type::Composite { size, .. }
+            | Type::Enum { size, .. } => Some(size),
+            Type::Array {
+                element,
+                dimensions,
+            } => {
+                let count = dimensions
+                    .iter()
+                    .try_fold(1u64, |count, dimension| count.checked_mul((*dimension)?));
+                match (count, self.type_size_at_depth(element, depth + 1)?) {
+                    (Some(count), Some(size)) => count.checked_mul(size),
+                    _ => None,
+                }
+            }
+            Type::Void
+            | Type::Typedef { .. }
+            | Type::Qualified { .. }
+            | Type::Function { .. }
+            | Type::Unsupported(_) => None,
+        };
+
+        Ok(size)
+    }
+
+    /// The name of a type prefixed with the path of the namespaces defining it, e.g. `alloc::vec::Vec<i32>` instead
+    /// of `Vec<i32>`.
+    pub(crate) fn qualified_type_name(&self, ty: Option<TypeId>) -> Result<String> {
+        let name = self.type_name(ty);
+        let Some(id @ TypeId(TypeSource::Dwarf { module, offset })) = ty else {
+            return Ok(name);
+        };
+        if !matches!(
+            self.parse_type(id)?,
+            Type::Composite { name: Some(_), .. } | Type::Enum { name: Some(_), .. }
+        ) {
+            return Ok(name);
+        }
+        let Some(debug_info) = self.get(module) else {
+            return Ok(name);
+        };
+        let Some((unit, offset)) = debug_info.unit_at(offset) else {
+            return Ok(name);
+        };
+
+        let mut tree = unit.entries_tree(None)?;
+        let path = namespace_path(debug_info, unit, tree.root()?, offset, "")?;
+        Ok(path.unwrap_or_default() + &name)
+    }
+
+    /// The name of a type as it would be written in its source language.
+    pub(crate) fn type_name(&self, ty: Option<TypeId>) -> String {
+        self.declaration(ty, String::new(), false, 0)
+    }
+
+    // Declares `declarator` with a type in C syntax, e.g. `char *argv[2]`. Without a declarator this is the name of
+    // the type. When expanding, the definition of the struct, union or enum the declaration is based on is included.
+    pub(crate) fn declaration(
+        &self,
+        ty: Option<TypeId>,
+        declarator: String,
+        expand: bool,
+        depth: usize,
+    ) -> String {
+        let declare = |base: &str| {
+            if declarator.is_empty() {
+                base.to_owned()
+            } else {
+                format!("{base} {declarator}")
+            }
+        };
+
+        let Some(id) = ty else {
+            return declare("void");
+        };
+        if depth > MAX_TYPE_DEPTH {
+            return declare("...");
+        }
+        let parsed = match self.parse_type(id) {
+            Ok(parsed) => parsed,
+            Err(_) => return declare("<unknown type>"),
+        };
+        let language = self.language(id);
+
+        match parsed {
+            Type::Void => declare("void"),
+            Type::Base { name, .. } | Type::Typedef { name, .. } => declare(&name),
+            Type::Pointer {
+                name: Some(name), ..
+            } => declare(&name),
+            Type::Pointer { kind, pointee, .. } => {
+                let operator = match kind {
+                    PointerKind::Pointer => "*",
+                    PointerKind::Reference => "&",
+                    PointerKind::RvalueReference => "&&",
+                };
+                let mut declarator = format!("{operator}{declarator}");
+                if let Ok((_, Type::Array { .. } | Type::Function { .. })) =
+                    self.strip_qualifiers(pointee)
+                {
+                    declarator = format!("({declarator})");
+                }
+                self.declaration(pointee, declarator, expand, depth + 1)
+            }
+            Type::Array {
+                element,
+                dimensions,
+            } if language == Some(gimli::DW_LANG_Rust) => {
+                let mut name = self.declaration(element, String::new(), false, depth + 1);
+                for dimension in dimensions.iter().rev() {
+                    name = match dimension {
+                        Some(count) => format!("[{name}; {count}]"),
+                        None => format!("[{name}]"),
+                    };
+                }
+                declare(&name)
+            }
+            Type::Array {
+                element,
+                dimensions,
+            } => {
+                let mut declarator = declarator.clone();
+                for dimension in &dimensions {
+                    match dimension {
+                        Some(count) => declarator += &format!("[{count}]"),
+                        None => declarator += "[]",
+                    }
+                }
+                self.declaration(element, declarator, expand, depth + 1)
+            }
+            Type::Composite { .. } | Type::Enum { .. } if expand => {
+                declare(&self.definition(id, &parsed, language, depth))
+            }
+            Type::Composite { kind, name, .. } => match (name, is_c_language(language)) {
+                (Some(name), true) => declare(&format!("{} {name}", kind.keyword())),
+                (Some(name), false) => declare(&name),
+                (None, _) => declare(&format!("{} {{...}}", kind.keyword())),
+            },
+            Type::Enum { name, .. } => match (name, is_c_language(language)) {
+                (Some(name), true) => declare(&format!("enum {name}")),
+                (Some(name), false) => declare(&name),
+                (None, _) => declare("enum {...}"),
+            },
+            Type::Qualified { qualifier, target } => {
+                match self.strip_qualifiers(target) {
+                    // The qualifier applies to the pointer itself, e.g. `char *const`
+                    Ok((_, Type::Pointer { name: None, .. })) => self.declaration(
+                        target,
+                        format!("{} {declarator}", qualifier.keyword())
+                            .trim_end()
+                            .to_owned(),
+                        expand,
+                        depth + 1,
+                    ),
+                    _ => format!(
+                        "{} {}",
+                        qualifier.keyword(),
+                        self.declaration(target, declarator, expand, depth + 1)
+                    ),
+                }
+            }
+            Type::Function {
+                return_type,
+                parameters,
+                variadic,
+            } => {
+                let mut parameters: Vec<_> = parameters
+                    .iter()
+                    .map(|parameter| self.declaration(*parameter, String::new(), false, depth + 1))
+                    .collect();
+                if variadic {
+                    parameters.push("...".to_owned());
+                } else if parameters.is_empty() && is_c_language(language) {
+                    parameters.push("void".to_owned());
+                }
+
+                let declarator = format!("{declarator}({})", parameters.join(", "));
+                self.declaration(return_type, declarator, expand, depth + 1)
+            }
+            Type::Unsupported(name) => declare(&format!("<{name}>")),
+        }
+    }
+
+    // The fields of a Rust enum variant in the syntax of its definition, `(u32, u32)` for tuple variants and
+    // `{r: f64}` for struct variants
+    fn variant_fields(&self, ty: Option<TypeId>) -> String {
+        let Ok((_, Type::Composite { members, .. })) = self.strip_typedefs(ty) else {
+            return String::new();
+        };
+        if members.is_empty() {
+            return String::new();
+        }
+
+        if members
+            .iter()
+            .all(|member| is_tuple_field(member.name.as_deref()))
+        {
+            let fields: Vec<_> = members
+                .iter()
+                .map(|member| self.type_name(member.ty))
+                .collect();
+            format!("({})", fields.join(", "))
+        } else {
+            let fields: Vec<_> = members
+                .iter()
+                .map(|member| {
+                    let member_name = member.name.clone().unwrap_or_default();
+                    format!("{member_name}: {}", self.type_name(member.ty))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+
+    // Like `strip_typedefs`, but stops at typedefs
+    fn strip_qualifiers(&self, mut ty: Option<TypeId>) -> Result<(Option<TypeId>, Type)> {
+        for _ in 0..MAX_TYPE_DEPTH {
+            let Some(id) = ty else {
+                return Ok((None, Type::Void));
+            };
+
+            match self.parse_type(id)? {
+                Type::Qualified { target, .. } => ty = target,
+                parsed => return Ok((Some(id), parsed)),
+            }
+        }
+
+        Ok((ty, Type::Unsupported("too deeply nested type".to_owned())))
+    }
+
+    // The definition of a struct, union or enum with all its members
+    fn definition(
+        &self,
+        id: TypeId,
+        parsed: &Type,
+        language: Option<DwLang>,
+        depth: usize,
+    ) -> String {
+        let rust = language == Some(gimli::DW_LANG_Rust);
+        let name = self.declaration(Some(id), String::new(), false, depth + 1);
+
+        match parsed {
+            Type::Composite {
+                variant_part: Some(variant_part),
+                ..
+            } if rust => {
+                let mut definition = format!("enum {name} {{\n");
+                for member in variant_part
+                    .variants
+                    .iter()
+                    .flat_map(|variant| &variant.members)
+                {
+                    let member_name = member.name.clone().unwrap_or_default();
+                    definition +=
+                        &format!("    {member_name}{},\n", self.variant_fields(member.ty));
+                }
+
+                definition + "}"
+            }
+            Type::Composite { kind, members, .. } => {
+                // Anonymous and C types already carry the keyword in their name
+                let mut header = if is_c_language(language) || name.contains("{...}") {
+                    name.replace(" {...}", "")
+                } else {
+                    format!("{} {name}", kind.keyword())
+                };
+                let bases: Vec<_> = members
+                    .iter()
+                    .filter(|member| member.is_base)
+                    .map(|member| format!("public {}", self.type_name(member.ty)))
+                    .collect();
+                if !bases.is_empty() && !rust {
+                    header += &format!(" : {}", bases.join(", "));
+                }
+
+                let mut definition = header + " {\n";
+                for member in members.iter().filter(|member| !member.is_base || rust) {
+                    let member_name = member.name.clone().unwrap_or_default();
+                    let line = if rust {
+                        format!("{member_name}: {},", self.type_name(member.ty))
+                    } else {
+                        let mut line = self.declaration(member.ty, member_name, false, depth + 1);
+                        if let Some(bit_field) = member.bit_field {
+                            line += &format!(" : {}", bit_field.bit_size);
+                        }
+                        line + ";"
+                    };
+                    definition += &format!("    {line}\n");
+                }
+
+                definition + "}"
+            }
+            Type::Enum { enumerators, .. } => {
+                let header = if is_c_language(language) || name.contains("{...}") {
+                    name.replace(" {...}", "")
+                } else {
+                    format!("enum {name}")
+                };
+
+                // Values are only shown where they differ from the implicit ones
+                let mut expected = 0;
+                let enumerators: Vec<_> = enumerators
+                    .iter()
+                    .map(|enumerator| {
+                        let declaration = if enumerator.value == expected {
+                            enumerator.name.clone()
+                        } else {
+                            format!("{} = {}", enumerator.name, enumerator.value)
+                        };
+                        expected = enumerator.value + 1;
+                        declaration
+                    })
+                    .collect();
+
+                format!("{header} {{{}}}", enumerators.join(", "))
+            }
+            _ => name,
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use elf::{ElfBytes, endian::AnyEndian};
+use gimli::{
+    BaseAddresses, CfaRule, DebugFrame, EhFrame, EhFrameHdr, EndianSlice, Evaluation,
+    EvaluationResult, Expression, FrameDescriptionEntry, Location, Register, RegisterRule,
+    RunTimeEndian, UnwindContext, UnwindSection, Value, X86_64,
+};
+use log::*;
+
+use crate::{Debugger, backtrace::FrameRegisters, dwarf::DwarfReader, module::Module};
+
+const ADDRESS_SIZE: u8 = 8;
+
+// Registers a callee has to preserve according to the System V ABI. Without a rule they keep their value.
+const CALLEE_SAVED_REGISTERS: [Register; 6] = [
+    X86_64::RBX,
+    X86_64::RBP,
+    X86_64::R12,
+    X86_64::R13,
+    X86_64::R14,
+    X86_64::R15,
+];
+
+// The call frame information sections of an ELF file
+struct CallFrameInformation<'a> {
+    bases: BaseAddresses,
+    eh_frame: Option<EhFrame<DwarfReader<'a>>>,
+    eh_frame_hdr: Option<&'a [u8]>,
+    debug_frame: Option<DebugFrame<DwarfReader<'a>>>,
+    endian: RunTimeEndian,
+}
+
+impl<'a> CallFrameInformation<'a> {
+    fn from_elf_data(data: &'a [u8]) -> Option<Self> {
+        let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data).ok()?;
+        let endian = match elf_bytes.ehdr.endianness {
+            AnyEndian::Little => RunTimeEndian::Little,
+            AnyEndian::Big => RunTimeEndian::Big,
+        };
+
+        // Returns the link-time address and the data of an uncompressed section
+        let section = |name: &str| {
+            let section_header = elf_bytes.section_header_by_name(name).ok()??;
+            match elf_bytes.section_data(&section_header).ok()? {
+                (data, None) => Some((section_header.sh_addr, data)),
+                (_, Some(_)) => {
+                    warn!("Compressed section {name} is not supported");
+                    None
+                }
+            }
+        };
+
+        let mut bases = BaseAddresses::default();
+        if let Some((address, _)) = section(".text") {
+            bases = bases.set_text(address);
+        }
+        if let Some((address, _)) = section(".got") {
+            bases = bases.set_got(address);
+        }
+
+        let eh_frame = section(".eh_frame").map(|(address, data)| {
+            bases = bases.clone().set_eh_frame(address);
+            let mut eh_frame = EhFrame::new(data, endian);
+            eh_frame.set_address_size(ADDRESS_SIZE);
+            eh_frame
+        });
+        let eh_frame_hdr = section(".eh_frame_hdr").map(|(address, data)| {
+            bases = bases.clone().set_eh_frame_hdr(address);
+            data
+        });
+        let debug_frame = section(".debug_frame").map(|(_, data)| {
+            let mut debug_frame = DebugFrame::new(data, endian);
+            debug_frame.set_address_size(ADDRESS_SIZE);
+            debug_frame
+        });
+
+        Some(Self {
+            bases,
+            eh_frame,
+            eh_frame_hdr,
+            debug_frame,
+            endian,
+        })
+    }
+}
+
+impl Debugger {
+    // Recovers the registers of the caller of a frame from the call frame information of the module containing the
+    // address, preferring `.eh_frame` over `.debug_frame`. Returns the registers together with the canonical frame
+    // address, or `None` if no CFI covers the address.
+    pub(crate) fn unwind_with_cfi(
+        &self,
+        address: u64,
+        registers: &FrameRegisters,
+    ) -> Option<(FrameRegisters, u64)> {
+        let module = self.modules().find(|module| module.contains(address))?;
+        let link_address = address.wrapping_sub(module.load_bias);
+
+        module_data(module).find_map(|data| {
+            let cfi = CallFrameInformation::from_elf_data(data)?;
+
+            if let Some(eh_frame) = &cfi.eh_frame {
+                // The sorted table of `.eh_frame_hdr` avoids scanning all entries of `.eh_frame`
+                let eh_frame_hdr = cfi.eh_frame_hdr.and_then(|eh_frame_hdr| {
+                    EhFrameHdr::new(eh_frame_hdr, cfi.endian)
+                        .parse(&cfi.bases, ADDRESS_SIZE)
+                        .ok()
+                });
+                let fde = match eh_frame_hdr
+                    .as_ref()
+                    .and_then(|eh_frame_hdr| eh_frame_hdr.table())
+                {
+                    Some(table) => table.fde_for_address(
+                        eh_frame,
+                        &cfi.bases,
+                        link_address,
+                        EhFrame::cie_from_offset,
+                    ),
+                    None => {
+                        eh_frame.fde_for_address(&cfi.bases, link_address, EhFrame::cie_from_offset)
+                    }
+                };
+
+                if let Some(unwound) =
+                    self.unwind_with_fde(eh_frame, &cfi.bases, fde, link_address, registers)
+                {
+                    return Some(unwound);
+                }
+            }
+
+            if let Some(debug_frame) = &cfi.debug_frame {
+                let fde = debug_frame.fde_for_address(
+                    &cfi.bases,
+                    link_address,
+                    DebugFrame::cie_from_offset,
+                );
+
+                return self.unwind_with_fde(debug_frame, &cfi.bases, fde, link_address, registers);
+            }
+
+            None
+        })
+    }
+
+    fn unwind_with_fde<'a, S: UnwindSection<DwarfReader<'a>>>(
+        &self,
+        section: &S,
+        bases: &BaseAddresses,
+        fde: gimli::Result<FrameDescriptionEntry<DwarfReader<'a>>>,
+        link_address: u64,
+        registers: &FrameRegisters,
+    ) -> Option<(FrameRegisters, u64)> {
+        let fde = match fde {
+            Ok(fde) => fde,
+            Err(gimli::Error::NoUnwindInfoForAddress) => return None,
+            Err(err) => {
+                debug!("Failed to find the FDE for 0x{link_address:x}: {err}");
+                return None;
+            }
+        };
+
+        let mut context = UnwindContext::new();
+        let row = match fde.unwind_info_for_address(section, bases, &mut context, link_address) {
+            Ok(row) => row,
+            Err(err) => {
+                debug!("Failed to evaluate the CFI for 0x{link_address:x}: {err}");
+                return None;
+            }
+        };
+        let encoding = fde.cie().encoding();
+
+        let cfa = match row.cfa() {
+            CfaRule::RegisterAndOffset { register, offset } => {
+                registers.get(register.0)?.wrapping_add_signed(*offset)
+            }
+            CfaRule::Expression(expression) => {
+                let expression = expression.get(section).ok()?;
+                self.evaluate_cfi_expression(expression, encoding, registers, None)?
+            }
+        };
+
+        let mut caller_registers = FrameRegisters::default();
+        for register in CALLEE_SAVED_REGISTERS {
+            caller_registers.set(register.0, registers.get(register.0));
+        }
+        caller_registers.set(X86_64::RSP.0, Some(cfa));
+
+        for (register, rule) in row.registers() {
+            let value = match rule {
+                RegisterRule::Undefined => None,
+                RegisterRule::SameValue => registers.get(register.0),
+                RegisterRule::Offset(offset) => {
+                    self.read_u64(cfa.wrapping_add_signed(*offset)).ok()
+                }
+                RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add_signed(*offset)),
+                RegisterRule::Register(other) => registers.get(other.0),
+                RegisterRule::Expression(expression) => {
+                    let expression = expression.get(section).ok()?;
+                    self.evaluate_cfi_expression(expression, encoding, registers, Some(cfa))
+                        .and_then(|address| self.read_u64(address).ok())
+                }
+                RegisterRule::ValExpression(expression) => {
+                    let expression = expression.get(section).ok()?;
+                    self.evaluate_cfi_expression(expression, encoding, registers, Some(cfa))
+                }
+                RegisterRule::Constant(value) => Some(*value),
+                RegisterRule::Architectural => None,
+            };
+            caller_registers.set(register.0, value);
+        }
+
+        Some((caller_registers, cfa))
+    }
+
+    // Evaluates a DWARF expression of a CFA or register rule. Register rules start with the CFA on the stack.
+    fn evaluate_cfi_expression(
+        &self,
+        expression: Expression<DwarfReader<'_>>,
+        encoding: gimli::Encoding,
+        registers: &FrameRegisters,
+        cfa: Option<u64>,
+    ) -> Option<u64> {
+        let mut evaluation: Evaluation<EndianSlice<'_, RunTimeEndian>> =
+            expression.evaluation(encoding);
+        if let Some(cfa) = cfa {
+            evaluation.set_initial_value(cfa);
+        }
+
+        let mut result = evaluation.evaluate().ok()?;
+        loop {
+            result = match result {
+                EvaluationResult::Complete => break,
+                EvaluationResult::RequiresMemory { address, size, .. } if size <= 8 => {
+                    let data = self.read_memory(address, size as usize).ok()?;
+                    let mut bytes = [0; 8];
+                    bytes[..data.len()].copy_from_slice(&data);
+                    evaluation
+                        .resume_with_memory(Value::Generic(u64::from_le_bytes(bytes)))
+                        .ok()?
+                }
+                EvaluationResult::RequiresRegister { register, .. } => evaluation
+                    .resume_with_register(Value::Generic(registers.get(register.0)?))
+                    .ok()?,
+                other => {
+                    debug!("Unsupported requirement in CFI expression: {other:?}");
+                    return None;
+                }
+            };
+        }
+
+        match evaluation.result().first()?.location {
+            Location::Address { address } => Some(address),
+            Location::Value { value } => value.to_u64(u64::MAX).ok(),
+            _ => None,
+        }
+    }
+}
+
+// The module itself and its separate debug file, which may carry the `.debug_frame` section
+fn module_data(module: &Module) -> impl Iterator<Item = &[u8]> {
+    std::iter::once(&*module.data).chain(
+        module
+            .debug_file
+            .as_ref()
+            .map(|debug_file| &*debug_file.data),
+    )
+}
@@ -0,0 +1,699 @@
+use std::fmt::Write;
+
+use crate::{
+    Result,
+    pretty_printer::{PrettyChildren, PrettyValue},
+    types::{BaseEncoding, Member, PointerKind, Type, TypeId},
+    variables::Inspector,
+};
+
+// Like GDB, only the beginning of long arrays and strings is shown
+pub(crate) const MAX_ELEMENTS: usize = 200;
+pub(crate) const MAX_STRING_LENGTH: usize = 200;
+// Runs of identical elements at least this long are collapsed
+const MIN_REPEATS: usize = 10;
+// Guards against cyclic type references in malformed debug information
+const MAX_VALUE_DEPTH: usize = 32;
+
+/// Where the contents of a value are found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueLocation {
+    /// The value is stored in the memory of the tracee.
+    Address(u64),
+    /// The value is not stored in memory, e.g. a variable kept in a register or a constant.
+    Data(Vec<u8>),
+    /// The compiler did not keep the value around at the current pc.
+    OptimizedOut,
+}
+
+/// A typed value of the tracee. A type of `None` stands for `void`.
+#[derive(Debug, Clone)]
+pub struct Value {
+    pub ty: Option<TypeId>,
+    pub location: ValueLocation,
+}
+
+impl Value {
+    pub fn address(&self) -> Option<u64> {
+        match self.location {
+            ValueLocation::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// The part of the value at an offset with another type, e.g. a member of a struct.
+    pub fn at_offset(&self, offset: u64, ty: Option<TypeId>) -> Value {
+        let location = match &self.location {
+            ValueLocation::Address(address) => ValueLocation::Address(address.wrapping_add(offset)),
+            ValueLocation::Data(data) => {
+                ValueLocation::Data(data.get(offset as usize..).unwrap_or_default().to_vec())
+            }
+            ValueLocation::OptimizedOut => ValueLocation::OptimizedOut,
+        };
+
+        Value { ty, location }
+    }
+}
+
+impl Inspector<'_> {
+    /// Reads the first `size` bytes of a value.
+    pub fn read_value(&self, value: &Value, size: u64) -> Result<Option<Vec<u8>>> {
+        match &value.location {
+            ValueLocation::Address(address) => {
+                self.debugger.read_memory(*address, size as usize).map(Some)
+            }
+            ValueLocation::Data(data) => {
+                let mut data = data.clone();
+                data.resize(size as usize, 0);
+                Ok(Some(data))
+            }
+            ValueLocation::OptimizedOut => Ok(None),
+        }
+    }
+
+    // Extracts the value of a bit field member from the struct containing it, sign extended if its type is signed
+    pub(crate) fn read_bit_field(&self, value: &Value, member: &Member) -> Result<i128> {
+        let Some(bit_field) = member.bit_field else {
+            return Ok(0);
+        };
+
+        let first_byte = bit_field.bit_offset / 8;
+        let last_byte = (bit_field.bit_offset + bit_field.bit_size).div_ceil(8);
+        let data = self
+            .read_value(&value.at_offset(first_byte, None), last_byte - first_byte)?
+            .unwrap_or_default();
+        let bits = (integer(&data, false) as u128 >> (bit_field.bit_offset % 8))
+            & mask(bit_field.bit_size);
+        let signed = matches!(
+            self.debug_info.strip_typedefs(member.ty)?.1,
+            Type::Base { encoding, .. } if encoding.is_signed()
+        );
+
+        if signed {
+            Ok(sign_extend(bits, bit_field.bit_size))
+        } else {
+            Ok(bits as i128)
+        }
+    }
+
+    /// Formats a value like `print` of GDB, e.g. `{x = 1, y = 2}` for a struct. Pointers are prefixed with their type.
+    /// Values with a pretty printer are shown in the form it gives, e.g. `Some(5)`, see [`Debugger::add_pretty_printer`].
+    ///
+    /// [`Debugger::add_pretty_printer`]: crate::Debugger::add_pretty_printer
+    pub fn format_value(&self, value: &Value) -> String {
+        let mut formatted = String::new();
+        self.write_value(&mut formatted, value, 0);
+
+        formatted
+    }
+
+    fn write_value(&self, formatted: &mut String, value: &Value, depth: usize) {
+        if let Err(err) = self.try_write_value(formatted, value, depth) {
+            let _ = write!(formatted, "<error: {err}>");
+        }
+    }
+
+    fn try_write_value(&self, formatted: &mut String, value: &Value, depth: usize) -> Result<()> {
+        if value.location == ValueLocation::OptimizedOut {
+            *formatted += "<optimized out>";
+            return Ok(());
+        }
+        if depth > MAX_VALUE_DEPTH {
+            *formatted += "...";
+            return Ok(());
+        }
+
+        let (ty, parsed) = self.debug_info.strip_typedefs(value.ty)?;
+        let stripped = Value {
+            ty,
+            location: value.location.clone(),
+        };
+        if let Some(pretty) = self.pretty_print(&stripped)? {
+            self.write_pretty(formatted, pretty, depth);
+            return Ok(());
+        }
+
+        match parsed {
+            Type::Void => *formatted += "void",
+            Type::Base { encoding, size, .. } => {
+                let data = self.read_value(value, size)?.unwrap_or_default();
+                *formatted += &format_base(&data, encoding);
+            }
+            Type::Enum {
+                size,
+                underlying,
+                enumerators,
+                ..
+            } => {
+                let data = self.read_value(value, size)?.unwrap_or_default();
+                let signed = match self.debug_info.strip_typedefs(underlying)?.1 {
+                    Type::Base { encoding, .. } => encoding.is_signed(),
+                    _ => enumerators.iter().any(|enumerator| enumerator.value < 0),
+                };
+                let number = integer(&data, signed);
+                let mask = mask(size * 8);
+
+                match enumerators
+                    .iter()
+                    .find(|enumerator| enumerator.value as u128 & mask == number as u128 & mask)
+                {
+                    Some(enumerator) => *formatted += &enumerator.name,
+                    None => *formatted += &number.to_string(),
+                }
+            }
+            Type::Pointer {
+                kind,
+                pointee,
+                size,
+                ..
+            } => {
+                if depth == 0 {
+                    let _ = write!(formatted, "({}) ", self.type_name(value.ty));
+                }
+                let data = self.read_value(value, size)?.unwrap_or_default();
+                let address = integer(&data, false) as u64;
+                self.write_pointer(formatted, kind, address, pointee, depth)?;
+            }
+            Type::Array {
+                element,
+                dimensions,
+            } => self.write_array(formatted, value, element, &dimensions, depth)?,
+            Type::Composite { members, .. } => {
+                *formatted += "{";
+                for (index, member) in members.iter().enumerate() {
+                    if index > 0 {
+                        *formatted += ", ";
+                    }
+                    match (&member.name, member.is_base) {
+                        (_, true) => {
+                            let _ = write!(formatted, "<{}> = ", self.type_name(member.ty));
+                        }
+                        (Some(name), false) => {
+                            let _ = write!(formatted, "{name} = ");
+                        }
+                        (None, false) => {}
+                    }
+
+                    match member.bit_field {
+                        Some(_) => {
+                            *formatted += &self.read_bit_field(value, member)?.to_string();
+                        }
+                        None => self.write_value(
+                            formatted,
+                            &value.at_offset(member.offset, member.ty),
+                            depth + 1,
+                        ),
+                    }
+                }
+                *formatted += "}";
+            }
+            Type::Function { .. } => {
+                let address = value.address().unwrap_or_default();
+                let _ = write!(formatted, "{{{}}} ", self.type_name(ty));
+                self.write_code_address(formatted, address);
+            }
+            Type::Typedef { .. } | Type::Qualified { .. } | Type::Unsupported(_) => {
+                let _ = write!(formatted, "<{}>", self.type_name(ty));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_pointer(
+        &self,
+        formatted: &mut String,
+        kind: PointerKind,
+        address: u64,
+        pointee: Option<TypeId>,
+        depth: usize,
+    ) -> Result<()> {
+        let pointee_type = self.debug_info.strip_typedefs(pointee)?.1;
+
+        match kind {
+            PointerKind::Pointer => match pointee_type {
+                // Functions are named by their symbol
+                Type::Function { .. } => self.write_code_address(formatted, address),
+                Type::Base {
+                    encoding, size: 1, ..
+                } if encoding.is_char() && address != 0 => {
+                    let _ = write!(formatted, "0x{address:x} ");
+                    match self.debugger.read_c_string(address, MAX_STRING_LENGTH) {
+                        Ok(string) => *formatted += &quote_string(string.as_bytes()),
+                        Err(_) => {
+                            let _ = write!(
+                                formatted,
+                                "<error: Cannot access memory at address 0x{address:x}>"
+                            );
+                        }
+                    }
+                }
+                _ => {
+                    let _ = write!(formatted, "0x{address:x}");
+                }
+            },
+            // References are shown with the value they refer to
+            PointerKind::Reference | PointerKind::RvalueReference => {
+                let _ = write!(formatted, "@0x{address:x}: ");
+                let referenced = Value {
+                    ty: pointee,
+                    location: ValueLocation::Address(address),
+                };
+                self.write_value(formatted, &referenced, depth + 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_code_address(&self, formatted: &mut String, address: u64) {
+        let _ = write!(formatted, "0x{address:x}");
+        if let Some(symbol) = self.debugger.symbolize(address) {
+            let _ = write!(formatted, " <{symbol}>");
+        }
+    }
+
+    fn write_array(
+        &self,
+        formatted: &mut String,
+        value: &Value,
+        element: Option<TypeId>,
+        dimensions: &[Option<u64>],
+        depth: usize,
+    ) -> Result<()> {
+        let Some((count, inner_dimensions)) = dimensions.split_first() else {
+            return Ok(());
+        };
+        let Some(count) = *count else {
+            *formatted += "{...}";
+            return Ok(());
+        };
+
+        let element_size = self.debug_info.type_size(element)?.unwrap_or(0);
+        let stride = inner_dimensions
+            .iter()
+            .map(|dimension| dimension.unwrap_or(0))
+            .product::<u64>()
+            * element_size;
+
+        // Arrays of characters are shown as strings
+        if inner_dimensions.is_empty()
+            && let Type::Base {
+                encoding, size: 1, ..
+            } = self.debug_info.strip_typedefs(element)?.1
+            && encoding.is_char()
+        {
+            let length = count.min(MAX_STRING_LENGTH as u64);
+            let data = self.read_value(value, length)?.unwrap_or_default();
+            let end = data
+                .iter()
+                .rposition(|byte| *byte != 0)
+                .map_or(0, |last| last + 1);
+            *formatted += &quote_string(&data[..end]);
+            if count > length {
+                *formatted += "...";
+            }
+            return Ok(());
+        }
+
+        let elements: Vec<String> = (0..count.min(MAX_ELEMENTS as u64))
+            .map(|index| {
+                let mut formatted = String::new();
+                let element_value = value.at_offset(index * stride, element);
+                if inner_dimensions.is_empty() {
+                    self.write_value(&mut formatted, &element_value, depth + 1);
+                } else if let Err(err) = self.write_array(
+                    &mut formatted,
+                    &element_value,
+                    element,
+                    inner_dimensions,
+                    depth + 1,
+                ) {
+                    let _ = write!(formatted, "<error: {err}>");
+                }
+                formatted
+            })
+            .collect();
+
+        write_elements(formatted, &elements, count > MAX_ELEMENTS as u64);
+
+        Ok(())
+    }
+
+    fn write_pretty(&self, formatted: &mut String, pretty: PrettyValue, depth: usize) {
+        let format = |value: &Value| {
+            let mut formatted = String::new();
+            self.write_value(&mut formatted, value, depth + 1);
+            formatted
+        };
+        let separator = if pretty.summary.is_empty() { "" } else { " = " };
+
+        *formatted += &pretty.summary;
+        match pretty.children {
+            PrettyChildren::None => {}
+            PrettyChildren::Elements(elements) => {
+                *formatted += separator;
+                // Printers registered by users may return more children than are shown of arrays
+                let truncated = pretty.truncated || elements.len() > MAX_ELEMENTS;
+                let elements: Vec<_> = elements.iter().take(MAX_ELEMENTS).map(format).collect();
+                write_elements(formatted, &elements, truncated);
+            }
+            PrettyChildren::Entries(entries) => {
+                *formatted += separator;
+                let truncated = pretty.truncated || entries.len() > MAX_ELEMENTS;
+                let entries: Vec<_> = entries
+                    .iter()
+                    .take(MAX_ELEMENTS)
+                    .map(|(key, value)| format!("[{}] = {}", format(key), format(value)))
+                    .collect();
+                write_elements(formatted, &entries, truncated);
+            }
+            PrettyChildren::Fields(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{name} = {}", format(value)))
+                    .collect();
+                let _ = write!(formatted, " {{{}}}", fields.join(", "));
+            }
+            PrettyChildren::Tuple(fields) => {
+                let fields: Vec<_> = fields.iter().map(format).collect();
+                let _ = write!(formatted, "({})", fields.join(", "));
+            }
+            PrettyChildren::Value(value) => {
+                *formatted += separator;
+                *formatted += &format(&value);
+            }
+        }
+    }
+}
+
+// Writes formatted elements in braces, collapsing runs of identical elements
+fn write_elements(formatted: &mut String, elements: &[String], truncated: bool) {
+    *formatted += "{";
+    let mut index = 0;
+    while index < elements.len() {
+        if index > 0 {
+            *formatted += ", ";
+        }
+        let repeats = elements[index..]
+            .iter()
+            .take_while(|element| **element == elements[index])
+            .count();
+        if repeats >= MIN_REPEATS {
+            let _ = write!(formatted, "{} <repeats {repeats} times>", elements[index]);
+            index += repeats;
+        } else {
+            *formatted += &elements[index];
+            index += 1;
+        }
+    }
+    if truncated {
+        *formatted += "...";
+    }
+    *formatted += "}";
+}
+
+fn format_base(data: &[u8], encoding: BaseEncoding) -> String {
+    match encoding {
+        BaseEncoding::Boolean => data.iter().any(|byte| *byte != 0).to_string(),
+        BaseEncoding::Float => match data.len() {
+            4 => f32::from_le_bytes(data.try_into().expect("length to be 4")).to_string(),
+            8 => f64::from_le_bytes(data.try_into().expect("length to be 8")).to_string(),
+            10 | 16 => x87_extended_to_f64(data).to_string(),
+            _ => hex_bytes(data),
+        },
+        BaseEncoding::SignedChar | BaseEncoding::UnsignedChar => {
+            let number = integer(data, encoding.is_signed());
+            let byte = data.first().copied().unwrap_or_default();
+            format!("{number} {}", quote_char(byte))
+        }
+        BaseEncoding::Utf => {
+            let code_point = integer(data, false) as u32;
+            match char::from_u32(code_point) {
+                Some(character) => format!("{code_point} {:?}", character),
+                None => code_point.to_string(),
+            }
+        }
+        BaseEncoding::Signed | BaseEncoding::Unsigned if data.len() <= 16 => {
+            integer(data, encoding.is_signed()).to_string()
+        }
+        _ => hex_bytes(data),
+    }
+}
+
+// Reads a little-endian integer of up to 16 bytes
+pub(crate) fn integer(data: &[u8], signed: bool) -> i128 {
+    let length = data.len().min(16);
+    let mut bytes = [0; 16];
+    bytes[..length].copy_from_slice(&data[..length]);
+    let value = u128::from_le_bytes(bytes);
+
+    if signed {
+        sign_extend(value, length as u64 * 8)
+    } else {
+        value as i128
+    }
+}
+
+pub(crate) fn sign_extend(value: u128, bits: u64) -> i128 {
+    if bits == 0 || bits >= 128 {
+        return value as i128;
+    }
+    let shift = 128 - bits as u32;
+    ((value << shift) as i128) >> shift
+}
+
+pub(crate) fn mask(bits: u64) -> u128 {
+    match bits {
+        128.. => u128::MAX,
+        bits => (1 << bits) - 1,
+    }
+}
+
+// Converts the 80-bit extended precision format of x87 `long double`s, which are padded to 16 bytes
+pub(crate) fn x87_extended_to_f64(data: &[u8]) -> f64 {
+    let mut bytes = [0; 10];
+    let length = data.len().min(10);
+    bytes[..length].copy_from_slice(&data[..length]);
+
+    let mantissa = u64::from_le_bytes(bytes[..8].try_into().expect("length to be 8"));
+    let sign_exponent = u16::from_le_bytes([bytes[8], bytes[9]]);
+    let sign = if sign_exponent & 0x8000 != 0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let exponent = i32::from(sign_exponent & 0x7fff);
+
+    match exponent {
+        0 if mantissa == 0 => sign * 0.0,
+        0x7fff if mantissa << 1 == 0 => sign * f64::INFINITY,
+        0x7fff => f64::NAN,
+        // The integer bit is explicit, so the mantissa is a fixed point number with 63 fraction bits
+        exponent => sign * (mantissa as f64) * 2f64.powi(exponent - 16383 - 63),
+    }
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    let hex: String = data
+        .iter()
+        .rev()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("0x{hex}")
+}
+
+fn escape_byte(byte: u8, quote: u8) -> String {
+    match byte {
+        b'\n' => "\\n".to_owned(),
+        b'\t' => "\\t".to_owned(),
+        b'\r' => "\\r".to_owned(),
+        b'\\' => "\\\\".to_owned(),
+        byte if byte == quote => format!("\\{}", byte as char),
+        0x20..=0x7e => (byte as char).to_string(),
+        byte => format!("\\{byte:03o}"),
+    }
+}
+
+fn quote_char(byte: u8) -> String {
+    format!("'{}'", escape_byte(byte, b'\''))
+}
+
+fn quote_string(bytes: &[u8]) -> String {
+    // Valid UTF-8 is kept as it is, other bytes are escaped
+    let mut quoted = String::from("\"");
+    for chunk in bytes.utf8_chunks() {
+        for character in chunk.valid().chars() {
+            if character.is_ascii() {
+                quoted += &escape_byte(character as u8, b'"');
+            } else {
+                quoted.push(character);
+            }
+        }
+        for byte in chunk.invalid() {
+            quoted += &escape_byte(*byte, b'"');
+        }
+    }
+    quoted + "\""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_extend_uses_the_top_bit() {
+        assert_eq!(sign_extend(0xff, 8), -1);
+        assert_eq!(sign_extend(0x7f, 8), 127);
+        assert_eq!(sign_extend(0x8000, 16), -32768);
+        assert_eq!(sign_extend(0xffff_fffe, 32), -2);
+        assert_eq!(sign_extend(0x1_ffff_fffe, 32), -2);
+        assert_eq!(sign_extend(0b101, 3), -3);
+        assert_eq!(sign_extend(u128::MAX, 128), -1);
+        assert_eq!(sign_extend(0xff, 0), 0xff);
+    }
+
+    #[test]
+    fn mask_covers_the_lowest_bits() {
+        assert_eq!(mask(0), 0);
+        assert_eq!(mask(1), 1);
+        assert_eq!(mask(8), 0xff);
+        assert_eq!(mask(64), u64::MAX as u128);
+        assert_eq!(mask(127), u128::MAX >> 1);
+        assert_eq!(mask(128), u128::MAX);
+        assert_eq!(mask(200), u128::MAX);
+    }
+
+    #[test]
+    fn integer_reads_little_endian() {
+        assert_eq!(integer(&[0x34, 0x12], false), 0x1234);
+        assert_eq!(integer(&[0xfe, 0xff], true), -2);
+        assert_eq!(integer(&[0xfe, 0xff], false), 0xfffe);
+        assert_eq!(integer(&[], true), 0);
+        // Only the first 16 bytes are used
+        assert_eq!(integer(&[1; 20], false), i128::from_le_bytes([1; 16]));
+    }
+
+    fn x87(mantissa: u64, sign_exponent: u16) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&mantissa.to_le_bytes());
+        bytes[8..10].copy_from_slice(&sign_exponent.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn x87_extended_to_f64_converts_normal_numbers() {
+        assert_eq!(x87_extended_to_f64(&x87(1 << 63, 0x3fff)), 1.0);
+        assert_eq!(
+            x87_extended_to_f64(&x87(0xa000_0000_0000_0000, 0xc000)),
+            -2.5
+        );
+        assert_eq!(
+            x87_extended_to_f64(&x87(0xc000_0000_0000_0000, 0x3ffe)),
+            0.75
+        );
+        // Only the first 10 bytes are used, the remaining 6 are padding
+        let mut padded = x87(1 << 63, 0x4000);
+        padded[10..].fill(0xff);
+        assert_eq!(x87_extended_to_f64(&padded), 2.0);
+        assert_eq!(x87_extended_to_f64(&padded[..10]), 2.0);
+    }
+
+    #[test]
+    fn x87_extended_to_f64_converts_special_values() {
+        let zero = x87_extended_to_f64(&x87(0, 0));
+        assert_eq!(zero, 0.0);
+        assert!(zero.is_sign_positive());
+        assert!(x87_extended_to_f64(&x87(0, 0x8000)).is_sign_negative());
+        assert_eq!(x87_extended_to_f64(&x87(1 << 63, 0x7fff)), f64::INFINITY);
+        assert_eq!(
+            x87_extended_to_f64(&x87(1 << 63, 0xffff)),
+            f64::NEG_INFINITY
+        );
+        assert!(x87_extended_to_f64(&x87(0xc000_0000_0000_0000, 0x7fff)).is_nan());
+        // Too small for an f64
+        assert_eq!(x87_extended_to_f64(&x87(1 << 63, 1)), 0.0);
+    }
+
+    #[test]
+    fn format_base_by_encoding() {
+        assert_eq!(format_base(&[1], BaseEncoding::Boolean), "true");
+        assert_eq!(format_base(&[0], BaseEncoding::Boolean), "false");
+        assert_eq!(
+            format_base(&[0xff, 0xff, 0xff, 0xff], BaseEncoding::Signed),
+            "-1"
+        );
+        assert_eq!(
+            format_base(&[0xff, 0xff, 0xff, 0xff], BaseEncoding::Unsigned),
+            "4294967295"
+        );
+        assert_eq!(
+            format_base(&1.5f32.to_le_bytes(), BaseEncoding::Float),
+            "1.5"
+        );
+        assert_eq!(
+            format_base(&(-0.25f64).to_le_bytes(), BaseEncoding::Float),
+            "-0.25"
+        );
+        assert_eq!(format_base(&[0x41], BaseEncoding::SignedChar), "65 'A'");
+        assert_eq!(
+            format_base(&[0xe9], BaseEncoding::SignedChar),
+            "-23 '\\351'"
+        );
+        assert_eq!(
+            format_base(&[0xe9], BaseEncoding::UnsignedChar),
+            "233 '\\351'"
+        );
+        assert_eq!(format_base(b"'", BaseEncoding::UnsignedChar), "39 '\\''");
+        assert_eq!(format_base(&[0xe9, 0, 0, 0], BaseEncoding::Utf), "233 'é'");
+        assert_eq!(
+            format_base(&[0x34, 0x12, 0, 0, 0, 0], BaseEncoding::Float),
+            "0x000000001234"
+        );
+    }
+
+    #[test]
+    fn escape_byte_escapes_quotes_and_control_characters() {
+        assert_eq!(escape_byte(b'a', b'"'), "a");
+        assert_eq!(escape_byte(b'"', b'"'), "\\\"");
+        assert_eq!(escape_byte(b'"', b'\''), "\"");
+        assert_eq!(escape_byte(b'\n', b'"'), "\\n");
+        assert_eq!(escape_byte(b'\\', b'"'), "\\\\");
+        assert_eq!(escape_byte(0, b'"'), "\\000");
+        assert_eq!(escape_byte(0x7f, b'"'), "\\177");
+    }
+
+    fn elements(elements: &[&str], truncated: bool) -> String {
+        let elements: Vec<String> = elements.iter().map(|element| element.to_string()).collect();
+        let mut formatted = String::new();
+        write_elements(&mut formatted, &elements, truncated);
+        formatted
+    }
+
+    #[test]
+    fn write_elements_collapses_repeats() {
+        assert_eq!(elements(&[], false), "{}");
+        assert_eq!(elements(&["1", "2", "3"], false), "{1, 2, 3}");
+        assert_eq!(elements(&["0"; 10], false), "{0 <repeats 10 times>}");
+        // Shorter runs are written out
+        assert_eq!(
+            elements(&["0"; 9], false),
+            format!("{{{}}}", ["0"; 9].join(", "))
+        );
+
+        let mut mixed = vec!["1"];
+        mixed.extend(["0"; 12]);
+        mixed.extend(["2", "2"]);
+        mixed.extend(["3"; 10]);
+        assert_eq!(
+            elements(&mixed, false),
+            "{1, 0 <repeats 12 times>, 2, 2, 3 <repeats 10 times>}"
+        );
+    }
+
+    #[test]
+    fn write_elements_marks_truncation() {
+        assert_eq!(elements(&["1", "2"], true), "{1, 2...}");
+        assert_eq!(elements(&["0"; 10], true), "{0 <repeats 10 times>...}");
+    }
+}
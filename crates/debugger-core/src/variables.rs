@@ -0,0 +1,388 @@
+use std::cmp::Reverse;
+
+use gimli::{EntriesTreeNode, UnitOffset};
+use log::*;
+
+use crate::{
+    Debugger, Error, Result,
+    backtrace::StackFrame,
+    debug_info::{DebugInfo, DebugInfoCache, DwarfEntry, DwarfUnit},
+    dwarf::SharedDwarfReader,
+    location::LocationContext,
+    types::{Type, TypeId},
+    value::{Value, ValueLocation},
+};
+
+/// Where a variable is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    Argument,
+    Local,
+    Global,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub kind: VariableKind,
+    pub value: Value,
+}
+
+// A variable entry visible at the pc of a frame, with the nesting depth of the lexical block declaring it
+struct ScopeVariable {
+    offset: UnitOffset,
+    kind: VariableKind,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct Scope {
+    function: Option<UnitOffset>,
+    variables: Vec<ScopeVariable>,
+}
+
+/// Reads variables and types from the DWARF debug information, in the context of the selected frame.
+///
+/// The debug information of a module is read when it is first needed and kept for the lifetime of the inspector, so
+/// an inspector should be reused for related queries, but not kept across executions of the tracee.
+pub struct Inspector<'a> {
+    pub(crate) debugger: &'a Debugger,
+    pub(crate) debug_info: DebugInfoCache<'a>,
+    frame: Option<StackFrame>,
+}
+
+impl Debugger {
+    /// Creates an inspector for the variables of the selected frame and global variables.
+    pub fn inspector(&self) -> Inspector<'_> {
+        let frame = self
+            .selected_frame()
+            .inspect_err(|err| debug!("No frame to inspect: {err}"))
+            .ok();
+
+        Inspector {
+            debugger: self,
+            debug_info: DebugInfoCache::new(self),
+            frame,
+        }
+    }
+}
+
+impl Inspector<'_> {
+    pub fn frame(&self) -> Option<&StackFrame> {
+        self.frame.as_ref()
+    }
+
+    /// The parameters of the function of the selected frame.
+    pub fn arguments(&self) -> Result<Vec<Variable>> {
+        let mut variables = self.frame_variables()?;
+        variables.retain(|variable| variable.kind == VariableKind::Argument);
+
+        Ok(variables)
+    }
+
+    /// The local variables visible at the pc of the selected frame, starting with the innermost lexical block.
+    pub fn locals(&self) -> Result<Vec<Variable>> {
+        let mut variables = self.frame_variables()?;
+        variables.retain(|variable| variable.kind == VariableKind::Local);
+
+        Ok(variables)
+    }
+
+    /// Looks up a variable by its name. Local variables and arguments of the selected frame shadow global variables,
+    /// which can also be named by their path, e.g. `module::VARIABLE`.
+    pub fn find_variable(&self, name: &str) -> Result<Variable> {
+        if let Some(variable) = self
+            .frame_variables()?
+            .into_iter()
+            .find(|variable| variable.name == name)
+        {
+            return Ok(variable);
+        }
+
+        self.find_global_variable(name)?
+            .ok_or_else(|| Error::UnknownVariable(name.to_owned()))
+    }
+
+    pub fn parse_type(&self, ty: TypeId) -> Result<Type> {
+        self.debug_info.parse_type(ty)
+    }
+
+    /// The name of a type as it would be written in its source language, `None` is `void`.
+    pub fn type_name(&self, ty: Option<TypeId>) -> String {
+        self.debug_info.type_name(ty)
+    }
+
+    /// Describes a type like `ptype` of GDB: typedefs are resolved and structs, unions and enums are shown with their
+    /// members.
+    pub fn describe_type(&self, ty: Option<TypeId>) -> Result<String> {
+        let (ty, _) = self.debug_info.strip_typedefs(ty)?;
+
+        Ok(self.debug_info.declaration(ty, String::new(), true, 0))
+    }
+
+    pub fn type_size(&self, ty: Option<TypeId>) -> Result<Option<u64>> {
+        self.debug_info.type_size(ty)
+    }
+
+    // The arguments and local variables of the selected frame, arguments first
+    fn frame_variables(&self) -> Result<Vec<Variable>> {
+        let Some(frame) = &self.frame else {
+            return Ok(Vec::new());
+        };
+        let address = frame.lookup_address();
+        let Some(module_index) = self.debug_info.module_containing(address) else {
+            return Ok(Vec::new());
+        };
+        let Some(debug_info) = self.debug_info.get(module_index) else {
+            return Ok(Vec::new());
+        };
+        let module = self.debug_info.module(module_index);
+        let link_address = address.wrapping_sub(module.load_bias);
+        let Some(unit) = debug_info.unit_for_address(link_address)? else {
+            return Ok(Vec::new());
+        };
+
+        // The variables of an inlined function are declared in its inlined subroutine entry
+        let inline_levels = self
+            .debugger
+            .inlined_subroutines_at(address)
+            .len()
+            .saturating_sub(frame.inline_depth);
+
+        let mut scope = Scope::default();
+        let mut tree = unit.entries_tree(None)?;
+        collect_scope(
+            debug_info,
+            unit,
+            tree.root()?,
+            link_address,
+            None,
+            inline_levels,
+            &mut scope,
+        )?;
+        let Some(function) = scope.function else {
+            return Ok(Vec::new());
+        };
+
+        let mut context = LocationContext {
+            debugger: self.debugger,
+            module,
+            debug_info,
+            unit,
+            registers: Some(frame.registers),
+            cfa: frame.cfa,
+            frame_base: None,
+            address: link_address,
+        };
+        let function = unit.entry(function)?;
+        if let Some(frame_base) = function.attr_value(gimli::DW_AT_frame_base) {
+            context.frame_base = match context.location(frame_base)? {
+                ValueLocation::Address(address) => Some(address),
+                ValueLocation::Data(data) => {
+                    let mut bytes = [0; 8];
+                    let length = data.len().min(8);
+                    bytes[..length].copy_from_slice(&data[..length]);
+                    Some(u64::from_le_bytes(bytes))
+                }
+                ValueLocation::OptimizedOut => None,
+            };
+        }
+
+        scope
+            .variables
+            .sort_by_key(|variable| Reverse(variable.depth));
+        scope
+            .variables
+            .iter()
+            .filter_map(|variable| {
+                let entry = match unit.entry(variable.offset) {
+                    Ok(entry) => entry,
+                    Err(err) => return Some(Err(err.into())),
+                };
+                self.variable(&context, &entry, variable.kind).transpose()
+            })
+            .collect()
+    }
+
+    // Global variables are searched in all modules, starting with the executable
+    fn find_global_variable(&self, name: &str) -> Result<Option<Variable>> {
+        for module_index in 0..self.debug_info.len() {
+            let Some(debug_info) = self.debug_info.get(module_index) else {
+                continue;
+            };
+
+            for unit in debug_info.units {
+                let mut tree = unit.entries_tree(None)?;
+                let Some(offset) = find_global(debug_info, unit, tree.root()?, name, "")? else {
+                    continue;
+                };
+
+                let context = LocationContext {
+                    debugger: self.debugger,
+                    module: self.debug_info.module(module_index),
+                    debug_info,
+                    unit,
+                    registers: self.frame.map(|frame| frame.registers),
+                    cfa: self.frame.and_then(|frame| frame.cfa),
+                    frame_base: None,
+                    address: 0,
+                };
+                return self.variable(&context, &unit.entry(offset)?, VariableKind::Global);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn variable<'a>(
+        &self,
+        context: &LocationContext<'_, 'a>,
+        entry: &DwarfEntry,
+        kind: VariableKind,
+    ) -> Result<Option<Variable>> {
+        let Some(name) = context.debug_info.name(context.unit, entry)? else {
+            return Ok(None);
+        };
+        let ty = context.debug_info.type_of(context.unit, entry)?;
+        let size = self.debug_info.type_size(ty)?;
+
+        let location = context
+            .variable_location(entry, size)
+            .unwrap_or_else(|err| {
+                debug!("Failed to evaluate the location of {name}: {err}");
+                ValueLocation::OptimizedOut
+            });
+
+        Ok(Some(Variable {
+            name,
+            kind,
+            value: Value { ty, location },
+        }))
+    }
+}
+
+// Descends into the function containing the address and the lexical blocks containing it, collecting the variables
+// declared in them. `depth` is the nesting depth of lexical blocks inside of the function. Up to `inline_levels`
+// inlined subroutines containing the address are entered, each one replaces the variables of its caller. Returns
+// whether the function was found.
+fn collect_scope<'a>(
+    debug_info: &DebugInfo<'a>,
+    unit: &DwarfUnit,
+    node: EntriesTreeNode<'_, '_, SharedDwarfReader>,
+    address: u64,
+    depth: Option<usize>,
+    inline_levels: usize,
+    scope: &mut Scope,
+) -> Result<bool> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match (entry.tag(), depth) {
+            (gimli::DW_TAG_subprogram, None) | (gimli::DW_TAG_lexical_block, Some(_)) => {
+                if !debug_info.entry_contains(unit, entry, address)? {
+                    continue;
+                }
+                if entry.tag() == gimli::DW_TAG_subprogram {
+                    scope.function = Some(entry.offset());
+                }
+
+                let depth = depth.map_or(0, |depth| depth + 1);
+                collect_scope(
+                    debug_info,
+                    unit,
+                    child,
+                    address,
+                    Some(depth),
+                    inline_levels,
+                    scope,
+                )?;
+                return Ok(true);
+            }
+            (gimli::DW_TAG_inlined_subroutine, Some(depth)) if inline_levels > 0 => {
+                if !debug_info.entry_contains(unit, entry, address)? {
+                    continue;
+                }
+                scope.variables.clear();
+
+                collect_scope(
+                    debug_info,
+                    unit,
+                    child,
+                    address,
+                    Some(depth + 1),
+                    inline_levels - 1,
+                    scope,
+                )?;
+                return Ok(true);
+            }
+            // Functions can be nested in namespaces, and in types for methods
+            (
+                gimli::DW_TAG_namespace
+                | gimli::DW_TAG_module
+                | gimli::DW_TAG_structure_type
+                | gimli::DW_TAG_class_type
+                | gimli::DW_TAG_union_type
+                | gimli::DW_TAG_enumeration_type,
+                None,
+            ) => {
+                let found =
+                    collect_scope(debug_info, unit, child, address, None, inline_levels, scope)?;
+                if found {
+                    return Ok(true);
+                }
+            }
+            (gimli::DW_TAG_formal_parameter, Some(depth)) => scope.variables.push(ScopeVariable {
+                offset: entry.offset(),
+                kind: VariableKind::Argument,
+                depth,
+            }),
+            (gimli::DW_TAG_variable, Some(depth)) if !entry.has_attr(gimli::DW_AT_declaration) => {
+                scope.variables.push(ScopeVariable {
+                    offset: entry.offset(),
+                    kind: VariableKind::Local,
+                    depth,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    Ok(false)
+}
+
+// Searches the definition of a global variable by its name or its path of namespaces
+fn find_global<'a>(
+    debug_info: &DebugInfo<'a>,
+    unit: &DwarfUnit,
+    node: EntriesTreeNode<'_, '_, SharedDwarfReader>,
+    name: &str,
+    path: &str,
+) -> Result<Option<UnitOffset>> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match entry.tag() {
+            gimli::DW_TAG_namespace | gimli::DW_TAG_module => {
+                let namespace = debug_info.name(unit, entry)?.unwrap_or_default();
+                let path = format!("{path}{namespace}::");
+                if let Some(offset) = find_global(debug_info, unit, child, name, &path)? {
+                    return Ok(Some(offset));
+                }
+            }
+            gimli::DW_TAG_variable
+                if entry.has_attr(gimli::DW_AT_location)
+                    || entry.has_attr(gimli::DW_AT_const_value) =>
+            {
+                let Some(variable_name) = debug_info.name(unit, entry)? else {
+                    continue;
+                };
+                if variable_name == name || name.strip_prefix(path) == Some(&variable_name) {
+                    return Ok(Some(entry.offset()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
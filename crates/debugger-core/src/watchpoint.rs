@@ -28,6 +28,17 @@ pub enum WatchpointLength {
     EightBytes = 0b10,
 }
 
+impl WatchpointLength {
+    pub fn bytes(&self) -> u64 {
+        match self {
+            WatchpointLength::OneByte => 1,
+            WatchpointLength::TwoBytes => 2,
+            WatchpointLength::FourBytes => 4,
+            WatchpointLength::EightBytes => 8,
+        }
+    }
+}
+
 impl TryFrom<usize> for WatchpointLength {
     type Error = Error;
 
@@ -51,6 +62,25 @@ pub enum Watchpoint {
     },
 }
 
+impl Watchpoint {
+    // Enables a debug register in the value of DR7 and sets the condition and length of data watchpoints
+    pub(crate) fn debug_control(&self, mut debug_control: i64, debug_register: usize) -> i64 {
+        debug_control |= 1 << (debug_register * 2);
+
+        match *self {
+            Watchpoint::Execution => {}
+            Watchpoint::Data { condition, length } => {
+                // Every debug register has two condition bits followed by two length bits
+                debug_control &= !(0b1111 << (16 + (4 * debug_register)));
+                debug_control |= (condition as i64) << (16 + (4 * debug_register));
+                debug_control |= (length as i64) << (18 + (4 * debug_register));
+            }
+        }
+
+        debug_control
+    }
+}
+
 impl Debugger {
     fn get_b0_offset(&self) -> usize {
         std::mem::offset_of!(nix::libc::user, u_debugreg)
@@ -91,3 +121,46 @@ impl Debugger {
         self.get_debug_register(DebugRegisterOffsets::DebugStatus as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_control_sets_condition_and_length_bits() {
+        let watchpoint = Watchpoint::Data {
+            condition: WatchpointDataCondition::Write,
+            length: WatchpointLength::EightBytes,
+        };
+        // L0, R/W0 = 01 and LEN0 = 10
+        assert_eq!(watchpoint.debug_control(0, 0), 0b1001 << 16 | 0b1);
+
+        let watchpoint = Watchpoint::Data {
+            condition: WatchpointDataCondition::ReadWrite,
+            length: WatchpointLength::TwoBytes,
+        };
+        // L3, R/W3 = 11 and LEN3 = 01
+        assert_eq!(watchpoint.debug_control(0, 3), 0b0111 << 28 | 0b1 << 6);
+    }
+
+    #[test]
+    fn debug_control_keeps_other_debug_registers() {
+        let first = Watchpoint::Data {
+            condition: WatchpointDataCondition::Write,
+            length: WatchpointLength::FourBytes,
+        };
+        let second = Watchpoint::Data {
+            condition: WatchpointDataCondition::ReadWrite,
+            length: WatchpointLength::OneByte,
+        };
+
+        let debug_control = second.debug_control(first.debug_control(0, 0), 1);
+        assert_eq!(debug_control, 0b0011_1101 << 16 | 0b0101);
+
+        // Reusing a debug register replaces its previous condition and length
+        let debug_control = second.debug_control(debug_control, 0);
+        assert_eq!(debug_control, 0b0011_0011 << 16 | 0b0101);
+
+        assert_eq!(Watchpoint::Execution.debug_control(0, 2), 0b1 << 4);
+    }
+}